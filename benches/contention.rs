@@ -0,0 +1,81 @@
+//! Measures throughput when many threads create and release allocations and
+//! handles at once, which stresses the collector's shared counters and queue
+//! tails. Run with `cargo bench --bench contention`.
+
+use basedrop::{Collector, Handle, Owned};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Barrier};
+use std::time::{Duration, Instant};
+
+const ITERATIONS: usize = 200_000;
+
+fn run(threads: usize, shards: usize) -> Duration {
+    let mut collector = Collector::builder().shards(shards).build();
+    let handle = collector.handle();
+    let barrier = Arc::new(Barrier::new(threads + 1));
+    let done = Arc::new(AtomicBool::new(false));
+
+    let workers: Vec<_> = (0..threads)
+        .map(|_| {
+            let handle = handle.clone();
+            let barrier = barrier.clone();
+            std::thread::spawn(move || {
+                barrier.wait();
+                for i in 0..ITERATIONS {
+                    let clone: Handle = handle.clone();
+                    drop(Owned::new(&clone, i));
+                }
+            })
+        })
+        .collect();
+
+    barrier.wait();
+    let start = Instant::now();
+    let reaper = std::thread::spawn({
+        let done = done.clone();
+        move || {
+            while !done.load(Ordering::Relaxed) {
+                collector.collect();
+            }
+            collector.collect();
+            collector
+        }
+    });
+
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    let elapsed = start.elapsed();
+
+    done.store(true, Ordering::Relaxed);
+    let collector = reaper.join().unwrap();
+    drop(handle);
+    assert!(collector.try_cleanup().is_ok());
+
+    elapsed
+}
+
+fn main() {
+    let cores = std::thread::available_parallelism().map_or(4, |n| n.get());
+    let mut thread_counts = vec![1, cores / 2, cores];
+    thread_counts.retain(|&threads| threads > 0);
+    thread_counts.dedup();
+
+    for threads in thread_counts {
+        for &shards in &[1, threads] {
+            let elapsed = run(threads, shards);
+            let ops = (threads * ITERATIONS) as f64 / elapsed.as_secs_f64();
+            println!(
+                "{:>3} threads, {:>3} shards: {:>10.2?} ({:.1} M allocations/s)",
+                threads,
+                shards,
+                elapsed,
+                ops / 1e6,
+            );
+            if threads == 1 {
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use crate::Shared;
+
+use core::any::{Any, TypeId};
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ptr::NonNull;
+
+/// A type-erased [`Shared`] pointer, which can be converted back into a
+/// [`Shared<T>`] of the original type with [`downcast`].
+///
+/// This allows collector-managed values of different types to be passed
+/// through a single channel, e.g. a [`SharedCell<SharedAny>`] or a queue of
+/// messages. Cloning and dropping a `SharedAny` behave exactly like cloning
+/// and dropping the original [`Shared<T>`].
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, Shared, SharedAny};
+///
+/// let collector = Collector::new();
+/// let handle = collector.handle();
+///
+/// let messages = vec![
+///     SharedAny::new(Shared::new(&handle, 440.0f32)),
+///     SharedAny::new(Shared::new(&handle, String::from("reset"))),
+/// ];
+///
+/// for message in messages {
+///     match message.downcast::<f32>() {
+///         Ok(frequency) => assert_eq!(*frequency, 440.0),
+///         Err(message) => assert_eq!(message.downcast_ref::<String>().unwrap(), "reset"),
+///     }
+/// }
+/// ```
+///
+/// [`Shared`]: crate::Shared
+/// [`Shared<T>`]: crate::Shared
+/// [`downcast`]: crate::SharedAny::downcast
+/// [`SharedCell<SharedAny>`]: crate::SharedCell
+pub struct SharedAny {
+    node: NonNull<u8>,
+    type_id: TypeId,
+    clone: unsafe fn(NonNull<u8>),
+    drop: unsafe fn(NonNull<u8>),
+}
+
+// Only `Send + Sync` types can be erased.
+unsafe impl Send for SharedAny {}
+unsafe impl Sync for SharedAny {}
+
+/// Reconstructs the original `Shared<T>` from an erased pointer.
+unsafe fn shared<T>(node: NonNull<u8>) -> Shared<T> {
+    Shared {
+        node: node.cast(),
+        phantom: PhantomData,
+    }
+}
+
+unsafe fn clone_shared<T>(node: NonNull<u8>) {
+    let shared = ManuallyDrop::new(shared::<T>(node));
+    core::mem::forget(Shared::clone(&shared));
+}
+
+unsafe fn drop_shared<T>(node: NonNull<u8>) {
+    drop(shared::<T>(node));
+}
+
+impl SharedAny {
+    /// Erases the type of a [`Shared<T>`].
+    ///
+    /// [`Shared<T>`]: crate::Shared
+    pub fn new<T: Any + Send + Sync>(shared: Shared<T>) -> SharedAny {
+        let node = shared.node.cast();
+        core::mem::forget(shared);
+
+        SharedAny {
+            node,
+            type_id: TypeId::of::<T>(),
+            clone: clone_shared::<T>,
+            drop: drop_shared::<T>,
+        }
+    }
+
+    /// Returns `true` if the erased value is of type `T`.
+    pub fn is<T: Any>(&self) -> bool {
+        self.type_id == TypeId::of::<T>()
+    }
+
+    /// Gets a reference to the erased value if it is of type `T`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        if self.is::<T>() {
+            let shared = ManuallyDrop::new(unsafe { shared::<T>(self.node) });
+            Some(unsafe { &*Shared::as_ptr(&shared) })
+        } else {
+            None
+        }
+    }
+
+    /// Converts back into a [`Shared<T>`] if the erased value is of type `T`,
+    /// and otherwise returns the `SharedAny` unchanged.
+    ///
+    /// [`Shared<T>`]: crate::Shared
+    pub fn downcast<T: Any>(self) -> Result<Shared<T>, SharedAny> {
+        if self.is::<T>() {
+            let this = ManuallyDrop::new(self);
+            Ok(unsafe { shared::<T>(this.node) })
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl<T: Any + Send + Sync> From<Shared<T>> for SharedAny {
+    fn from(shared: Shared<T>) -> SharedAny {
+        SharedAny::new(shared)
+    }
+}
+
+impl Clone for SharedAny {
+    fn clone(&self) -> Self {
+        unsafe { (self.clone)(self.node) };
+
+        SharedAny {
+            node: self.node,
+            type_id: self.type_id,
+            clone: self.clone,
+            drop: self.drop,
+        }
+    }
+}
+
+impl fmt::Debug for SharedAny {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SharedAny").finish_non_exhaustive()
+    }
+}
+
+impl Drop for SharedAny {
+    fn drop(&mut self) {
+        unsafe { (self.drop)(self.node) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Collector, Shared, SharedAny};
+
+    #[test]
+    fn downcast() {
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        let any = SharedAny::from(Shared::new(&handle, 3u32));
+        let clone = any.clone();
+        assert!(any.is::<u32>());
+        assert!(any.downcast_ref::<i32>().is_none());
+
+        let any = any.downcast::<u64>().err().unwrap();
+        let three = any.downcast::<u32>().ok().unwrap();
+        assert_eq!(*three, 3);
+        assert_eq!(Shared::strong_count(&three), 2);
+
+        drop((three, clone, handle));
+        collector.collect();
+        assert!(collector.try_cleanup().is_ok());
+    }
+}
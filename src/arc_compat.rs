@@ -0,0 +1,96 @@
+use crate::Shared;
+
+use core::borrow::Borrow;
+use core::ops::Deref;
+
+extern crate alloc;
+use alloc::sync::Arc;
+
+/// An adapter for handing a [`Shared<T>`] to APIs which require an `Arc`.
+///
+/// An `Arc<ArcCompat<T>>` (see [`Shared::into_arc`]) dereferences to `T`, and
+/// when the last `Arc` is dropped, the contained [`Shared<T>`] is dropped as
+/// usual, adding its contents to the [`Collector`]'s drop queue if it was the
+/// last reference.
+///
+/// Note that only the contents are collected in a deferred manner: the `Arc`'s
+/// own allocation, which holds just the `Shared` pointer and the reference
+/// counts, is freed immediately by whichever thread drops the last `Arc`.
+/// APIs which require exactly `Arc<T>` rather than `Arc<U>` for some
+/// `U: Deref<Target = T>` cannot be served this way.
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, Shared};
+/// use std::sync::Arc;
+///
+/// fn takes_arc<T: std::ops::Deref<Target = i32>>(value: Arc<T>) -> i32 {
+///     **value
+/// }
+///
+/// let mut collector = Collector::new();
+/// let x = Shared::new(&collector.handle(), 3);
+///
+/// assert_eq!(takes_arc(Shared::into_arc(x)), 3);
+///
+/// collector.collect();
+/// assert_eq!(collector.alloc_count(), 0);
+/// ```
+///
+/// [`Shared<T>`]: crate::Shared
+/// [`Shared::into_arc`]: crate::Shared::into_arc
+/// [`Collector`]: crate::Collector
+#[derive(Clone)]
+pub struct ArcCompat<T>(Shared<T>);
+
+impl<T> ArcCompat<T> {
+    /// Wraps a [`Shared<T>`].
+    ///
+    /// [`Shared<T>`]: crate::Shared
+    pub fn new(shared: Shared<T>) -> ArcCompat<T> {
+        ArcCompat(shared)
+    }
+
+    /// Returns the wrapped [`Shared<T>`].
+    ///
+    /// [`Shared<T>`]: crate::Shared
+    pub fn into_inner(self) -> Shared<T> {
+        self.0
+    }
+}
+
+impl<T> Shared<T> {
+    /// Moves a `Shared<T>` into an `Arc<ArcCompat<T>>`. See [`ArcCompat`] for
+    /// details.
+    ///
+    /// [`ArcCompat`]: crate::ArcCompat
+    pub fn into_arc(this: Self) -> Arc<ArcCompat<T>> {
+        Arc::new(ArcCompat(this))
+    }
+}
+
+impl<T> Deref for ArcCompat<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> AsRef<T> for ArcCompat<T> {
+    fn as_ref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> Borrow<T> for ArcCompat<T> {
+    fn borrow(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> From<Shared<T>> for ArcCompat<T> {
+    fn from(shared: Shared<T>) -> ArcCompat<T> {
+        ArcCompat(shared)
+    }
+}
@@ -0,0 +1,71 @@
+use core::time::Duration;
+
+/// A monotonic time source, used by time-based functionality such as
+/// [`Collector::collect_until`] and [`Collector::collect_for`].
+///
+/// `Clock` is implemented for closures returning a [`Duration`], which makes
+/// it easy to use a hardware timer or cycle counter on embedded targets. With
+/// the `std` feature, [`StdClock`] provides an implementation based on
+/// `std::time::Instant`.
+///
+/// # Examples
+/// ```
+/// use basedrop::Clock;
+/// use core::time::Duration;
+///
+/// fn read_timer() -> u64 {
+///     // Read a hardware timer counting microseconds.
+///     # 0
+/// }
+///
+/// let clock = || Duration::from_micros(read_timer());
+/// let now = clock.now();
+/// ```
+///
+/// [`Collector::collect_until`]: crate::Collector::collect_until
+/// [`Collector::collect_for`]: crate::Collector::collect_for
+/// [`Duration`]: core::time::Duration
+/// [`StdClock`]: crate::StdClock
+pub trait Clock {
+    /// Returns the time elapsed since some fixed but arbitrary point. The
+    /// returned value must never decrease.
+    fn now(&self) -> Duration;
+}
+
+impl<F: Fn() -> Duration> Clock for F {
+    fn now(&self) -> Duration {
+        self()
+    }
+}
+
+/// A [`Clock`] based on `std::time::Instant`, measuring time since its
+/// construction. Only available with the `std` feature.
+///
+/// [`Clock`]: crate::Clock
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug)]
+pub struct StdClock {
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl StdClock {
+    /// Constructs a new `StdClock`.
+    pub fn new() -> StdClock {
+        StdClock { start: std::time::Instant::now() }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for StdClock {
+    fn default() -> StdClock {
+        StdClock::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
@@ -1,13 +1,109 @@
+use core::alloc::Layout;
 use core::mem::ManuallyDrop;
-use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use core::time::Duration;
+
+use crate::counter::{stripe, StripedCounter};
+use crate::padded::CachePadded;
+use crate::{Clock, CollectorStats, DropExecutor, DropJob, NodeAllocator, PassInfo, Profiler};
+#[cfg(feature = "latency-stats")]
+use crate::LatencyStats;
 
 extern crate alloc;
 use alloc::boxed::Box;
 
+#[cfg(feature = "diagnostics")]
+use crate::spin::SpinLock;
+#[cfg(feature = "thread-stats")]
+use crate::{ThreadRegistry, ThreadStats};
+#[cfg(feature = "track-allocations")]
+use crate::TypeStats;
+#[cfg(feature = "diagnostics")]
+use alloc::collections::BTreeMap;
+#[cfg(any(feature = "std", feature = "diagnostics", feature = "thread-stats"))]
+use alloc::vec::Vec;
+#[cfg(feature = "diagnostics")]
+use core::panic::Location;
+
 #[repr(C)]
-struct NodeHeader {
+pub(crate) struct NodeHeader {
     link: NodeLink,
-    drop: unsafe fn(*mut NodeHeader),
+    /// Drops the node's contents, returning whether the node was freed as
+    /// well. A node which isn't freed has been revived with
+    /// [`NodeHeader::revive`] and will be queued again later.
+    pub(crate) drop: unsafe fn(*mut NodeHeader) -> bool,
+    /// Releases the memory of a node constructed in place with
+    /// [`Node::init_in`]. Kept separately from `drop`, which may be replaced
+    /// over the node's lifetime, e.g. once its contents are moved out.
+    dealloc: Option<unsafe fn(*mut NodeHeader)>,
+    #[cfg(feature = "diagnostics")]
+    magic: usize,
+    #[cfg(feature = "byte-stats")]
+    size: usize,
+    // In nanoseconds on the reclamation clock.
+    #[cfg(feature = "latency-stats")]
+    queued_at: u64,
+}
+
+/// Magic values stored in each node's header when the `diagnostics` feature
+/// is enabled, recording whether the node is live, queued for dropping, or
+/// already freed.
+const LIVE: usize = 0xba5e_d409;
+const QUEUED: usize = 0xd409_9e0e;
+const FREED: usize = 0xdead_d409;
+
+#[cfg(feature = "diagnostics")]
+fn describe(magic: usize) -> &'static str {
+    match magic {
+        LIVE => "a live node",
+        QUEUED => "a node queued for dropping",
+        FREED => "a freed node",
+        _ => "something which is not a node",
+    }
+}
+
+impl NodeHeader {
+    /// Panics if `node` doesn't hold the magic value `expected`, turning a
+    /// garbage or stale pointer passed to the unsafe `Node` API into an
+    /// immediate panic rather than silent corruption. Does nothing unless the
+    /// `diagnostics` feature is enabled.
+    #[cfg(feature = "diagnostics")]
+    #[track_caller]
+    unsafe fn check(node: *const NodeHeader, expected: usize) {
+        let magic = (*node).magic;
+        if magic != expected {
+            panic!(
+                "basedrop: invalid node pointer {:p}: expected {}, found {}",
+                node,
+                describe(expected),
+                describe(magic),
+            );
+        }
+    }
+
+    #[cfg(not(feature = "diagnostics"))]
+    #[inline(always)]
+    unsafe fn check(_node: *const NodeHeader, _expected: usize) {}
+
+    /// Records that `node` has moved into a new state. Does nothing unless the
+    /// `diagnostics` feature is enabled.
+    #[cfg(feature = "diagnostics")]
+    unsafe fn mark(node: *mut NodeHeader, magic: usize) {
+        (*node).magic = magic;
+    }
+
+    #[cfg(not(feature = "diagnostics"))]
+    #[inline(always)]
+    unsafe fn mark(_node: *mut NodeHeader, _magic: usize) {}
+
+    /// Returns a node whose drop function is running to the live state, for
+    /// drop functions which leave the node allocated so that it can be queued
+    /// again. This must happen before anything else may queue the node.
+    pub(crate) unsafe fn revive(node: *mut NodeHeader) {
+        NodeHeader::mark(node, LIVE);
+    }
 }
 
 #[repr(C)]
@@ -27,14 +123,186 @@ union NodeLink {
 /// [`Owned`]: crate::Owned
 /// [`Shared`]: crate::Shared
 #[repr(C)]
-pub struct Node<T> {
+pub struct Node<T: ?Sized> {
     header: NodeHeader,
     /// The data stored in this allocation.
     pub data: T,
 }
 
-unsafe fn drop_node<T>(node: *mut NodeHeader) {
-    let _ = Box::from_raw(node as *mut Node<T>);
+/// Drops and frees a node which has been removed from the drop queue.
+pub(crate) unsafe fn drop_node<T>(node: *mut NodeHeader) -> bool {
+    core::ptr::drop_in_place(&mut (*(node as *mut Node<T>)).data);
+    free_node::<T>(node)
+}
+
+/// Frees a node which has been removed from the drop queue without dropping
+/// its contents.
+pub(crate) unsafe fn free_node<T>(node: *mut NodeHeader) -> bool {
+    let layout = Layout::new::<Node<T>>();
+    let type_name = core::any::type_name::<T>();
+    match (*node).dealloc {
+        Some(dealloc) => {
+            retire_raw(node, layout, type_name);
+            dealloc(node);
+            true
+        }
+        None => free_raw(node, layout, type_name),
+    }
+}
+
+/// Allocates a node with the given layout, which must start with a
+/// `NodeHeader`, and initializes its header. The rest of the node is left
+/// uninitialized.
+#[cfg_attr(feature = "track-allocations", track_caller)]
+pub(crate) unsafe fn alloc_raw(
+    handle: &Handle,
+    layout: Layout,
+    type_name: &'static str,
+    drop: unsafe fn(*mut NodeHeader) -> bool,
+) -> *mut NodeHeader {
+    match try_alloc_raw(handle, layout, type_name, drop) {
+        Ok(node) => node,
+        Err(AllocError) => alloc::alloc::handle_alloc_error(layout),
+    }
+}
+
+/// Like [`alloc_raw`], but returns an error if memory can't be allocated.
+/// Nothing is counted or reported to hooks in that case.
+#[cfg_attr(feature = "track-allocations", track_caller)]
+pub(crate) unsafe fn try_alloc_raw(
+    handle: &Handle,
+    layout: Layout,
+    type_name: &'static str,
+    drop: unsafe fn(*mut NodeHeader) -> bool,
+) -> Result<*mut NodeHeader, AllocError> {
+    let node = (*handle.collector).allocate(layout)?.as_ptr() as *mut NodeHeader;
+    init_raw(handle, node, layout, type_name, drop, None);
+    Ok(node)
+}
+
+/// Counts a node placed at `node`, reports it to the allocation hooks, and
+/// initializes its header.
+#[cfg_attr(feature = "track-allocations", track_caller)]
+unsafe fn init_raw(
+    handle: &Handle,
+    node: *mut NodeHeader,
+    layout: Layout,
+    type_name: &'static str,
+    drop: unsafe fn(*mut NodeHeader) -> bool,
+    dealloc: Option<unsafe fn(*mut NodeHeader)>,
+) {
+    let collector = handle.collector;
+
+    if (*collector).counters {
+        (*collector).allocs.increment(Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "thread-stats")]
+    (*collector).thread_stats.record_alloc();
+
+    if let Some(on_alloc) = &(*collector).on_alloc {
+        on_alloc(&AllocInfo {
+            type_name,
+            size: layout.size(),
+            align: layout.align(),
+        });
+    }
+
+    core::ptr::write(node, NodeHeader {
+        link: NodeLink { collector },
+        drop,
+        dealloc,
+        #[cfg(feature = "diagnostics")]
+        magic: LIVE,
+        #[cfg(feature = "byte-stats")]
+        size: layout.size(),
+        #[cfg(feature = "latency-stats")]
+        queued_at: 0,
+    });
+
+    #[cfg(feature = "byte-stats")]
+    if (*collector).counters {
+        (*collector).live_bytes.add(layout.size(), Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "track-allocations")]
+    (*collector).alloc_registry.lock().insert(node as usize, LiveAllocation {
+        type_name,
+        size: layout.size(),
+        location: Location::caller(),
+        tag: None,
+    });
+}
+
+/// Frees a node whose data is still uninitialized when dropped, e.g. because
+/// the code initializing it panicked.
+pub(crate) struct UninitGuard<T>(pub(crate) *mut Node<T>);
+
+impl<T> Drop for UninitGuard<T> {
+    fn drop(&mut self) {
+        unsafe { Node::queue_free(self.0) };
+    }
+}
+
+/// Frees a node allocated with the given layout and type name, which has
+/// been removed from the drop queue, without dropping its contents.
+pub(crate) unsafe fn free_raw(
+    node: *mut NodeHeader,
+    layout: Layout,
+    type_name: &'static str,
+) -> bool {
+    let collector = (*node).link.collector;
+    if (*collector).counters {
+        (*collector).freed_bytes.fetch_add(layout.size(), Ordering::Relaxed);
+    }
+    retire_raw(node, layout, type_name);
+    (*collector).deallocate(node as *mut u8, layout);
+    true
+}
+
+/// Marks a node which has been removed from the drop queue as freed, just
+/// before its memory is released, and reports it to the drop hook.
+unsafe fn retire_raw(node: *mut NodeHeader, layout: Layout, type_name: &'static str) {
+    let collector = (*node).link.collector;
+    // The registry entry must be removed before the node is freed, since its
+    // address may be reused immediately afterwards.
+    (*collector).untrack(node);
+    (*collector).record_freed(node);
+    NodeHeader::mark(node, FREED);
+
+    if let Some(on_drop) = &(*collector).on_drop {
+        on_drop(&AllocInfo {
+            type_name,
+            size: layout.size(),
+            align: layout.align(),
+        });
+    }
+}
+
+/// Releases the memory of a [`Node`] which was constructed in place with
+/// [`Node::init_in`], once the [`Collector`] has dropped its contents.
+///
+/// See [`Node::init_in`] for an example.
+///
+/// [`Node`]: crate::Node
+/// [`Node::init_in`]: crate::Node::init_in
+/// [`Collector`]: crate::Collector
+pub trait NodeDealloc<T> {
+    /// Releases the memory of `node`, whose contents have already been
+    /// dropped.
+    ///
+    /// # Safety
+    ///
+    /// This is only called by the [`Collector`], once for each `Node`
+    /// constructed with [`Node::init_in`].
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`Node::init_in`]: crate::Node::init_in
+    unsafe fn dealloc(node: *mut Node<T>);
+}
+
+unsafe fn dealloc_placed<T, D: NodeDealloc<T>>(node: *mut NodeHeader) {
+    D::dealloc(node as *mut Node<T>);
 }
 
 impl<T: Send + 'static> Node<T> {
@@ -51,32 +319,167 @@ impl<T: Send + 'static> Node<T> {
     /// ```
     ///
     /// [`queue_drop`]: crate::Node::queue_drop
+    #[cfg_attr(feature = "track-allocations", track_caller)]
     pub fn alloc(handle: &Handle, data: T) -> *mut Node<T> {
+        Node::alloc_with_drop(handle, data, drop_node::<T>)
+    }
+
+    /// Allocates a `Node` with the given data, returning an error instead of
+    /// aborting if memory can't be allocated. In that case, `data` is
+    /// dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Node};
+    ///
+    /// let collector = Collector::new();
+    /// let node = Node::try_alloc(&collector.handle(), 3).unwrap();
+    ///
+    /// unsafe {
+    ///     Node::queue_drop(node);
+    /// }
+    /// ```
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn try_alloc(handle: &Handle, data: T) -> Result<*mut Node<T>, AllocError> {
+        Node::try_alloc_with_drop(handle, data, drop_node::<T>)
+    }
+
+    /// Constructs a `Node` in memory provided by the caller, such as a static
+    /// buffer or a memory-mapped region, instead of allocating it. Once the
+    /// `Node` has been queued and its contents dropped by the [`Collector`],
+    /// `D::dealloc` is called to release the memory.
+    ///
+    /// The `Node` is otherwise treated like an allocated one: it is counted
+    /// by [`Collector::alloc_count`] until it has been collected, and can be
+    /// wrapped in an [`Owned`] with [`Owned::from_node`]. Its memory is
+    /// released through `D::dealloc` however it is collected, including after
+    /// its contents are moved out with [`Owned::into_inner`], but never if
+    /// it is leaked with [`Owned::leak`].
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for writes and suitably aligned for a `Node<T>`,
+    /// and must remain valid until `D::dealloc` is called for it.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Node, NodeDealloc, Owned};
+    /// use std::mem::MaybeUninit;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    ///
+    /// static mut BUFFER: MaybeUninit<Node<[f32; 256]>> = MaybeUninit::uninit();
+    /// static IN_USE: AtomicBool = AtomicBool::new(false);
+    ///
+    /// struct ReleaseBuffer;
+    ///
+    /// impl NodeDealloc<[f32; 256]> for ReleaseBuffer {
+    ///     unsafe fn dealloc(_node: *mut Node<[f32; 256]>) {
+    ///         IN_USE.store(false, Ordering::Release);
+    ///     }
+    /// }
+    ///
+    /// let mut collector = Collector::new();
+    ///
+    /// assert!(!IN_USE.swap(true, Ordering::Acquire));
+    /// let buffer = unsafe {
+    ///     let ptr = std::ptr::addr_of_mut!(BUFFER) as *mut Node<[f32; 256]>;
+    ///     let node = Node::init_in::<ReleaseBuffer>(ptr, &collector.handle(), [0.0; 256]);
+    ///     Owned::from_node(node)
+    /// };
+    ///
+    /// drop(buffer);
+    /// collector.collect();
+    /// assert!(!IN_USE.load(Ordering::Acquire));
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`Collector::alloc_count`]: crate::Collector::alloc_count
+    /// [`Owned`]: crate::Owned
+    /// [`Owned::from_node`]: crate::Owned::from_node
+    /// [`Owned::into_inner`]: crate::Owned::into_inner
+    /// [`Owned::leak`]: crate::Owned::leak
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub unsafe fn init_in<D: NodeDealloc<T>>(
+        ptr: *mut Node<T>,
+        handle: &Handle,
+        data: T,
+    ) -> *mut Node<T> {
+        let layout = Layout::new::<Node<T>>();
+        let type_name = core::any::type_name::<T>();
+        let dealloc = dealloc_placed::<T, D> as unsafe fn(*mut NodeHeader);
+        init_raw(handle, ptr as *mut NodeHeader, layout, type_name, drop_node::<T>, Some(dealloc));
+        core::ptr::addr_of_mut!((*ptr).data).write(data);
+        ptr
+    }
+
+    /// Allocates a `Node` which the [`Collector`] drops using the given
+    /// function rather than by simply dropping and freeing it.
+    ///
+    /// [`Collector`]: crate::Collector
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub(crate) fn alloc_with_drop(
+        handle: &Handle,
+        data: T,
+        drop: unsafe fn(*mut NodeHeader) -> bool,
+    ) -> *mut Node<T> {
+        match Node::try_alloc_with_drop(handle, data, drop) {
+            Ok(node) => node,
+            Err(AllocError) => alloc::alloc::handle_alloc_error(Layout::new::<Node<T>>()),
+        }
+    }
+
+    /// Allocates a `Node` with uninitialized data, which must be initialized
+    /// before the `Node` is queued with [`queue_drop`] (or freed with
+    /// [`queue_free`] instead).
+    ///
+    /// [`queue_drop`]: crate::Node::queue_drop
+    /// [`queue_free`]: crate::Node::queue_free
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub(crate) fn alloc_uninit(
+        handle: &Handle,
+        drop: unsafe fn(*mut NodeHeader) -> bool,
+    ) -> *mut Node<T> {
         unsafe {
-            (*handle.collector).allocs.fetch_add(1, Ordering::Relaxed);
+            let layout = Layout::new::<Node<T>>();
+            alloc_raw(handle, layout, core::any::type_name::<T>(), drop) as *mut Node<T>
         }
+    }
 
-        Box::into_raw(Box::new(Node {
-            header: NodeHeader {
-                link: NodeLink {
-                    collector: handle.collector,
-                },
-                drop: drop_node::<T>,
-            },
-            data,
-        }))
+    /// Like [`alloc_with_drop`], but returns an error if memory can't be
+    /// allocated.
+    ///
+    /// [`alloc_with_drop`]: crate::Node::alloc_with_drop
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub(crate) fn try_alloc_with_drop(
+        handle: &Handle,
+        data: T,
+        drop: unsafe fn(*mut NodeHeader) -> bool,
+    ) -> Result<*mut Node<T>, AllocError> {
+        unsafe {
+            let layout = Layout::new::<Node<T>>();
+            let node = try_alloc_raw(handle, layout, core::any::type_name::<T>(), drop)?
+                as *mut Node<T>;
+            core::ptr::write(&mut (*node).data, data);
+            Ok(node)
+        }
     }
 }
 
-impl<T> Node<T> {
+impl<T: ?Sized> Node<T> {
     /// Adds a `Node` to its associated [`Collector`]'s drop queue. The `Node`
     /// and its contained data may be dropped at a later time when
     /// [`Collector::collect`] or [`Collector::collect_one`] is called.
     ///
+    /// # Safety
+    ///
     /// The argument must point to a valid `Node` previously allocated with
     /// [`Node::alloc`]. `queue_drop` may only be called once for a given
     /// `Node`, and the `Node`'s data must not be accessed afterwards.
     ///
+    /// With the `diagnostics` feature enabled, passing a pointer which
+    /// doesn't refer to a live `Node` is detected on a best-effort basis and
+    /// results in a panic.
+    ///
     /// # Examples
     /// ```
     /// use basedrop::{Collector, Handle, Node};
@@ -94,15 +497,253 @@ impl<T> Node<T> {
     /// [`Collector::collect`]: crate::Collector::collect
     /// [`Collector::collect_one`]: crate::Collector::collect_one
     /// [`Node::alloc`]: crate::Node::alloc
+    #[cfg_attr(feature = "diagnostics", track_caller)]
     pub unsafe fn queue_drop(node: *mut Node<T>) {
+        NodeHeader::check(node as *mut NodeHeader, LIVE);
+        NodeHeader::mark(node as *mut NodeHeader, QUEUED);
+        let collector = (*node).header.link.collector;
+        #[cfg(feature = "thread-stats")]
+        (*collector).thread_stats.record_retire(1);
+        (*collector).count_queued(1);
+        (*collector).record_queued(node as *mut NodeHeader);
+        (*collector).stamp_queued(node as *mut NodeHeader);
+        push((*collector).normal_tail(), node as *mut NodeHeader);
+        (*collector).notify();
+    }
+
+    /// Adds a `Node` to its associated [`Collector`]'s drop queue, unless the
+    /// queue already holds as many allocations as the limit set with
+    /// [`CollectorBuilder::max_pending`]. In that case, the `Node` is left
+    /// untouched and [`QueueFull`] is returned, so the caller can hold on to
+    /// it and retry later, or fall back to dropping it some other way.
+    ///
+    /// Without a limit, this always succeeds and is equivalent to
+    /// [`queue_drop`].
+    ///
+    /// # Safety
+    ///
+    /// The same requirements apply as for [`queue_drop`], except that the
+    /// `Node` remains valid and may be passed to `try_queue_drop` again if an
+    /// error is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Node};
+    ///
+    /// let mut collector = Collector::builder().max_pending(1).build();
+    /// let handle = collector.handle();
+    /// let a = Node::alloc(&handle, 1);
+    /// let b = Node::alloc(&handle, 2);
+    ///
+    /// unsafe {
+    ///     assert!(Node::try_queue_drop(a).is_ok());
+    ///     assert!(Node::try_queue_drop(b).is_err());
+    ///
+    ///     collector.collect();
+    ///     assert!(Node::try_queue_drop(b).is_ok());
+    /// }
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`CollectorBuilder::max_pending`]: crate::CollectorBuilder::max_pending
+    /// [`QueueFull`]: crate::QueueFull
+    /// [`queue_drop`]: crate::Node::queue_drop
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub unsafe fn try_queue_drop(node: *mut Node<T>) -> Result<(), QueueFull> {
+        NodeHeader::check(node as *mut NodeHeader, LIVE);
         let collector = (*node).header.link.collector;
-        (*node).header.link.next = ManuallyDrop::new(AtomicPtr::new(core::ptr::null_mut()));
-        let tail = (*collector).tail.swap(node as *mut NodeHeader, Ordering::AcqRel);
-        (*tail).link.next.store(node as *mut NodeHeader, Ordering::Relaxed);
+        if let Some(max_pending) = (*collector).max_pending {
+            let pending = (*collector)
+                .pending
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |pending| {
+                    if pending < max_pending {
+                        Some(pending + 1)
+                    } else {
+                        None
+                    }
+                })
+                .map_err(|_| QueueFull)?;
+            (*collector).after_queued(pending, pending + 1);
+        } else {
+            (*collector).count_queued(1);
+        }
+        (*collector).record_queued(node as *mut NodeHeader);
+        (*collector).stamp_queued(node as *mut NodeHeader);
+
+        NodeHeader::mark(node as *mut NodeHeader, QUEUED);
+        #[cfg(feature = "thread-stats")]
+        (*collector).thread_stats.record_retire(1);
+        push((*collector).normal_tail(), node as *mut NodeHeader);
+        (*collector).notify();
+        Ok(())
+    }
+
+    /// Adds a `Node` to its associated [`Collector`]'s high-priority drop
+    /// queue. The [`Collector`] always drains the high-priority queue before
+    /// the normal one, which is useful for large allocations whose memory
+    /// should be reclaimed as soon as possible.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements apply as for [`queue_drop`].
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Handle, Node};
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    /// let node = Node::alloc(&handle, 3);
+    ///
+    /// unsafe {
+    ///     Node::queue_drop_urgent(node);
+    /// }
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`queue_drop`]: crate::Node::queue_drop
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub unsafe fn queue_drop_urgent(node: *mut Node<T>) {
+        NodeHeader::check(node as *mut NodeHeader, LIVE);
+        NodeHeader::mark(node as *mut NodeHeader, QUEUED);
+        let collector = (*node).header.link.collector;
+        #[cfg(feature = "thread-stats")]
+        (*collector).thread_stats.record_retire(1);
+        (*collector).count_queued(1);
+        (*collector).record_queued(node as *mut NodeHeader);
+        (*collector).stamp_queued(node as *mut NodeHeader);
+        push(&(*collector).tails[URGENT], node as *mut NodeHeader);
+        (*collector).notify();
+    }
+
+    /// Adds a sequence of `Node`s to their associated [`Collector`]'s drop
+    /// queue. Consecutive `Node`s belonging to the same [`Collector`] are
+    /// linked together first and then appended to the queue with a single
+    /// atomic operation, so that releasing many allocations at once costs
+    /// little more contention than releasing one.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements apply as for [`queue_drop`], for each `Node`.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Node};
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    /// let nodes: Vec<_> = (0..1000).map(|i| Node::alloc(&handle, i)).collect();
+    ///
+    /// unsafe {
+    ///     Node::queue_drop_list(nodes);
+    /// }
+    ///
+    /// collector.collect();
+    /// assert_eq!(collector.alloc_count(), 0);
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`queue_drop`]: crate::Node::queue_drop
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub unsafe fn queue_drop_list<I: IntoIterator<Item = *mut Node<T>>>(nodes: I) {
+        let mut batch = Batch::new();
+        for node in nodes {
+            batch.push(node as *mut NodeHeader);
+        }
+        batch.flush();
+    }
+
+    /// Adds a `Node` whose data has already been moved out to its associated
+    /// [`Collector`]'s drop queue, so that only its memory is freed.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements apply as for [`queue_drop`], and the `Node`'s
+    /// data must be treated as uninitialized.
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`queue_drop`]: crate::Node::queue_drop
+    pub(crate) unsafe fn queue_free(node: *mut Node<T>)
+    where
+        T: Sized,
+    {
+        (*node).header.drop = free_node::<T>;
+        Node::queue_drop(node);
+    }
+
+    /// Attaches a tag to a `Node`'s entry in the live allocation registry, so
+    /// that leaks can be attributed to the subsystem which made them. Does
+    /// nothing without the `track-allocations` feature.
+    #[cfg(feature = "track-allocations")]
+    pub(crate) unsafe fn set_tag(node: *mut Node<T>, tag: &'static str) {
+        let collector = (*node).header.link.collector;
+        let mut registry = (*collector).alloc_registry.lock();
+        if let Some(allocation) = registry.get_mut(&(node as *mut u8 as usize)) {
+            allocation.tag = Some(tag);
+        }
+    }
+
+    #[cfg(not(feature = "track-allocations"))]
+    #[inline(always)]
+    pub(crate) unsafe fn set_tag(_node: *mut Node<T>, _tag: &'static str) {}
+
+    /// Replaces the function used to drop a `Node`'s data, e.g. once data
+    /// which was allocated uninitialized has been initialized.
+    pub(crate) unsafe fn set_drop(node: *mut Node<T>, drop: unsafe fn(*mut NodeHeader) -> bool) {
+        (*node).header.drop = drop;
+    }
+
+    /// Removes a `Node` from its associated [`Collector`]'s allocation count
+    /// without freeing it, so that it won't prevent the [`Collector`] from
+    /// being cleaned up.
+    ///
+    /// # Safety
+    ///
+    /// The argument must point to a valid `Node` previously allocated with
+    /// [`Node::alloc`], on which neither `leak` nor [`queue_drop`] has been
+    /// called. [`queue_drop`] must not be called on it afterwards.
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`Node::alloc`]: crate::Node::alloc
+    /// [`queue_drop`]: crate::Node::queue_drop
+    pub(crate) unsafe fn leak(node: *mut Node<T>) {
+        NodeHeader::check(node as *mut NodeHeader, LIVE);
+        let collector = (*node).header.link.collector;
+        (*collector).untrack(node as *mut NodeHeader);
+        (*collector).record_freed(node as *mut NodeHeader);
+        if (*collector).counters {
+            (*collector).allocs.decrement(Ordering::Release);
+        }
+    }
+
+    /// Reports that a writer has been waiting on readers of this `Node` for
+    /// `spins` iterations, if its [`Collector`] has a stall hook installed and
+    /// `spins` has just reached the hook's threshold.
+    ///
+    /// # Safety
+    ///
+    /// The argument must point to a valid `Node` previously allocated with
+    /// [`Node::alloc`], on which [`queue_drop`] has not been called.
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`Node::alloc`]: crate::Node::alloc
+    /// [`queue_drop`]: crate::Node::queue_drop
+    pub(crate) unsafe fn report_stall(node: *mut Node<T>, spins: usize) {
+        let collector = (*node).header.link.collector;
+        if let Some((threshold, on_writer_stall)) = &(*collector).on_writer_stall {
+            if spins == *threshold {
+                on_writer_stall(&StallInfo {
+                    type_name: core::any::type_name::<T>(),
+                    spins,
+                });
+            }
+        }
     }
 
     /// Gets a [`Handle`] to this `Node`'s associated [`Collector`].
     ///
+    /// # Safety
+    ///
     /// The argument must point to a valid `Node` previously allocated with
     /// [`Node::alloc`], on which [`queue_drop`] has not been called.
     ///
@@ -110,304 +751,4307 @@ impl<T> Node<T> {
     /// [`Collector`]: crate::Collector
     /// [`Node::alloc`]: crate::Node::alloc
     /// [`queue_drop`]: crate::Node::queue_drop
+    #[cfg_attr(feature = "diagnostics", track_caller)]
     pub unsafe fn handle(node: *mut Node<T>) -> Handle {
-        let collector = (*node).header.link.collector;
-        (*collector).handles.fetch_add(1, Ordering::Relaxed);
-        Handle { collector }
+        NodeHeader::check(node as *mut NodeHeader, LIVE);
+        Handle::new((*node).header.link.collector)
     }
 }
 
-/// A handle to a [`Collector`], used when allocating [`Owned`] and [`Shared`]
-/// values.
-///
-/// Multiple `Handle`s to a given [`Collector`] can exist at one time, and they
-/// can be safely moved and shared between threads.
-///
-/// [`Collector`]: crate::Collector
-/// [`Owned`]: crate::Owned
-/// [`Shared`]: crate::Shared
-pub struct Handle {
+/// Appends a node to the queue ending in `tail`.
+unsafe fn push(tail: &AtomicPtr<NodeHeader>, node: *mut NodeHeader) {
+    (*node).link.next = ManuallyDrop::new(AtomicPtr::new(core::ptr::null_mut()));
+    push_chain(tail, node, node);
+}
+
+/// Appends a chain of nodes, already linked from `first` to `last`, to the
+/// queue ending in `tail`.
+unsafe fn push_chain(tail: &AtomicPtr<NodeHeader>, first: *mut NodeHeader, last: *mut NodeHeader) {
+    // This is sequentially consistent so that either a collector going to
+    // sleep in `collect_blocking` sees the node, or the pusher sees that the
+    // collector is sleeping and wakes it up.
+    let prev = tail.swap(last, Ordering::SeqCst);
+    (*prev).link.next.store(first, Ordering::Relaxed);
+}
+
+/// A chain of nodes linked up locally, to be appended to their collector's
+/// drop queue with a single atomic operation.
+pub(crate) struct Batch {
     collector: *mut CollectorInner,
+    first: *mut NodeHeader,
+    last: *mut NodeHeader,
+    len: usize,
 }
 
-unsafe impl Send for Handle {}
-unsafe impl Sync for Handle {}
+impl Batch {
+    pub(crate) fn new() -> Batch {
+        Batch {
+            collector: core::ptr::null_mut(),
+            first: core::ptr::null_mut(),
+            last: core::ptr::null_mut(),
+            len: 0,
+        }
+    }
 
-impl Clone for Handle {
-    fn clone(&self) -> Self {
-        unsafe {
-            (*self.collector).handles.fetch_add(1, Ordering::Relaxed);
+    /// Adds a live node to the chain. If it belongs to a different collector
+    /// than the nodes already in the chain, those are flushed first.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub(crate) unsafe fn push(&mut self, node: *mut NodeHeader) {
+        NodeHeader::check(node, LIVE);
+        NodeHeader::mark(node, QUEUED);
+        let collector = (*node).link.collector;
+        if collector != self.collector {
+            self.flush();
+            self.collector = collector;
         }
+        (*collector).record_queued(node);
+        (*collector).stamp_queued(node);
 
-        Handle { collector: self.collector }
+        (*node).link.next = ManuallyDrop::new(AtomicPtr::new(core::ptr::null_mut()));
+        if self.last.is_null() {
+            self.first = node;
+        } else {
+            (*self.last).link.next.store(node, Ordering::Relaxed);
+        }
+        self.last = node;
+        self.len += 1;
     }
-}
 
-impl Drop for Handle {
-    fn drop(&mut self) {
+    /// Appends the chain to the drop queue.
+    pub(crate) fn flush(&mut self) {
+        if self.len == 0 {
+            return;
+        }
+
         unsafe {
-            (*self.collector).handles.fetch_sub(1, Ordering::Release);
+            let collector = &*self.collector;
+            #[cfg(feature = "thread-stats")]
+            collector.thread_stats.record_retire(self.len);
+            collector.count_queued(self.len);
+            push_chain(collector.normal_tail(), self.first, self.last);
+            collector.notify();
         }
+
+        self.first = core::ptr::null_mut();
+        self.last = core::ptr::null_mut();
+        self.len = 0;
     }
 }
 
-struct CollectorInner {
-    handles: AtomicUsize,
-    allocs: AtomicUsize,
-    tail: AtomicPtr<NodeHeader>,
+impl Drop for Batch {
+    fn drop(&mut self) {
+        self.flush();
+    }
 }
 
-/// A garbage collector for [`Owned`] and [`Shared`] allocations.
-///
-/// If a `Collector` is dropped, it will leak all associated allocations as
-/// well as its internal data structures. To avoid this, ensure that all
-/// allocations have been collected and all [`Handle`]s have been dropped, then
-/// call [`try_cleanup`].
-///
-/// [`Owned`]: crate::Owned
-/// [`Shared`]: crate::Shared
-/// [`try_cleanup`]: crate::Collector::try_cleanup
-pub struct Collector {
+/// Indices of the drop queues, in the order in which they are drained. The
+/// normal-priority queue may be split into several shards, which occupy the
+/// indices from `NORMAL` onwards.
+const URGENT: usize = 0;
+const NORMAL: usize = 1;
+
+/// The producer end of a drop queue, on its own cache line so that threads
+/// pushing to different shards don't contend with each other.
+type Tail = CachePadded<AtomicPtr<NodeHeader>>;
+
+/// The consumer end of a drop queue.
+struct Lane {
     head: *mut NodeHeader,
     stub: *mut NodeHeader,
-    inner: *mut CollectorInner,
+    // The node which was at the tail when the watchdog last took a
+    // checkpoint, or null once it has been removed.
+    mark: *mut NodeHeader,
 }
 
-unsafe impl Send for Collector {}
-
-impl Collector {
-    /// Constructs a new `Collector`.
-    pub fn new() -> Collector {
-        let head = Box::into_raw(Box::new(Node {
+impl Lane {
+    fn new() -> Lane {
+        let stub = Box::into_raw(Box::new(Node {
             header: NodeHeader {
                 link: NodeLink {
                     next: ManuallyDrop::new(AtomicPtr::new(core::ptr::null_mut())),
                 },
                 drop: drop_node::<()>,
+                dealloc: None,
+                #[cfg(feature = "diagnostics")]
+                magic: QUEUED,
+                #[cfg(feature = "byte-stats")]
+                size: 0,
+                #[cfg(feature = "latency-stats")]
+                queued_at: 0,
             },
             data: (),
         })) as *mut NodeHeader;
 
-        let inner = Box::into_raw(Box::new(CollectorInner {
-            handles: AtomicUsize::new(0),
-            allocs: AtomicUsize::new(0),
-            tail: AtomicPtr::new(head),
-        }));
+        Lane {
+            head: stub,
+            stub,
+            mark: core::ptr::null_mut(),
+        }
+    }
 
-        Collector {
-            head,
-            stub: head,
-            inner,
+    /// Marks the node currently at the tail of the queue. Returns false if
+    /// the queue is empty, in which case nothing is marked.
+    fn mark(&mut self, tail: &AtomicPtr<NodeHeader>) -> bool {
+        let tail = tail.load(Ordering::Acquire);
+        self.mark = if tail == self.stub && self.head == self.stub {
+            core::ptr::null_mut()
+        } else {
+            tail
+        };
+        !self.mark.is_null()
+    }
+
+    /// Removes the first node from the queue, requeueing the stub node as
+    /// necessary.
+    unsafe fn pop(&mut self, tail: &AtomicPtr<NodeHeader>) -> Option<*mut NodeHeader> {
+        loop {
+            let next = (*self.head).link.next.load(Ordering::Acquire);
+            if next.is_null() {
+                return None;
+            }
+
+            let head = self.head;
+            self.head = next;
+            if head == self.mark {
+                self.mark = core::ptr::null_mut();
+            }
+            if head == self.stub {
+                push(tail, head);
+            } else {
+                return Some(head);
+            }
         }
     }
+}
 
-    /// Gets a [`Handle`] to this `Collector`.
-    ///
-    /// [`Handle`]: crate::Handle
-    pub fn handle(&self) -> Handle {
-        unsafe {
-            (*self.inner).handles.fetch_add(1, Ordering::Relaxed);
+/// A handle to a [`Collector`], used when allocating [`Owned`] and [`Shared`]
+/// values.
+///
+/// Multiple `Handle`s to a given [`Collector`] can exist at one time, and they
+/// can be safely moved and shared between threads.
+///
+/// [`Collector`]: crate::Collector
+/// [`Owned`]: crate::Owned
+/// [`Shared`]: crate::Shared
+pub struct Handle {
+    collector: *mut CollectorInner,
+    #[cfg(feature = "diagnostics")]
+    id: usize,
+}
+
+unsafe impl Send for Handle {}
+unsafe impl Sync for Handle {}
+
+impl UnwindSafe for Handle {}
+impl RefUnwindSafe for Handle {}
+impl Unpin for Handle {}
+
+impl Handle {
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    unsafe fn new(collector: *mut CollectorInner) -> Handle {
+        if (*collector).counters {
+            (*collector).handles.fetch_add(1, Ordering::Relaxed);
         }
 
-        Handle { collector: self.inner }
+        Handle {
+            collector,
+            #[cfg(feature = "diagnostics")]
+            id: (*collector).handle_registry.lock().insert(Location::caller()),
+        }
     }
 
-    /// Drops all of the garbage in the queue.
+    /// Gets the number of live `Handle`s to the associated [`Collector`]. See
+    /// [`Collector::handle_count`] for details.
     ///
     /// # Examples
     /// ```
-    /// use basedrop::{Collector, Handle, Owned};
-    /// use core::mem::drop;
+    /// use basedrop::Collector;
+    ///
+    /// let collector = Collector::new();
+    /// let handle = collector.handle();
+    /// assert_eq!(handle.handle_count(), 1);
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`Collector::handle_count`]: crate::Collector::handle_count
+    pub fn handle_count(&self) -> usize {
+        unsafe { (*self.collector).handles.load(Ordering::Relaxed) }
+    }
+
+    /// Gets the number of live allocations associated with the associated
+    /// [`Collector`]. See [`Collector::alloc_count`] for details.
+    ///
+    /// This allows producer threads to throttle themselves without access to
+    /// the [`Collector`].
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
     ///
     /// let mut collector = Collector::new();
     /// let handle = collector.handle();
-    /// let x = Owned::new(&handle, 1);
-    /// let y = Owned::new(&handle, 2);
-    /// let z = Owned::new(&handle, 3);
     ///
-    /// assert_eq!(collector.alloc_count(), 3);
+    /// let x = Owned::new(&handle, 3);
+    /// assert_eq!(handle.alloc_count(), 1);
     ///
     /// drop(x);
-    /// drop(y);
-    /// drop(z);
     /// collector.collect();
-    ///
-    /// assert_eq!(collector.alloc_count(), 0);
+    /// assert_eq!(handle.alloc_count(), 0);
     /// ```
-    pub fn collect(&mut self) {
-        while self.collect_one() {}
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`Collector::alloc_count`]: crate::Collector::alloc_count
+    pub fn alloc_count(&self) -> usize {
+        unsafe { (*self.collector).allocs.load(Ordering::Relaxed) }
     }
 
-    /// Attempts to drop the first allocation in the queue. If successful,
-    /// returns true; otherwise returns false.
+    /// Gets the approximate number of allocations waiting in the associated
+    /// [`Collector`]'s drop queue. See [`Collector::pending_count`] for
+    /// details.
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`Collector::pending_count`]: crate::Collector::pending_count
+    pub fn pending_count(&self) -> usize {
+        unsafe { (*self.collector).pending.load(Ordering::Relaxed) }
+    }
+
+    /// Gets the number of times the associated [`Collector`] has fully
+    /// drained its queue. See [`Collector::collection_epoch`] for details.
     ///
     /// # Examples
     /// ```
-    /// use basedrop::{Collector, Handle, Owned};
-    /// use core::mem::drop;
+    /// use basedrop::{Collector, Owned};
     ///
     /// let mut collector = Collector::new();
     /// let handle = collector.handle();
-    /// let x = Owned::new(&handle, 1);
-    /// let y = Owned::new(&handle, 2);
-    /// let z = Owned::new(&handle, 3);
-    ///
-    /// assert_eq!(collector.alloc_count(), 3);
     ///
+    /// let epoch = handle.collection_epoch();
+    /// let x = Owned::new(&handle, 3);
     /// drop(x);
-    /// drop(y);
-    /// drop(z);
     ///
-    /// assert!(collector.collect_one());
-    /// assert!(collector.collect_one());
-    /// assert!(collector.collect_one());
+    /// collector.collect();
+    /// collector.collect();
+    /// assert!(handle.collection_epoch() >= epoch + 2);
+    /// ```
     ///
-    /// assert!(!collector.collect_one());
+    /// [`Collector`]: crate::Collector
+    /// [`Collector::collection_epoch`]: crate::Collector::collection_epoch
+    pub fn collection_epoch(&self) -> usize {
+        unsafe { (*self.collector).epoch.load(Ordering::Acquire) }
+    }
+
+    /// Moves a value into a new allocation and immediately adds it to the
+    /// associated [`Collector`]'s drop queue, so that it is dropped on the
+    /// [`Collector`]'s thread. This is useful for values which aren't wrapped
+    /// in [`Owned`] or [`Shared`] but are expensive to drop, such as large
+    /// `Vec`s or open files.
+    ///
+    /// This allocates, and so should not be called from a real-time thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::Collector;
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    ///
+    /// let samples = vec![0.0f32; 1 << 20];
+    /// handle.defer(samples);
+    /// assert_eq!(collector.alloc_count(), 1);
+    ///
+    /// collector.collect();
     /// assert_eq!(collector.alloc_count(), 0);
     /// ```
-    pub fn collect_one(&mut self) -> bool {
-        loop {
-            unsafe {
-                let next = (*self.head).link.next.load(Ordering::Acquire);
-                if next.is_null() {
-                    return false;
-                }
-
-                let head = self.head;
-                self.head = next;
-                if head == self.stub {
-                    (*head).link.next.store(core::ptr::null_mut(), Ordering::Relaxed);
-                    let tail = (*self.inner).tail.swap(head, Ordering::Release);
-                    (*tail).link.next.store(head, Ordering::Relaxed);
-                } else {
-                    ((*head).drop)(head);
-                    (*self.inner).allocs.fetch_sub(1, Ordering::Relaxed);
-                    return true;
-                }
-            }
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`Owned`]: crate::Owned
+    /// [`Shared`]: crate::Shared
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn defer<T: Send + 'static>(&self, value: T) {
+        unsafe {
+            Node::queue_drop(Node::alloc(self, value));
         }
     }
 
-    /// Gets the number of live [`Handle`]s to this `Collector`.
+    /// Adds a closure to the associated [`Collector`]'s drop queue, to be
+    /// called on the [`Collector`]'s thread when it is collected, in order
+    /// with other queued allocations.
     ///
-    /// [`Handle`]: crate::Handle
-    pub fn handle_count(&self) -> usize {
-        unsafe { (*self.inner).handles.load(Ordering::Relaxed) }
-    }
+    /// This allocates, and so should not be called from a real-time thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::Collector;
+    /// use std::sync::mpsc::channel;
+    ///
+    /// let mut collector = Collector::new();
+    /// let (pool, returned) = channel();
+    ///
+    /// let buffer = vec![0.0f32; 4096];
+    /// collector.handle().defer_fn(move || pool.send(buffer).unwrap());
+    ///
+    /// collector.collect();
+    /// assert_eq!(returned.recv().unwrap().len(), 4096);
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn defer_fn<F: FnOnce() + Send + 'static>(&self, f: F) {
+        unsafe fn call<F: FnOnce()>(node: *mut NodeHeader) -> bool {
+            let f = core::ptr::read(&(*(node as *mut Node<F>)).data);
+            free_node::<F>(node);
+            f();
+            true
+        }
 
-    /// Gets the number of live allocations associated with this `Collector`.
-    pub fn alloc_count(&self) -> usize {
-        unsafe { (*self.inner).allocs.load(Ordering::Relaxed) }
+        unsafe {
+            Node::queue_drop(Node::alloc_with_drop(self, f, call::<F>));
+        }
     }
 
-    /// Attempts to free all resources associated with this `Collector`. This
-    /// method will fail and return the original `Collector` if there are any
-    /// live [`Handle`]s or allocations associated with it.
+    /// Returns `true` if the associated [`Collector`]'s drop queue has
+    /// reached the limit set with [`CollectorBuilder::max_pending`], meaning
+    /// that the [`Collector`] is falling behind and producers of garbage
+    /// should slow down. Always returns `false` if no limit is set.
     ///
     /// # Examples
     /// ```
-    /// use basedrop::{Collector, Handle, Owned};
-    /// use core::mem::drop;
+    /// use basedrop::{Collector, Owned};
     ///
-    /// let mut collector = Collector::new();
+    /// let mut collector = Collector::builder().max_pending(1).build();
     /// let handle = collector.handle();
-    /// let x = Owned::new(&handle, 3);
     ///
-    /// let result = collector.try_cleanup();
-    /// assert!(result.is_err());
-    /// let mut collector = result.unwrap_err();
+    /// drop(Owned::new(&handle, 1));
+    /// assert!(handle.is_congested());
     ///
-    /// drop(handle);
-    /// drop(x);
     /// collector.collect();
+    /// assert!(!handle.is_congested());
+    /// ```
     ///
-    /// assert!(collector.try_cleanup().is_ok());
+    /// [`Collector`]: crate::Collector
+    /// [`CollectorBuilder::max_pending`]: crate::CollectorBuilder::max_pending
+    pub fn is_congested(&self) -> bool {
+        unsafe {
+            let collector = &*self.collector;
+            collector
+                .max_pending
+                .is_some_and(|max_pending| collector.pending.load(Ordering::Relaxed) >= max_pending)
+        }
+    }
+
+    /// Returns `true` if garbage has been waiting in the associated
+    /// [`Collector`]'s drop queue for longer than the limit set with
+    /// [`CollectorBuilder::watchdog`]. Always returns `false` if no watchdog
+    /// is installed.
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`CollectorBuilder::watchdog`]: crate::CollectorBuilder::watchdog
+    pub fn is_overdue(&self) -> bool {
+        unsafe { (*self.collector).is_overdue() }
+    }
+
+    /// Registers the current thread for [`Collector::thread_stats`]. Only
+    /// available with the `thread-stats` feature.
+    ///
+    /// A thread is otherwise registered on its first allocation or
+    /// retirement, which allocates and briefly takes a lock. Calling this
+    /// from a real-time thread before it starts processing keeps that off
+    /// the real-time path; after registration, recording a count only
+    /// touches atomic counters.
+    ///
+    /// # Examples
     /// ```
+    /// use basedrop::{Collector, Owned};
     ///
-    /// [`Handle`]: crate::Handle
-    pub fn try_cleanup(self) -> Result<(), Self> {
+    /// let collector = Collector::new();
+    /// let handle = collector.handle();
+    ///
+    /// std::thread::spawn(move || {
+    ///     handle.register_thread_stats();
+    ///     drop(Owned::new(&handle, 3));
+    /// })
+    /// .join()
+    /// .unwrap();
+    ///
+    /// assert_eq!(collector.thread_stats()[0].retired, 1);
+    /// ```
+    ///
+    /// [`Collector::thread_stats`]: crate::Collector::thread_stats
+    #[cfg(feature = "thread-stats")]
+    pub fn register_thread_stats(&self) {
+        unsafe { (*self.collector).thread_stats.register() }
+    }
+}
+
+impl Clone for Handle {
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    fn clone(&self) -> Self {
+        unsafe { Handle::new(self.collector) }
+    }
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
         unsafe {
-            if (*self.inner).handles.load(Ordering::Acquire) == 0 {
-                if (*self.inner).allocs.load(Ordering::Acquire) == 0 {
-                    let _ = Box::from_raw(self.stub);
-                    let _ = Box::from_raw(self.inner);
+            #[cfg(feature = "diagnostics")]
+            (*self.collector).handle_registry.lock().remove(self.id);
 
-                    return Ok(());
-                }
+            if (*self.collector).counters {
+                (*self.collector).handles.fetch_sub(1, Ordering::Release);
             }
         }
+    }
+}
+
+pub(crate) struct CollectorInner {
+    pub(crate) counters: bool,
+    // Handles are counted exactly, so that once the count reaches zero it is
+    // known that no more allocations can be made. Allocations are counted
+    // with a striped counter, which only needs to be exact at that point.
+    //
+    // The counters and queue tails written by other threads each have their
+    // own cache line, so that they don't contend with each other or with the
+    // read-mostly fields.
+    handles: CachePadded<AtomicUsize>,
+    pub(crate) allocs: StripedCounter,
+    epoch: AtomicUsize,
+    tails: Box<[Tail]>,
+    allocator: Option<Box<dyn NodeAllocator>>,
+    on_alloc: Option<Box<AllocHook>>,
+    on_drop: Option<Box<AllocHook>>,
+    on_writer_stall: Option<(usize, Box<StallHook>)>,
+    on_backlog: Option<(usize, Box<BacklogHook>)>,
+    watchdog: Option<Watchdog>,
+    // Only maintained if counters are enabled, a limit is set, or a backlog
+    // hook or watchdog is installed.
+    pending: CachePadded<AtomicUsize>,
+    // The total size of all nodes freed so far, wrapping on overflow.
+    freed_bytes: AtomicUsize,
+    // Only written by the collector, at the start of each collection pass.
+    peak_pending: AtomicUsize,
+    peak_allocs: AtomicUsize,
+    #[cfg(feature = "byte-stats")]
+    live_bytes: StripedCounter,
+    #[cfg(feature = "byte-stats")]
+    pending_bytes: CachePadded<AtomicUsize>,
+    #[cfg(feature = "latency-stats")]
+    reclamation_clock: Option<Box<dyn Clock + Send + Sync>>,
+    // Only accessed by the collector.
+    #[cfg(feature = "latency-stats")]
+    latency: core::cell::Cell<LatencyTotals>,
+    #[cfg(feature = "latency-stats")]
+    last_latency: core::cell::Cell<Option<LatencyStats>>,
+    max_pending: Option<usize>,
+    #[cfg(feature = "diagnostics")]
+    handle_registry: SpinLock<HandleRegistry>,
+    #[cfg(feature = "track-allocations")]
+    alloc_registry: SpinLock<BTreeMap<usize, LiveAllocation>>,
+    #[cfg(feature = "thread-stats")]
+    thread_stats: ThreadRegistry,
+    // Holds the collector while it waits in `collect_blocking` or
+    // `collect_async`.
+    #[cfg(feature = "std")]
+    waiter: WaiterSlot,
+}
 
-        Err(self)
+/// Something waiting for a node to be queued.
+#[cfg(feature = "std")]
+enum Waiter {
+    Thread(std::thread::Thread),
+    #[cfg(feature = "async")]
+    Task(core::task::Waker),
+}
+
+#[cfg(feature = "std")]
+impl Waiter {
+    fn wake(self) {
+        match self {
+            Waiter::Thread(thread) => thread.unpark(),
+            #[cfg(feature = "async")]
+            Waiter::Task(waker) => waker.wake(),
+        }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Hands a [`Waiter`] from the collector to the next thread which queues a
+/// node. Neither side ever waits for a lock held by the other, so queueing
+/// never blocks on the collector thread.
+#[cfg(feature = "std")]
+struct WaiterSlot {
+    state: AtomicUsize,
+    // Only written by the collector while the state is `REGISTERING`, and
+    // only taken by the thread which changed it from `WAITING` to `TAKING`.
+    waiter: core::cell::UnsafeCell<Option<Waiter>>,
+}
 
-    extern crate std;
+#[cfg(feature = "std")]
+impl WaiterSlot {
+    const EMPTY: usize = 0;
+    const REGISTERING: usize = 1;
+    const WAITING: usize = 2;
+    const TAKING: usize = 3;
 
-    use alloc::sync::Arc;
-    use core::sync::atomic::AtomicUsize;
+    fn new() -> WaiterSlot {
+        WaiterSlot {
+            state: AtomicUsize::new(Self::EMPTY),
+            waiter: core::cell::UnsafeCell::new(None),
+        }
+    }
 
-    struct Test(Arc<AtomicUsize>);
+    /// Stores `waiter` to be woken by the next call to `wake`. Only called by
+    /// the collector.
+    fn register(&self, waiter: Waiter) {
+        // A queueing thread may be in the middle of taking the previous
+        // waiter, which only takes a few instructions.
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            if state == Self::TAKING {
+                core::hint::spin_loop();
+                state = self.state.load(Ordering::Relaxed);
+                continue;
+            }
 
-    impl Drop for Test {
-        fn drop(&mut self) {
-            self.0.fetch_add(1, Ordering::Relaxed);
+            match self.state.compare_exchange_weak(
+                state,
+                Self::REGISTERING,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(actual) => state = actual,
+            }
         }
+
+        unsafe { *self.waiter.get() = Some(waiter) };
+        // This is sequentially consistent so that either the collector sees a
+        // node queued after this, or the queueing thread sees the waiter.
+        self.state.store(Self::WAITING, Ordering::SeqCst);
     }
 
-    #[test]
-    fn collector() {
-        let counter = Arc::new(AtomicUsize::new(0));
+    /// Withdraws the registered waiter, if it hasn't been woken yet. Only
+    /// called by the collector.
+    fn cancel(&self) {
+        let _ = self.state.compare_exchange(
+            Self::WAITING,
+            Self::EMPTY,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        );
+    }
 
-        let collector = Collector::new();
-        let handle = collector.handle();
+    /// Wakes the registered waiter, if any. This never blocks, and if nothing
+    /// is registered, it is a single load.
+    fn wake(&self) {
+        if self.state.load(Ordering::SeqCst) == Self::WAITING
+            && self
+                .state
+                .compare_exchange(Self::WAITING, Self::TAKING, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+        {
+            let waiter = unsafe { (*self.waiter.get()).take() };
+            self.state.store(Self::EMPTY, Ordering::Release);
+            if let Some(waiter) = waiter {
+                waiter.wake();
+            }
+        }
+    }
 
-        let node = Node::alloc(&handle, ());
-        let result = collector.try_cleanup();
-        assert!(result.is_err());
-        let mut collector = result.unwrap_err();
-        unsafe {
-            Node::queue_drop(node);
+    /// Drops the registered waiter. Must not be called concurrently with any
+    /// other method.
+    unsafe fn clear(&self) {
+        self.state.store(Self::EMPTY, Ordering::Relaxed);
+        *self.waiter.get() = None;
+    }
+}
+
+impl CollectorInner {
+    /// Picks the shard of the normal-priority queue for the current thread.
+    fn normal_tail(&self) -> &AtomicPtr<NodeHeader> {
+        let shards = self.tails.len() - NORMAL;
+        let index = if shards == 1 { NORMAL } else { NORMAL + stripe() % shards };
+        &self.tails[index]
+    }
+
+    fn tracks_pending(&self) -> bool {
+        self.counters
+            || self.max_pending.is_some()
+            || self.on_backlog.is_some()
+            || self.watchdog.is_some()
+    }
+
+    /// Counts `count` nodes being added to the drop queue.
+    fn count_queued(&self, count: usize) {
+        if self.tracks_pending() {
+            let before = self.pending.fetch_add(count, Ordering::Relaxed);
+            self.after_queued(before, before + count);
         }
+    }
 
-        let mut threads = alloc::vec![];
-        for _ in 0..100 {
-            let handle = handle.clone();
-            let counter = counter.clone();
-            threads.push(std::thread::spawn(move || {
-                for _ in 0..100 {
-                    let node = Node::alloc(&handle, Test(counter.clone()));
-                    unsafe {
-                        Node::queue_drop(node);
-                    }
+    /// Calls the backlog hook if the number of queued nodes has just reached
+    /// its threshold, going from `before` to `pending`, and lets the watchdog
+    /// check on the queue.
+    fn after_queued(&self, before: usize, pending: usize) {
+        if let Some((threshold, on_backlog)) = &self.on_backlog {
+            if before < *threshold && pending >= *threshold {
+                on_backlog(&BacklogInfo { pending });
+            }
+        }
+
+        if let Some(watchdog) = &self.watchdog {
+            if before == 0 {
+                watchdog.since.store(watchdog.now(), Ordering::Relaxed);
+                watchdog.fired.store(false, Ordering::Relaxed);
+            } else {
+                let waited = watchdog.waited();
+                if waited > watchdog.limit && !watchdog.fired.swap(true, Ordering::Relaxed) {
+                    (watchdog.on_overdue)(&OverdueInfo { waited, pending });
                 }
-            }));
+            }
         }
+    }
 
-        for _ in 0..100 {
-            collector.collect();
+    fn is_overdue(&self) -> bool {
+        match &self.watchdog {
+            Some(watchdog) => {
+                self.pending.load(Ordering::Relaxed) > 0 && watchdog.waited() > watchdog.limit
+            }
+            None => false,
         }
+    }
 
-        for thread in threads {
-            thread.join().unwrap();
+    /// Wakes up a collector waiting in [`Collector::collect_blocking`] or
+    /// [`Collector::collect_async`] after a node has been queued. If no
+    /// collector is waiting, this is a single load.
+    #[cfg(feature = "std")]
+    fn notify(&self) {
+        self.waiter.wake();
+    }
+
+    #[cfg(not(feature = "std"))]
+    #[inline(always)]
+    fn notify(&self) {}
+
+    /// Removes an allocation from the registry of live allocations. This must
+    /// happen before the allocation is freed, since its address may be reused
+    /// immediately afterwards.
+    #[cfg(feature = "track-allocations")]
+    pub(crate) fn untrack(&self, node: *mut NodeHeader) {
+        self.alloc_registry.lock().remove(&(node as usize));
+    }
+
+    #[cfg(not(feature = "track-allocations"))]
+    #[inline(always)]
+    pub(crate) fn untrack(&self, _node: *mut NodeHeader) {}
+
+    /// Stops counting a node which was leaked because its destructor
+    /// panicked during collection.
+    #[cfg(feature = "std")]
+    unsafe fn forget(&self, node: *mut NodeHeader) {
+        self.untrack(node);
+        self.record_freed(node);
+        if self.counters {
+            self.allocs.decrement(Ordering::Relaxed);
         }
+    }
 
-        collector.collect();
+    /// Removes a node's size from the live byte count once it is freed or
+    /// leaked.
+    #[cfg(feature = "byte-stats")]
+    unsafe fn record_freed(&self, node: *mut NodeHeader) {
+        if self.counters {
+            self.live_bytes.sub((*node).size, Ordering::Relaxed);
+        }
+    }
 
-        let tail = unsafe { (*collector.inner).tail.load(Ordering::Relaxed) };
-        assert!(collector.head == tail);
-        assert!(collector.head == collector.stub);
-        let next = unsafe { (*collector.head).link.next.load(Ordering::Relaxed) };
-        assert!(next.is_null());
+    #[cfg(not(feature = "byte-stats"))]
+    #[inline(always)]
+    unsafe fn record_freed(&self, _node: *mut NodeHeader) {}
 
-        assert!(counter.load(Ordering::Relaxed) == 10000);
+    /// Adds a node's size to the pending byte count as it is queued.
+    #[cfg(feature = "byte-stats")]
+    unsafe fn record_queued(&self, node: *mut NodeHeader) {
+        if self.counters {
+            self.pending_bytes.fetch_add((*node).size, Ordering::Relaxed);
+        }
+    }
 
-        core::mem::drop(handle);
+    #[cfg(not(feature = "byte-stats"))]
+    #[inline(always)]
+    unsafe fn record_queued(&self, _node: *mut NodeHeader) {}
 
-        let result = collector.try_cleanup();
-        assert!(result.is_ok());
+    /// Records the time at which a node is queued, if a reclamation clock is
+    /// installed.
+    #[cfg(feature = "latency-stats")]
+    unsafe fn stamp_queued(&self, node: *mut NodeHeader) {
+        if let Some(clock) = &self.reclamation_clock {
+            (*node).queued_at = clock.now().as_nanos() as u64;
+        }
+    }
+
+    #[cfg(not(feature = "latency-stats"))]
+    #[inline(always)]
+    unsafe fn stamp_queued(&self, _node: *mut NodeHeader) {}
+
+    /// Removes a node's size from the pending byte count as it is removed
+    /// from the queue.
+    #[cfg(feature = "byte-stats")]
+    unsafe fn record_dequeued(&self, node: *mut NodeHeader) {
+        if self.counters {
+            self.pending_bytes.fetch_sub((*node).size, Ordering::Relaxed);
+        }
+    }
+
+    #[cfg(not(feature = "byte-stats"))]
+    #[inline(always)]
+    unsafe fn record_dequeued(&self, _node: *mut NodeHeader) {}
+
+    /// Allocates memory for a node, using the [`NodeAllocator`] if one is
+    /// installed.
+    unsafe fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        match &self.allocator {
+            Some(allocator) => allocator.allocate(layout),
+            None => NonNull::new(alloc::alloc::alloc(layout)).ok_or(AllocError),
+        }
+    }
+
+    /// Frees memory returned by [`CollectorInner::allocate`].
+    unsafe fn deallocate(&self, ptr: *mut u8, layout: Layout) {
+        match &self.allocator {
+            Some(allocator) => allocator.deallocate(NonNull::new_unchecked(ptr), layout),
+            None => alloc::alloc::dealloc(ptr, layout),
+        }
+    }
+}
+
+type AllocHook = dyn Fn(&AllocInfo) + Send + Sync;
+
+/// Running totals of the time nodes spent in the queue during a pass.
+#[cfg(feature = "latency-stats")]
+#[derive(Clone, Copy, Default)]
+struct LatencyTotals {
+    count: usize,
+    total: Duration,
+    max: Duration,
+}
+
+/// Information about an allocation, passed to the hooks installed with
+/// [`CollectorBuilder::on_alloc`] and [`CollectorBuilder::on_drop`].
+///
+/// [`CollectorBuilder::on_alloc`]: crate::CollectorBuilder::on_alloc
+/// [`CollectorBuilder::on_drop`]: crate::CollectorBuilder::on_drop
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct AllocInfo {
+    /// The name of the type being allocated, as returned by
+    /// [`core::any::type_name`].
+    pub type_name: &'static str,
+    /// The size of the allocation in bytes, including the node header.
+    pub size: usize,
+    /// The alignment of the allocation in bytes.
+    pub align: usize,
+}
+
+/// A live allocation, as returned by [`Collector::live_allocations`]. Only
+/// available with the `track-allocations` feature.
+///
+/// [`Collector::live_allocations`]: crate::Collector::live_allocations
+#[cfg(feature = "track-allocations")]
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct LiveAllocation {
+    /// The name of the allocated type, as returned by
+    /// [`core::any::type_name`].
+    pub type_name: &'static str,
+    /// The size of the allocation in bytes, including the node header.
+    pub size: usize,
+    /// The source location at which the allocation was made.
+    pub location: &'static Location<'static>,
+    /// The tag given when the allocation was made, e.g. with
+    /// [`Owned::new_tagged`].
+    ///
+    /// [`Owned::new_tagged`]: crate::Owned::new_tagged
+    pub tag: Option<&'static str>,
+}
+
+type StallHook = dyn Fn(&StallInfo) + Send + Sync;
+
+/// Information about a stalled [`SharedCell`] writer, passed to the hook
+/// installed with [`CollectorBuilder::on_writer_stall`].
+///
+/// [`SharedCell`]: crate::SharedCell
+/// [`CollectorBuilder::on_writer_stall`]: crate::CollectorBuilder::on_writer_stall
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct StallInfo {
+    /// The name of the type held by the [`SharedCell`], as returned by
+    /// [`core::any::type_name`].
+    ///
+    /// [`SharedCell`]: crate::SharedCell
+    pub type_name: &'static str,
+    /// The number of iterations the writer has spent waiting for readers.
+    pub spins: usize,
+}
+
+type BacklogHook = dyn Fn(&BacklogInfo) + Send + Sync;
+
+/// Information about a growing drop queue, passed to the hook installed with
+/// [`CollectorBuilder::on_backlog`].
+///
+/// [`CollectorBuilder::on_backlog`]: crate::CollectorBuilder::on_backlog
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct BacklogInfo {
+    /// The number of allocations waiting in the drop queue.
+    pub pending: usize,
+}
+
+type OverdueHook = dyn Fn(&OverdueInfo) + Send + Sync;
+
+/// Watches for garbage waiting too long to be collected.
+struct Watchdog {
+    clock: Box<dyn Clock + Send + Sync>,
+    limit: Duration,
+    on_overdue: Box<OverdueHook>,
+    // No allocation in the queue was queued before this time, in
+    // microseconds on the clock, wrapping on overflow so that 32-bit targets
+    // without 64-bit atomics are supported. Set when the queue becomes
+    // nonempty, and moved forward by the collector as it passes checkpoints.
+    since: AtomicUsize,
+    // Whether the hook has been called since the wait was last within the
+    // limit.
+    fired: AtomicBool,
+    // Only accessed by the collector: when the current checkpoint was taken,
+    // and the number of lanes whose marked node has yet to be removed.
+    checkpoint: core::cell::Cell<usize>,
+    unreached: core::cell::Cell<usize>,
+}
+
+impl Watchdog {
+    fn now(&self) -> usize {
+        self.clock.now().as_micros() as usize
+    }
+
+    fn waited(&self) -> Duration {
+        let since = self.since.load(Ordering::Relaxed);
+        let waited = self.now().wrapping_sub(since);
+        // Another thread may have stored a reading taken after ours.
+        if waited > usize::MAX / 2 {
+            Duration::ZERO
+        } else {
+            Duration::from_micros(waited as u64)
+        }
+    }
+}
+
+/// Information about garbage which has been waiting too long, passed to the
+/// hook installed with [`CollectorBuilder::watchdog`].
+///
+/// [`CollectorBuilder::watchdog`]: crate::CollectorBuilder::watchdog
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct OverdueInfo {
+    /// Roughly how long the oldest allocation in the drop queue has been
+    /// waiting.
+    pub waited: Duration,
+    /// The number of allocations waiting in the drop queue.
+    pub pending: usize,
+}
+
+/// The error returned by [`Node::try_queue_drop`] when the drop queue is
+/// full.
+///
+/// [`Node::try_queue_drop`]: crate::Node::try_queue_drop
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueueFull;
+
+impl core::fmt::Display for QueueFull {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str("drop queue is full")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for QueueFull {}
+
+/// The error returned by fallible constructors such as [`Node::try_alloc`]
+/// when memory can't be allocated.
+///
+/// [`Node::try_alloc`]: crate::Node::try_alloc
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AllocError;
+
+impl core::fmt::Display for AllocError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str("memory allocation failed")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AllocError {}
+
+/// The error returned by [`Collector::try_cleanup`] when the [`Collector`]
+/// is still in use. It gives the [`Collector`] back, along with the counts
+/// which kept it alive.
+///
+/// [`Collector::try_cleanup`]: crate::Collector::try_cleanup
+/// [`Collector`]: crate::Collector
+pub struct CleanupError {
+    /// The [`Collector`] which could not be cleaned up.
+    ///
+    /// [`Collector`]: crate::Collector
+    pub collector: Collector,
+    /// The number of live [`Handle`]s at the time of the attempt.
+    ///
+    /// [`Handle`]: crate::Handle
+    pub live_handles: usize,
+    /// The number of live allocations at the time of the attempt, including
+    /// ones still in the drop queue.
+    pub live_allocs: usize,
+}
+
+impl core::fmt::Debug for CleanupError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("CleanupError")
+            .field("live_handles", &self.live_handles)
+            .field("live_allocs", &self.live_allocs)
+            .finish_non_exhaustive()
+    }
+}
+
+impl core::fmt::Display for CleanupError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        if !unsafe { (*self.collector.inner).counters } {
+            return f.write_str("collector has counters disabled and can't be cleaned up");
+        }
+
+        write!(
+            f,
+            "collector still has {} live handles and {} live allocations",
+            self.live_handles, self.live_allocs,
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CleanupError {}
+
+/// Records the creation site of every live [`Handle`] when the `diagnostics`
+/// feature is enabled.
+#[cfg(feature = "diagnostics")]
+struct HandleRegistry {
+    next_id: usize,
+    live: BTreeMap<usize, &'static Location<'static>>,
+}
+
+#[cfg(feature = "diagnostics")]
+impl HandleRegistry {
+    fn insert(&mut self, location: &'static Location<'static>) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.live.insert(id, location);
+        id
+    }
+
+    fn remove(&mut self, id: usize) {
+        self.live.remove(&id);
+    }
+}
+
+/// A garbage collector for [`Owned`] and [`Shared`] allocations.
+///
+/// By default, dropping a `Collector` drains its drop queue and then frees its
+/// internal data structures, unless there are still live [`Handle`]s or
+/// allocations associated with it, in which case they are leaked. Use
+/// [`try_cleanup`] to find out whether cleanup succeeded, or choose a
+/// different [`DropPolicy`] using [`CollectorBuilder::drop_policy`].
+///
+/// [`Owned`]: crate::Owned
+/// [`Shared`]: crate::Shared
+/// [`try_cleanup`]: crate::Collector::try_cleanup
+/// [`DropPolicy`]: crate::DropPolicy
+/// [`CollectorBuilder::drop_policy`]: crate::CollectorBuilder::drop_policy
+pub struct Collector {
+    lanes: Box<[Lane]>,
+    // The shard which was last popped from.
+    shard: usize,
+    inner: *mut CollectorInner,
+    // Not included in the handle count, and never dropped.
+    handle: ManuallyDrop<Handle>,
+    drop_policy: DropPolicy,
+    executor: Option<Box<dyn DropExecutor>>,
+    profiler: Option<Box<dyn Profiler>>,
+}
+
+/// Determines what happens when a [`Collector`] is dropped without having
+/// been cleaned up via [`try_cleanup`].
+///
+/// [`Collector`]: crate::Collector
+/// [`try_cleanup`]: crate::Collector::try_cleanup
+#[derive(Clone, Copy, Debug, Default)]
+pub enum DropPolicy {
+    /// Leak all associated allocations and internal data structures without
+    /// attempting to collect anything.
+    Leak,
+    /// Drain the drop queue, then free the `Collector`'s internal data
+    /// structures if there are no remaining live [`Handle`]s or allocations.
+    /// Otherwise, leak them. This is the default.
+    ///
+    /// [`Handle`]: crate::Handle
+    #[default]
+    Collect,
+    /// Behave like [`DropPolicy::Collect`], but call the given function with
+    /// the `Collector` before leaking it.
+    ///
+    /// [`DropPolicy::Collect`]: crate::DropPolicy::Collect
+    Report(fn(&Collector)),
+    /// Behave like [`DropPolicy::Collect`], but panic instead of leaking.
+    ///
+    /// Note that if the `Collector` is dropped during unwinding, this will
+    /// abort the process.
+    ///
+    /// [`DropPolicy::Collect`]: crate::DropPolicy::Collect
+    Panic,
+}
+
+/// A builder for configuring a [`Collector`].
+///
+/// # Examples
+/// ```
+/// use basedrop::{CollectorBuilder, DropPolicy};
+///
+/// let collector = CollectorBuilder::new()
+///     .drop_policy(DropPolicy::Collect)
+///     .build();
+/// ```
+///
+/// [`Collector`]: crate::Collector
+pub struct CollectorBuilder {
+    drop_policy: DropPolicy,
+    executor: Option<Box<dyn DropExecutor>>,
+    profiler: Option<Box<dyn Profiler>>,
+    counters: bool,
+    allocator: Option<Box<dyn NodeAllocator>>,
+    on_alloc: Option<Box<AllocHook>>,
+    on_drop: Option<Box<AllocHook>>,
+    on_writer_stall: Option<(usize, Box<StallHook>)>,
+    on_backlog: Option<(usize, Box<BacklogHook>)>,
+    watchdog: Option<Watchdog>,
+    max_pending: Option<usize>,
+    shards: usize,
+    #[cfg(feature = "latency-stats")]
+    reclamation_clock: Option<Box<dyn Clock + Send + Sync>>,
+}
+
+impl Default for CollectorBuilder {
+    fn default() -> CollectorBuilder {
+        CollectorBuilder {
+            drop_policy: DropPolicy::default(),
+            executor: None,
+            profiler: None,
+            counters: true,
+            allocator: None,
+            on_alloc: None,
+            on_drop: None,
+            on_writer_stall: None,
+            on_backlog: None,
+            watchdog: None,
+            max_pending: None,
+            shards: 1,
+            #[cfg(feature = "latency-stats")]
+            reclamation_clock: None,
+        }
+    }
+}
+
+impl CollectorBuilder {
+    /// Constructs a new `CollectorBuilder` with the default configuration.
+    pub fn new() -> CollectorBuilder {
+        CollectorBuilder::default()
+    }
+
+    /// Sets whether the [`Collector`] keeps track of its live [`Handle`]s and
+    /// allocations. Defaults to true.
+    ///
+    /// Maintaining these counts costs an atomic read-modify-write operation
+    /// each time an allocation is created, queued or collected and each time
+    /// a [`Handle`] is created or dropped. With counters disabled,
+    /// [`Collector::handle_count`], [`Collector::alloc_count`] and
+    /// [`Collector::pending_count`] always return 0, and since the
+    /// [`Collector`] can no longer tell when it is safe to free itself,
+    /// [`Collector::try_cleanup`] always fails. Use
+    /// [`Collector::cleanup_unchecked`] instead.
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`Handle`]: crate::Handle
+    /// [`Collector::handle_count`]: crate::Collector::handle_count
+    /// [`Collector::alloc_count`]: crate::Collector::alloc_count
+    /// [`Collector::pending_count`]: crate::Collector::pending_count
+    /// [`Collector::try_cleanup`]: crate::Collector::try_cleanup
+    /// [`Collector::cleanup_unchecked`]: crate::Collector::cleanup_unchecked
+    pub fn counters(mut self, counters: bool) -> CollectorBuilder {
+        self.counters = counters;
+        self
+    }
+
+    /// Sets the [`DropPolicy`] used if the [`Collector`] is dropped without
+    /// having been cleaned up. Defaults to [`DropPolicy::Collect`].
+    ///
+    /// [`DropPolicy`]: crate::DropPolicy
+    /// [`Collector`]: crate::Collector
+    /// [`DropPolicy::Collect`]: crate::DropPolicy::Collect
+    pub fn drop_policy(mut self, drop_policy: DropPolicy) -> CollectorBuilder {
+        self.drop_policy = drop_policy;
+        self
+    }
+
+    /// Sets a [`DropExecutor`] to which the [`Collector`] hands off collected
+    /// allocations instead of dropping them inline. See [`DropExecutor`] for
+    /// an example.
+    ///
+    /// [`DropExecutor`]: crate::DropExecutor
+    /// [`Collector`]: crate::Collector
+    pub fn executor<E: DropExecutor + 'static>(mut self, executor: E) -> CollectorBuilder {
+        self.executor = Some(Box::new(executor));
+        self
+    }
+
+    /// Installs a [`Profiler`] which is notified of each collection pass. See
+    /// [`Profiler`] for an example.
+    ///
+    /// [`Profiler`]: crate::Profiler
+    pub fn profiler<P: Profiler + 'static>(mut self, profiler: P) -> CollectorBuilder {
+        self.profiler = Some(Box::new(profiler));
+        self
+    }
+
+    /// Installs a [`NodeAllocator`] from which all allocations made through
+    /// the [`Collector`]'s [`Handle`]s are served, instead of the global
+    /// allocator. See [`NodeAllocator`] for an example.
+    ///
+    /// [`NodeAllocator`]: crate::NodeAllocator
+    /// [`Collector`]: crate::Collector
+    /// [`Handle`]: crate::Handle
+    pub fn allocator<A: NodeAllocator + 'static>(mut self, allocator: A) -> CollectorBuilder {
+        self.allocator = Some(Box::new(allocator));
+        self
+    }
+
+    /// Installs a hook which is called each time an allocation associated
+    /// with the [`Collector`] is created, e.g. for feeding a memory profiler
+    /// or enforcing an allocation budget.
+    ///
+    /// The hook runs synchronously on the allocating thread. Since allocating
+    /// is already not real-time safe, the hook is free to lock or allocate.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// let bytes = Arc::new(AtomicUsize::new(0));
+    /// let collector = Collector::builder()
+    ///     .on_alloc({
+    ///         let bytes = bytes.clone();
+    ///         move |info| {
+    ///             bytes.fetch_add(info.size, Ordering::Relaxed);
+    ///         }
+    ///     })
+    ///     .build();
+    ///
+    /// let x = Owned::new(&collector.handle(), [0u8; 1024]);
+    /// assert!(bytes.load(Ordering::Relaxed) >= 1024);
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    pub fn on_alloc<F>(mut self, on_alloc: F) -> CollectorBuilder
+    where
+        F: Fn(&AllocInfo) + Send + Sync + 'static,
+    {
+        self.on_alloc = Some(Box::new(on_alloc));
+        self
+    }
+
+    /// Installs a hook which is called each time an allocation associated
+    /// with the [`Collector`] has been dropped and is about to be freed, with
+    /// the same information as was passed to the [`on_alloc`] hook. Together
+    /// with [`on_alloc`], this is enough to feed a heap profiler.
+    ///
+    /// The hook runs synchronously on the thread dropping the allocation,
+    /// usually the one running the [`Collector`]. It is not called for
+    /// [`Pool`] slots which are returned to their pool rather than freed.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicIsize, Ordering};
+    ///
+    /// let bytes = Arc::new(AtomicIsize::new(0));
+    /// let mut collector = Collector::builder()
+    ///     .on_alloc({
+    ///         let bytes = bytes.clone();
+    ///         move |info| {
+    ///             bytes.fetch_add(info.size as isize, Ordering::Relaxed);
+    ///         }
+    ///     })
+    ///     .on_drop({
+    ///         let bytes = bytes.clone();
+    ///         move |info| {
+    ///             bytes.fetch_sub(info.size as isize, Ordering::Relaxed);
+    ///         }
+    ///     })
+    ///     .build();
+    ///
+    /// drop(Owned::new(&collector.handle(), [0u8; 1024]));
+    /// collector.collect();
+    /// assert_eq!(bytes.load(Ordering::Relaxed), 0);
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`on_alloc`]: crate::CollectorBuilder::on_alloc
+    /// [`Pool`]: crate::Pool
+    pub fn on_drop<F>(mut self, on_drop: F) -> CollectorBuilder
+    where
+        F: Fn(&AllocInfo) + Send + Sync + 'static,
+    {
+        self.on_drop = Some(Box::new(on_drop));
+        self
+    }
+
+    /// Installs a hook which is called when a [`SharedCell`] writer has spent
+    /// `spins` iterations waiting for readers to finish, in
+    /// [`SharedCell::replace`] or [`SharedCell::set`].
+    ///
+    /// Writers only have to wait while a reader is in the middle of
+    /// [`SharedCell::get`], which normally takes a handful of instructions. A
+    /// long wait usually means that a reader thread was preempted inside
+    /// `get`, leaving the writer (e.g. a GUI thread) frozen until it is
+    /// scheduled again. The hook makes that failure mode visible. It is
+    /// called at most once per call to `replace`, on the writer's thread,
+    /// while the writer keeps waiting; the hook of the collector which owns
+    /// the value being replaced is the one consulted.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Shared, SharedCell};
+    ///
+    /// let collector = Collector::builder()
+    ///     .on_writer_stall(1_000_000, |info| {
+    ///         eprintln!("SharedCell<{}> writer stalled", info.type_name);
+    ///     })
+    ///     .build();
+    ///
+    /// let cell = SharedCell::new(Shared::new(&collector.handle(), 1));
+    /// cell.set(Shared::new(&collector.handle(), 2));
+    /// ```
+    ///
+    /// [`SharedCell`]: crate::SharedCell
+    /// [`SharedCell::get`]: crate::SharedCell::get
+    /// [`SharedCell::replace`]: crate::SharedCell::replace
+    /// [`SharedCell::set`]: crate::SharedCell::set
+    pub fn on_writer_stall<F>(mut self, spins: usize, on_writer_stall: F) -> CollectorBuilder
+    where
+        F: Fn(&StallInfo) + Send + Sync + 'static,
+    {
+        self.on_writer_stall = Some((spins, Box::new(on_writer_stall)));
+        self
+    }
+
+    /// Installs a hook which is called when the number of allocations
+    /// waiting in the [`Collector`]'s drop queue reaches `threshold`, as an
+    /// early warning that the [`Collector`] is not being run often enough.
+    ///
+    /// The hook is called on the thread which queued the allocation that
+    /// reached the threshold, which may be a real-time thread, so it should
+    /// do no more than e.g. set a flag. It is called again each time the
+    /// queue grows back to the threshold after having been collected. If
+    /// counters are disabled (see [`counters`]), keeping track of the
+    /// queue's length costs an atomic read-modify-write operation each time
+    /// an allocation is queued or collected.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// let starved = Arc::new(AtomicBool::new(false));
+    /// let flag = starved.clone();
+    /// let mut collector = Collector::builder()
+    ///     .on_backlog(2, move |_| flag.store(true, Ordering::Relaxed))
+    ///     .build();
+    ///
+    /// let handle = collector.handle();
+    /// drop(Owned::new(&handle, 1));
+    /// assert!(!starved.load(Ordering::Relaxed));
+    /// drop(Owned::new(&handle, 2));
+    /// assert!(starved.load(Ordering::Relaxed));
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`counters`]: crate::CollectorBuilder::counters
+    pub fn on_backlog<F>(mut self, threshold: usize, on_backlog: F) -> CollectorBuilder
+    where
+        F: Fn(&BacklogInfo) + Send + Sync + 'static,
+    {
+        self.on_backlog = Some((threshold, Box::new(on_backlog)));
+        self
+    }
+
+    /// Installs a watchdog which calls `on_overdue` if garbage has been
+    /// waiting in the [`Collector`]'s drop queue for longer than `limit`,
+    /// e.g. because the thread running the [`Collector`] has stalled.
+    /// [`Collector::is_overdue`] and [`Handle::is_overdue`] can be polled
+    /// instead of, or as well as, relying on the hook.
+    ///
+    /// Waiting time is measured on `clock`, to the microsecond, from
+    /// checkpoints which the [`Collector`] takes as it works through the
+    /// queue. A [`Collector`] which keeps up with a steady backlog, e.g. by
+    /// calling [`Collector::collect_n`], is therefore not reported, while one
+    /// which falls behind or stalls is. The estimate may exceed the true
+    /// waiting time by up to the time the [`Collector`] takes to get through
+    /// the backlog. The hook is called at most once each time the limit is
+    /// exceeded, on the thread which queues an allocation after that, which
+    /// may be a real-time thread. The clock is read each time an allocation
+    /// is queued, so it must be cheap and safe to call from a real-time
+    /// thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let start = Instant::now();
+    /// let collector = Collector::builder()
+    ///     .watchdog(move || start.elapsed(), Duration::from_millis(100), |info| {
+    ///         eprintln!("{} allocations waiting for {:?}", info.pending, info.waited);
+    ///     })
+    ///     .build();
+    ///
+    /// drop(Owned::new(&collector.handle(), 3));
+    /// assert!(!collector.is_overdue());
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`Collector::collect_n`]: crate::Collector::collect_n
+    /// [`Collector::is_overdue`]: crate::Collector::is_overdue
+    /// [`Handle::is_overdue`]: crate::Handle::is_overdue
+    pub fn watchdog<C, F>(mut self, clock: C, limit: Duration, on_overdue: F) -> CollectorBuilder
+    where
+        C: Clock + Send + Sync + 'static,
+        F: Fn(&OverdueInfo) + Send + Sync + 'static,
+    {
+        self.watchdog = Some(Watchdog {
+            clock: Box::new(clock),
+            limit,
+            on_overdue: Box::new(on_overdue),
+            since: AtomicUsize::new(0),
+            fired: AtomicBool::new(false),
+            checkpoint: core::cell::Cell::new(0),
+            unreached: core::cell::Cell::new(0),
+        });
+        self
+    }
+
+    /// Installs a [`Clock`] used to timestamp allocations as they are queued,
+    /// so that the [`Collector`] can measure how long they wait before being
+    /// collected. The results are available from [`Collector::latency_stats`]
+    /// and [`PassInfo::latency`]. Only available with the `latency-stats`
+    /// feature.
+    ///
+    /// The clock is read on every thread which queues an allocation, so it
+    /// must be cheap and safe to call from a real-time thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    /// use std::time::Instant;
+    ///
+    /// let start = Instant::now();
+    /// let mut collector = Collector::builder()
+    ///     .reclamation_clock(move || start.elapsed())
+    ///     .build();
+    ///
+    /// drop(Owned::new(&collector.handle(), 3));
+    /// collector.collect();
+    ///
+    /// let latency = collector.latency_stats().unwrap();
+    /// assert_eq!(latency.count, 1);
+    /// println!("garbage waited up to {:?}", latency.max);
+    /// ```
+    ///
+    /// [`Clock`]: crate::Clock
+    /// [`Collector`]: crate::Collector
+    /// [`Collector::latency_stats`]: crate::Collector::latency_stats
+    /// [`PassInfo::latency`]: crate::PassInfo::latency
+    #[cfg(feature = "latency-stats")]
+    pub fn reclamation_clock<C>(mut self, clock: C) -> CollectorBuilder
+    where
+        C: Clock + Send + Sync + 'static,
+    {
+        self.reclamation_clock = Some(Box::new(clock));
+        self
+    }
+
+    /// Limits the number of allocations which may be waiting in the
+    /// [`Collector`]'s drop queue before producers are asked to back off.
+    /// Defaults to no limit.
+    ///
+    /// With a limit set, [`Node::try_queue_drop`] refuses to queue further
+    /// allocations once the limit is reached, and [`Handle::is_congested`]
+    /// returns `true`, so that a stalled [`Collector`] degrades gracefully
+    /// instead of letting memory grow without bound. Allocations dropped
+    /// through infallible means such as [`Owned`] and [`Shared`] are still
+    /// queued, but they count toward the limit. If counters are disabled,
+    /// keeping track of the queue's length costs an atomic read-modify-write
+    /// operation each time an allocation is queued or collected.
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`Node::try_queue_drop`]: crate::Node::try_queue_drop
+    /// [`Handle::is_congested`]: crate::Handle::is_congested
+    /// [`Owned`]: crate::Owned
+    /// [`Shared`]: crate::Shared
+    pub fn max_pending(mut self, max_pending: usize) -> CollectorBuilder {
+        self.max_pending = Some(max_pending);
+        self
+    }
+
+    /// Splits the normal-priority drop queue into `shards` separate queues,
+    /// each of which threads are assigned to based on their identity.
+    /// Defaults to 1, and values below 1 are treated as 1.
+    ///
+    /// Every allocation queued by any thread goes through a single atomic
+    /// swap on the queue's tail, so with many threads releasing garbage at
+    /// once, that cache line becomes a point of contention. Sharding spreads
+    /// these swaps out at the cost of a few more checks per collection.
+    /// Allocations queued by different threads are no longer collected in
+    /// the order in which they were queued; the order within a single
+    /// thread is unaffected.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let mut collector = Collector::builder().shards(8).build();
+    /// let handle = collector.handle();
+    ///
+    /// let workers: Vec<_> = (0..8)
+    ///     .map(|_| {
+    ///         let handle = handle.clone();
+    ///         std::thread::spawn(move || {
+    ///             for i in 0..100 {
+    ///                 drop(Owned::new(&handle, i));
+    ///             }
+    ///         })
+    ///     })
+    ///     .collect();
+    /// for worker in workers {
+    ///     worker.join().unwrap();
+    /// }
+    ///
+    /// collector.collect();
+    /// assert_eq!(collector.alloc_count(), 0);
+    /// ```
+    pub fn shards(mut self, shards: usize) -> CollectorBuilder {
+        self.shards = shards.max(1);
+        self
+    }
+
+    /// Constructs a [`Collector`] with this configuration.
+    ///
+    /// [`Collector`]: crate::Collector
+    pub fn build(self) -> Collector {
+        let lanes: Box<[Lane]> = (0..NORMAL + self.shards).map(|_| Lane::new()).collect();
+
+        let inner = Box::into_raw(Box::new(CollectorInner {
+            counters: self.counters,
+            handles: CachePadded::new(AtomicUsize::new(0)),
+            allocs: StripedCounter::new(),
+            epoch: AtomicUsize::new(0),
+            tails: lanes.iter().map(|lane| CachePadded::new(AtomicPtr::new(lane.stub))).collect(),
+            allocator: self.allocator,
+            on_alloc: self.on_alloc,
+            on_drop: self.on_drop,
+            on_writer_stall: self.on_writer_stall,
+            on_backlog: self.on_backlog,
+            watchdog: self.watchdog,
+            pending: CachePadded::new(AtomicUsize::new(0)),
+            freed_bytes: AtomicUsize::new(0),
+            peak_pending: AtomicUsize::new(0),
+            peak_allocs: AtomicUsize::new(0),
+            #[cfg(feature = "byte-stats")]
+            live_bytes: StripedCounter::new(),
+            #[cfg(feature = "byte-stats")]
+            pending_bytes: CachePadded::new(AtomicUsize::new(0)),
+            #[cfg(feature = "latency-stats")]
+            reclamation_clock: self.reclamation_clock,
+            #[cfg(feature = "latency-stats")]
+            latency: core::cell::Cell::new(LatencyTotals::default()),
+            #[cfg(feature = "latency-stats")]
+            last_latency: core::cell::Cell::new(None),
+            max_pending: self.max_pending,
+            #[cfg(feature = "diagnostics")]
+            handle_registry: SpinLock::new(HandleRegistry {
+                next_id: 0,
+                live: BTreeMap::new(),
+            }),
+            #[cfg(feature = "track-allocations")]
+            alloc_registry: SpinLock::new(BTreeMap::new()),
+            #[cfg(feature = "thread-stats")]
+            thread_stats: ThreadRegistry::new(),
+            #[cfg(feature = "std")]
+            waiter: WaiterSlot::new(),
+        }));
+
+        Collector {
+            lanes,
+            shard: 0,
+            inner,
+            handle: ManuallyDrop::new(Handle {
+                collector: inner,
+                #[cfg(feature = "diagnostics")]
+                id: usize::MAX,
+            }),
+            drop_policy: self.drop_policy,
+            executor: self.executor,
+            profiler: self.profiler,
+        }
+    }
+}
+
+unsafe impl Send for Collector {}
+
+// A destructor panicking during collection leaks the node being dropped but
+// leaves the queue itself in a consistent state.
+impl UnwindSafe for Collector {}
+impl RefUnwindSafe for Collector {}
+impl Unpin for Collector {}
+
+impl Default for Collector {
+    fn default() -> Collector {
+        Collector::new()
+    }
+}
+
+impl Collector {
+    /// Constructs a new `Collector`.
+    pub fn new() -> Collector {
+        CollectorBuilder::new().build()
+    }
+
+    /// Constructs a new `Collector` whose allocations are served by the given
+    /// [`NodeAllocator`]. This is shorthand for
+    /// `Collector::builder().allocator(allocator).build()`.
+    ///
+    /// [`NodeAllocator`]: crate::NodeAllocator
+    pub fn new_in<A: NodeAllocator + 'static>(allocator: A) -> Collector {
+        CollectorBuilder::new().allocator(allocator).build()
+    }
+
+    /// Constructs a [`CollectorBuilder`] for configuring a new `Collector`.
+    ///
+    /// [`CollectorBuilder`]: crate::CollectorBuilder
+    pub fn builder() -> CollectorBuilder {
+        CollectorBuilder::new()
+    }
+
+    /// Gets a [`Handle`] to this `Collector`.
+    ///
+    /// [`Handle`]: crate::Handle
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn handle(&self) -> Handle {
+        unsafe { Handle::new(self.inner) }
+    }
+
+    /// Borrows a [`Handle`] to this `Collector` which is owned by the
+    /// `Collector` itself, avoiding the cost of creating a new one.
+    ///
+    /// The borrowed [`Handle`] is not included in [`handle_count`], and does
+    /// not prevent [`try_cleanup`] from succeeding. Cloning it produces a new
+    /// [`Handle`] as usual.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let mut collector = Collector::new();
+    /// let x = Owned::new(collector.handle_ref(), 3);
+    /// assert_eq!(collector.handle_count(), 0);
+    ///
+    /// drop(x);
+    /// collector.collect();
+    /// assert!(collector.try_cleanup().is_ok());
+    /// ```
+    ///
+    /// [`Handle`]: crate::Handle
+    /// [`handle_count`]: crate::Collector::handle_count
+    /// [`try_cleanup`]: crate::Collector::try_cleanup
+    pub fn handle_ref(&self) -> &Handle {
+        &self.handle
+    }
+
+    /// Drops all of the garbage in the queue.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Handle, Owned};
+    /// use core::mem::drop;
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    /// let x = Owned::new(&handle, 1);
+    /// let y = Owned::new(&handle, 2);
+    /// let z = Owned::new(&handle, 3);
+    ///
+    /// assert_eq!(collector.alloc_count(), 3);
+    ///
+    /// drop(x);
+    /// drop(y);
+    /// drop(z);
+    /// collector.collect();
+    ///
+    /// assert_eq!(collector.alloc_count(), 0);
+    /// ```
+    pub fn collect(&mut self) {
+        self.drain();
+    }
+
+    /// Drops all of the garbage in the queue like [`collect`], and returns a
+    /// [`CollectReport`] describing the pass, with its duration measured on
+    /// the given [`Clock`].
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    /// use std::time::Instant;
+    ///
+    /// let start = Instant::now();
+    /// let clock = || start.elapsed();
+    ///
+    /// let mut collector = Collector::new();
+    /// drop(Owned::new(&collector.handle(), [0u8; 1024]));
+    ///
+    /// let report = collector.collect_report(&clock);
+    /// assert_eq!(report.dropped, 1);
+    /// assert!(report.bytes >= 1024);
+    /// println!("reclaimed {} bytes in {:?}", report.bytes, report.elapsed);
+    /// ```
+    ///
+    /// [`collect`]: crate::Collector::collect
+    /// [`CollectReport`]: crate::CollectReport
+    /// [`Clock`]: crate::Clock
+    pub fn collect_report<C: Clock + ?Sized>(&mut self, clock: &C) -> CollectReport {
+        let freed_bytes = unsafe { &(*self.inner).freed_bytes };
+
+        let start = clock.now();
+        let freed_before = freed_bytes.load(Ordering::Relaxed);
+        let dropped = self.drain();
+        let bytes = freed_bytes.load(Ordering::Relaxed).wrapping_sub(freed_before);
+
+        CollectReport {
+            dropped,
+            bytes,
+            elapsed: clock.now().saturating_sub(start),
+        }
+    }
+
+    /// Drops all of the garbage in the queue, returning the number of
+    /// allocations removed from it.
+    fn drain(&mut self) -> usize {
+        self.begin_pass();
+
+        let mut dropped = 0;
+        if self.executor.is_some() {
+            while self.collect_one() {
+                dropped += 1;
+            }
+        } else {
+            dropped = self.collect_batched();
+        }
+
+        self.finish_pass();
+        self.end_pass(dropped, true);
+        dropped
+    }
+
+    /// Drops garbage from the queue until either the queue is empty or the
+    /// given [`Clock`] reaches `deadline`. Returns true if the queue was
+    /// fully drained.
+    ///
+    /// The deadline is checked before each drop, so a single expensive
+    /// destructor can still cause it to be overrun.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    /// use core::cell::Cell;
+    /// use core::time::Duration;
+    ///
+    /// let ticks = Cell::new(0);
+    /// let clock = || {
+    ///     ticks.set(ticks.get() + 1);
+    ///     Duration::from_millis(ticks.get())
+    /// };
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    /// for i in 0..10 {
+    ///     drop(Owned::new(&handle, i));
+    /// }
+    ///
+    /// assert!(!collector.collect_until(&clock, Duration::from_millis(5)));
+    /// assert_eq!(collector.alloc_count(), 6);
+    ///
+    /// assert!(collector.collect_until(&clock, Duration::from_secs(1)));
+    /// assert_eq!(collector.alloc_count(), 0);
+    /// ```
+    ///
+    /// [`Clock`]: crate::Clock
+    pub fn collect_until<C: Clock + ?Sized>(&mut self, clock: &C, deadline: Duration) -> bool {
+        self.begin_pass();
+
+        let mut dropped = 0;
+        while clock.now() < deadline {
+            if !self.collect_one() {
+                self.finish_pass();
+                self.end_pass(dropped, true);
+                return true;
+            }
+            dropped += 1;
+        }
+
+        self.end_pass(dropped, false);
+        false
+    }
+
+    /// Drops garbage from the queue until either the queue is empty or
+    /// `budget` has elapsed on the given [`Clock`]. Returns true if the queue
+    /// was fully drained. This is equivalent to calling [`collect_until`]
+    /// with a deadline of `clock.now() + budget`.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let start = Instant::now();
+    /// let clock = || start.elapsed();
+    ///
+    /// let mut collector = Collector::new();
+    /// drop(Owned::new(&collector.handle(), vec![0.0f32; 4096]));
+    ///
+    /// // Once per UI tick:
+    /// collector.collect_for(&clock, Duration::from_micros(500));
+    /// ```
+    ///
+    /// [`Clock`]: crate::Clock
+    /// [`collect_until`]: crate::Collector::collect_until
+    pub fn collect_for<C: Clock + ?Sized>(&mut self, clock: &C, budget: Duration) -> bool {
+        let deadline = clock.now().saturating_add(budget);
+        self.collect_until(clock, deadline)
+    }
+
+    /// Waits until at least one allocation has been queued, then drops
+    /// everything in the queue as with [`collect`]. Only available with the
+    /// `std` feature.
+    ///
+    /// While waiting, the thread is parked rather than polling. Queueing an
+    /// allocation only costs an extra atomic load unless the collector is
+    /// parked, in which case the queueing thread unparks it.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    ///
+    /// let audio = std::thread::spawn(move || {
+    ///     drop(Owned::new(&handle, [0.0f32; 256]));
+    /// });
+    ///
+    /// // Sleeps until the audio thread drops its buffer.
+    /// collector.collect_blocking();
+    /// audio.join().unwrap();
+    /// ```
+    ///
+    /// [`collect`]: crate::Collector::collect
+    #[cfg(feature = "std")]
+    pub fn collect_blocking(&mut self) {
+        while self.queue_is_empty() {
+            if self.wait_for(Waiter::Thread(std::thread::current())) {
+                std::thread::park();
+            }
+        }
+
+        self.collect();
+    }
+
+    /// Drops all of the garbage in the queue like [`collect`], but catches
+    /// any panics from destructors instead of propagating them, and keeps
+    /// collecting. The payloads of the caught panics are returned in the
+    /// order they occurred. Only available with the `std` feature.
+    ///
+    /// An allocation whose destructor panicked is leaked, but no longer
+    /// counted as live, so the `Collector` can still be cleaned up.
+    /// Allocations handed off to a [`DropExecutor`] are dropped wherever the
+    /// executor runs them, so their panics are not caught here.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// struct Faulty;
+    ///
+    /// impl Drop for Faulty {
+    ///     fn drop(&mut self) {
+    ///         panic!("faulty destructor");
+    ///     }
+    /// }
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    /// drop(Owned::new(&handle, Faulty));
+    /// drop(Owned::new(&handle, 1));
+    ///
+    /// let panics = collector.collect_catching();
+    /// assert_eq!(panics.len(), 1);
+    /// assert_eq!(panics[0].downcast_ref::<&str>(), Some(&"faulty destructor"));
+    /// assert_eq!(collector.alloc_count(), 0);
+    /// ```
+    ///
+    /// [`collect`]: crate::Collector::collect
+    /// [`DropExecutor`]: crate::DropExecutor
+    #[cfg(feature = "std")]
+    pub fn collect_catching(&mut self) -> Vec<Box<dyn core::any::Any + Send>> {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        self.begin_pass();
+
+        let mut panics = Vec::new();
+        let mut dropped = 0;
+        while let Some(node) = self.pop() {
+            let job = unsafe { DropJob::new(node, self.inner) };
+            if let Some(executor) = &mut self.executor {
+                executor.execute(job);
+            } else if let Err(panic) = catch_unwind(AssertUnwindSafe(|| job.run())) {
+                unsafe { (*self.inner).forget(node) };
+                panics.push(panic);
+            }
+            dropped += 1;
+        }
+
+        self.finish_pass();
+        self.end_pass(dropped, true);
+        panics
+    }
+
+    /// Returns a future which waits until at least one allocation has been
+    /// queued, then drops everything in the queue as with [`collect`]. This
+    /// is the asynchronous counterpart of [`collect_blocking`], for running
+    /// the collector on an async executor. Only available with the `async`
+    /// feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::Collector;
+    ///
+    /// async fn collector_task(mut collector: Collector) {
+    ///     loop {
+    ///         collector.collect_async().await;
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`collect`]: crate::Collector::collect
+    /// [`collect_blocking`]: crate::Collector::collect_blocking
+    #[cfg(feature = "async")]
+    pub fn collect_async(&mut self) -> CollectAsync<'_> {
+        CollectAsync { collector: self }
+    }
+
+    /// Registers a [`Waker`] to be woken once an allocation is queued. If
+    /// anything has already been queued since the queues were last drained,
+    /// it is woken immediately. Only the most recently registered waker is
+    /// woken, and only once. Only available with the `async` feature.
+    ///
+    /// [`Waker`]: core::task::Waker
+    #[cfg(feature = "async")]
+    pub fn register_waker(&self, waker: &core::task::Waker) {
+        if !self.wait_for(Waiter::Task(waker.clone())) {
+            waker.wake_by_ref();
+        }
+    }
+
+    /// Arranges for `waiter` to be woken once a node is queued. Returns false
+    /// instead if one already has been.
+    #[cfg(feature = "std")]
+    fn wait_for(&self, waiter: Waiter) -> bool {
+        let inner = unsafe { &*self.inner };
+        inner.waiter.register(waiter);
+        if !self.queue_is_empty() {
+            inner.waiter.cancel();
+            return false;
+        }
+
+        true
+    }
+
+    /// Returns true if nothing has been queued since the queues were last
+    /// drained. This only reads the queues, without dropping anything or
+    /// otherwise modifying them, so it is cheap enough to decide whether a
+    /// collection pass is worth running at all.
+    ///
+    /// Allocations may be queued concurrently, so the result can be out of
+    /// date as soon as it is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    /// assert!(collector.queue_is_empty());
+    ///
+    /// drop(Owned::new(&handle, 3));
+    /// assert!(!collector.queue_is_empty());
+    ///
+    /// collector.collect();
+    /// assert!(collector.queue_is_empty());
+    /// ```
+    pub fn queue_is_empty(&self) -> bool {
+        let tails = unsafe { &(*self.inner).tails };
+        self.lanes
+            .iter()
+            .zip(tails.iter())
+            .all(|(lane, tail)| {
+                lane.head == lane.stub && tail.load(Ordering::SeqCst) == lane.stub
+            })
+    }
+
+    /// Drops at most `max` allocations from the queue, returning the number
+    /// which were dropped. This allows collecting a large amount of garbage
+    /// to be spread out over several calls, e.g. one per GUI frame.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    /// for i in 0..10 {
+    ///     drop(Owned::new(&handle, i));
+    /// }
+    ///
+    /// assert_eq!(collector.collect_n(4), 4);
+    /// assert_eq!(collector.alloc_count(), 6);
+    ///
+    /// assert_eq!(collector.collect_n(100), 6);
+    /// assert_eq!(collector.alloc_count(), 0);
+    /// ```
+    pub fn collect_n(&mut self, max: usize) -> usize {
+        self.begin_pass();
+
+        let mut dropped = 0;
+        while dropped < max {
+            if !self.collect_one() {
+                self.finish_pass();
+                self.end_pass(dropped, true);
+                return dropped;
+            }
+            dropped += 1;
+        }
+
+        self.end_pass(dropped, false);
+        dropped
+    }
+
+    fn begin_pass(&mut self) {
+        let inner = unsafe { &*self.inner };
+        #[cfg(feature = "latency-stats")]
+        inner.latency.set(LatencyTotals::default());
+        inner.peak_pending.store(self.peak_pending_count(), Ordering::Relaxed);
+        inner.peak_allocs.store(self.peak_alloc_count(), Ordering::Relaxed);
+
+        if let Some(profiler) = &mut self.profiler {
+            profiler.begin();
+        }
+    }
+
+    fn finish_pass(&mut self) {
+        unsafe {
+            (*self.inner).epoch.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    fn end_pass(&mut self, dropped: usize, completed: bool) {
+        #[cfg(feature = "latency-stats")]
+        let latency = {
+            let inner = unsafe { &*self.inner };
+            let totals = inner.latency.get();
+            let latency = if totals.count > 0 {
+                Some(LatencyStats {
+                    count: totals.count,
+                    max: totals.max,
+                    mean: totals.total / totals.count as u32,
+                })
+            } else {
+                None
+            };
+            if latency.is_some() {
+                inner.last_latency.set(latency);
+            }
+            latency
+        };
+
+        if self.profiler.is_some() {
+            let counters = unsafe { (*self.inner).counters };
+            let pass = PassInfo {
+                dropped,
+                completed,
+                live_allocations: if counters { Some(self.alloc_count()) } else { None },
+                epoch: self.collection_epoch(),
+                #[cfg(feature = "latency-stats")]
+                latency,
+            };
+            if let Some(profiler) = &mut self.profiler {
+                profiler.end(&pass);
+            }
+        }
+    }
+
+    /// Attempts to drop the first allocation in the queue. If successful,
+    /// returns true; otherwise returns false.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Handle, Owned};
+    /// use core::mem::drop;
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    /// let x = Owned::new(&handle, 1);
+    /// let y = Owned::new(&handle, 2);
+    /// let z = Owned::new(&handle, 3);
+    ///
+    /// assert_eq!(collector.alloc_count(), 3);
+    ///
+    /// drop(x);
+    /// drop(y);
+    /// drop(z);
+    ///
+    /// assert!(collector.collect_one());
+    /// assert!(collector.collect_one());
+    /// assert!(collector.collect_one());
+    ///
+    /// assert!(!collector.collect_one());
+    /// assert_eq!(collector.alloc_count(), 0);
+    /// ```
+    pub fn collect_one(&mut self) -> bool {
+        if let Some(node) = self.pop() {
+            let job = unsafe { DropJob::new(node, self.inner) };
+            if let Some(executor) = &mut self.executor {
+                executor.execute(job);
+            } else {
+                job.run();
+            }
+
+            return true;
+        }
+
+        false
+    }
+
+    /// Removes everything from the queue without dropping it, adding a
+    /// [`DropJob`] for each allocation to `jobs` instead. Returns the number
+    /// of allocations removed.
+    ///
+    /// This bypasses any installed [`DropExecutor`]. The allocations are
+    /// dropped once their jobs are run or dropped, and until then they are
+    /// still counted by [`alloc_count`], so jobs can e.g. be held on to for a
+    /// while before being released in bulk.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    /// drop(Owned::new(&handle, 1));
+    /// drop(Owned::new(&handle, 2));
+    ///
+    /// let mut jobs = Vec::new();
+    /// assert_eq!(collector.collect_into(&mut jobs), 2);
+    /// assert_eq!(collector.alloc_count(), 2);
+    ///
+    /// drop(jobs);
+    /// assert_eq!(collector.alloc_count(), 0);
+    /// ```
+    ///
+    /// [`DropJob`]: crate::DropJob
+    /// [`DropExecutor`]: crate::DropExecutor
+    /// [`alloc_count`]: crate::Collector::alloc_count
+    pub fn collect_into<E: Extend<DropJob>>(&mut self, jobs: &mut E) -> usize {
+        self.begin_pass();
+
+        let mut removed = 0;
+        while let Some(node) = self.pop() {
+            jobs.extend(Some(unsafe { DropJob::new(node, self.inner) }));
+            removed += 1;
+        }
+
+        self.finish_pass();
+        self.end_pass(removed, true);
+        removed
+    }
+
+    /// Replaces the [`DropExecutor`] to which collected allocations are handed
+    /// off, returning the previous one. Passing `None` makes the `Collector`
+    /// drop allocations inline on the collecting thread again.
+    ///
+    /// Allocations already handed to the previous executor are unaffected.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, DropJob, Owned};
+    /// use std::sync::mpsc::channel;
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    ///
+    /// let (sender, receiver) = channel::<DropJob>();
+    /// collector.set_executor(Some(Box::new(move |job| sender.send(job).unwrap())));
+    ///
+    /// drop(Owned::new(&handle, 1));
+    /// collector.collect();
+    /// assert_eq!(collector.alloc_count(), 1);
+    ///
+    /// receiver.recv().unwrap().run();
+    /// assert_eq!(collector.alloc_count(), 0);
+    ///
+    /// assert!(collector.set_executor(None).is_some());
+    /// ```
+    ///
+    /// [`DropExecutor`]: crate::DropExecutor
+    pub fn set_executor(
+        &mut self,
+        executor: Option<Box<dyn DropExecutor>>,
+    ) -> Option<Box<dyn DropExecutor>> {
+        core::mem::replace(&mut self.executor, executor)
+    }
+
+    /// Adds the time a node spent in the queue to the current pass's totals.
+    #[cfg(feature = "latency-stats")]
+    unsafe fn record_latency(&self, node: *mut NodeHeader) {
+        let inner = &*self.inner;
+        if let Some(clock) = &inner.reclamation_clock {
+            let now = clock.now().as_nanos() as u64;
+            let waited = Duration::from_nanos(now.saturating_sub((*node).queued_at));
+
+            let mut totals = inner.latency.get();
+            totals.count += 1;
+            totals.total += waited;
+            totals.max = totals.max.max(waited);
+            inner.latency.set(totals);
+        }
+    }
+
+    /// Marks the node at the tail of each lane, so that once they have all
+    /// been removed, it is known that nothing left in the queue was queued
+    /// before now.
+    fn checkpoint(&mut self, watchdog: &Watchdog) {
+        let tails = unsafe { &(*self.inner).tails };
+
+        // The clock is read first, so that anything queued before then is at
+        // or before the marked nodes.
+        watchdog.checkpoint.set(watchdog.now());
+        let mut unreached = 0;
+        for (lane, tail) in self.lanes.iter_mut().zip(tails.iter()) {
+            if lane.mark(tail) {
+                unreached += 1;
+            }
+        }
+        watchdog.unreached.set(unreached);
+    }
+
+    /// Called when one of the nodes marked by the last checkpoint has been
+    /// removed from its lane.
+    fn passed_mark(&mut self) {
+        if let Some(watchdog) = unsafe { &(*self.inner).watchdog } {
+            let unreached = watchdog.unreached.get() - 1;
+            watchdog.unreached.set(unreached);
+            if unreached == 0 {
+                watchdog.since.store(watchdog.checkpoint.get(), Ordering::Relaxed);
+                if watchdog.waited() <= watchdog.limit {
+                    watchdog.fired.store(false, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    /// Removes the next node from the highest-priority nonempty queue. Shards
+    /// of the normal-priority queue are drained one at a time, picking up
+    /// where the last call left off.
+    fn pop(&mut self) -> Option<*mut NodeHeader> {
+        let tails = unsafe { &(*self.inner).tails };
+        let shards = self.lanes.len() - NORMAL;
+        let start = self.shard;
+        let order = (0..shards).map(|i| NORMAL + (start + i) % shards);
+        for index in core::iter::once(URGENT).chain(order) {
+            let marked = !self.lanes[index].mark.is_null();
+            let popped = unsafe { self.lanes[index].pop(&tails[index]) };
+            if marked && self.lanes[index].mark.is_null() {
+                self.passed_mark();
+            }
+
+            if let Some(node) = popped {
+                if index != URGENT {
+                    self.shard = index - NORMAL;
+                }
+                unsafe {
+                    NodeHeader::check(node, QUEUED);
+                    // The link is no longer needed for the queue, so point it
+                    // back at the collector for the node's drop function.
+                    (*node).link.collector = self.inner;
+                    if (*self.inner).tracks_pending() {
+                        (*self.inner).pending.fetch_sub(1, Ordering::Relaxed);
+                    }
+                    (*self.inner).record_dequeued(node);
+                    #[cfg(feature = "latency-stats")]
+                    self.record_latency(node);
+                }
+                if let Some(watchdog) = unsafe { &(*self.inner).watchdog } {
+                    if watchdog.unreached.get() == 0 {
+                        self.checkpoint(watchdog);
+                    }
+                }
+                return Some(node);
+            }
+        }
+
+        None
+    }
+
+    /// Drops everything in the queue inline, returning the number of
+    /// allocations dropped. Used by [`collect`] when no executor is
+    /// installed.
+    ///
+    /// Tearing down a large collection typically queues many allocations of
+    /// the same type in a row. Such runs are dropped in a tight loop through
+    /// a single drop function, and the allocation count is updated once per
+    /// run rather than once per allocation.
+    ///
+    /// [`collect`]: crate::Collector::collect
+    fn collect_batched(&mut self) -> usize {
+        /// Updates the allocation count for the nodes freed in the run so far,
+        /// even if a destructor panics.
+        struct Run {
+            inner: *mut CollectorInner,
+            len: usize,
+        }
+
+        impl Drop for Run {
+            fn drop(&mut self) {
+                unsafe {
+                    if (*self.inner).counters {
+                        (*self.inner).allocs.sub(self.len, Ordering::Relaxed);
+                    }
+                }
+            }
+        }
+
+        let mut dropped = 0;
+        let mut next = self.pop();
+        while let Some(mut node) = next {
+            let drop = unsafe { (*node).drop };
+            let mut run = Run { inner: self.inner, len: 0 };
+            next = loop {
+                if unsafe { drop(node) } {
+                    run.len += 1;
+                }
+                dropped += 1;
+
+                // Comparing function pointers can give false negatives (e.g.
+                // due to duplicated code), which just end the run early.
+                match self.pop() {
+                    Some(following) if unsafe { (*following).drop } as usize == drop as usize => {
+                        node = following;
+                    }
+                    following => break following,
+                }
+            };
+        }
+
+        dropped
+    }
+
+    /// Gets the number of live [`Handle`]s to this `Collector`.
+    ///
+    /// Always returns 0 if counters are disabled (see
+    /// [`CollectorBuilder::counters`]).
+    ///
+    /// [`Handle`]: crate::Handle
+    /// [`CollectorBuilder::counters`]: crate::CollectorBuilder::counters
+    pub fn handle_count(&self) -> usize {
+        unsafe { (*self.inner).handles.load(Ordering::Relaxed) }
+    }
+
+    /// Gets the number of live allocations associated with this `Collector`.
+    ///
+    /// Always returns 0 if counters are disabled (see
+    /// [`CollectorBuilder::counters`]).
+    ///
+    /// [`CollectorBuilder::counters`]: crate::CollectorBuilder::counters
+    pub fn alloc_count(&self) -> usize {
+        unsafe { (*self.inner).allocs.load(Ordering::Relaxed) }
+    }
+
+    /// Gets the approximate number of allocations waiting in the drop queue.
+    /// Comparing this over time shows whether the `Collector` is keeping up
+    /// with the threads producing garbage.
+    ///
+    /// The count is maintained with relaxed atomics, so it may lag slightly
+    /// behind allocations which are being queued concurrently. Always returns
+    /// 0 if counters are disabled (see [`CollectorBuilder::counters`]) and no
+    /// limit has been set with [`CollectorBuilder::max_pending`].
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    ///
+    /// drop(Owned::new(&handle, 1));
+    /// drop(Owned::new(&handle, 2));
+    /// assert_eq!(collector.pending_count(), 2);
+    ///
+    /// collector.collect();
+    /// assert_eq!(collector.pending_count(), 0);
+    /// ```
+    ///
+    /// [`CollectorBuilder::counters`]: crate::CollectorBuilder::counters
+    /// [`CollectorBuilder::max_pending`]: crate::CollectorBuilder::max_pending
+    pub fn pending_count(&self) -> usize {
+        unsafe { (*self.inner).pending.load(Ordering::Relaxed) }
+    }
+
+    /// Gets the largest number of allocations seen waiting in the drop
+    /// queue, a measure of how far the `Collector` has fallen behind at
+    /// worst.
+    ///
+    /// The queue's length is sampled at the start of each collection pass,
+    /// when it is typically at its longest, and when this is called. See
+    /// [`pending_count`] for when it is tracked at all.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    ///
+    /// for i in 0..3 {
+    ///     drop(Owned::new(&handle, i));
+    /// }
+    /// collector.collect();
+    ///
+    /// assert_eq!(collector.pending_count(), 0);
+    /// assert_eq!(collector.peak_pending_count(), 3);
+    /// ```
+    ///
+    /// [`pending_count`]: crate::Collector::pending_count
+    pub fn peak_pending_count(&self) -> usize {
+        let peak = unsafe { (*self.inner).peak_pending.load(Ordering::Relaxed) };
+        peak.max(self.pending_count())
+    }
+
+    /// Gets the largest number of live allocations seen associated with this
+    /// `Collector`. Like [`peak_pending_count`], this is sampled at the
+    /// start of each collection pass and when this is called, so shorter
+    /// peaks in between may be missed. Always returns 0 if counters are
+    /// disabled (see [`CollectorBuilder::counters`]).
+    ///
+    /// [`peak_pending_count`]: crate::Collector::peak_pending_count
+    /// [`CollectorBuilder::counters`]: crate::CollectorBuilder::counters
+    pub fn peak_alloc_count(&self) -> usize {
+        let peak = unsafe { (*self.inner).peak_allocs.load(Ordering::Relaxed) };
+        peak.max(self.alloc_count())
+    }
+
+    /// Returns `true` if garbage has been waiting in the drop queue for
+    /// longer than the limit set with [`CollectorBuilder::watchdog`]. Always
+    /// returns `false` if no watchdog is installed.
+    ///
+    /// [`CollectorBuilder::watchdog`]: crate::CollectorBuilder::watchdog
+    pub fn is_overdue(&self) -> bool {
+        unsafe { (*self.inner).is_overdue() }
+    }
+
+    /// Gets how long the allocations dropped by the most recent collection
+    /// pass which dropped anything had been waiting in the drop queue. Only
+    /// available with the `latency-stats` feature.
+    ///
+    /// Returns `None` if no clock was installed with
+    /// [`CollectorBuilder::reclamation_clock`], or if nothing has been
+    /// collected yet. See [`CollectorBuilder::reclamation_clock`] for an
+    /// example.
+    ///
+    /// [`CollectorBuilder::reclamation_clock`]: crate::CollectorBuilder::reclamation_clock
+    #[cfg(feature = "latency-stats")]
+    pub fn latency_stats(&self) -> Option<LatencyStats> {
+        unsafe { (*self.inner).last_latency.get() }
+    }
+
+    /// Gets the total size in bytes of the live allocations associated with
+    /// this `Collector`, including their headers and any allocations waiting
+    /// in the drop queue. Only available with the `byte-stats` feature.
+    ///
+    /// Like [`alloc_count`], this always returns 0 if counters are disabled
+    /// (see [`CollectorBuilder::counters`]).
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    ///
+    /// let buffer = Owned::new(&handle, [0.0f32; 1024]);
+    /// assert!(collector.live_bytes() >= 4096);
+    ///
+    /// drop(buffer);
+    /// assert!(collector.pending_bytes() >= 4096);
+    ///
+    /// collector.collect();
+    /// assert_eq!(collector.live_bytes(), 0);
+    /// assert_eq!(collector.pending_bytes(), 0);
+    /// ```
+    ///
+    /// [`alloc_count`]: crate::Collector::alloc_count
+    /// [`CollectorBuilder::counters`]: crate::CollectorBuilder::counters
+    #[cfg(feature = "byte-stats")]
+    pub fn live_bytes(&self) -> usize {
+        unsafe { (*self.inner).live_bytes.load(Ordering::Relaxed) }
+    }
+
+    /// Gets the total size in bytes of the allocations waiting in the drop
+    /// queue, including their headers. Only available with the `byte-stats`
+    /// feature.
+    ///
+    /// Like [`pending_count`], this is approximate while allocations are
+    /// being queued concurrently, and always returns 0 if counters are
+    /// disabled (see [`CollectorBuilder::counters`]). See [`live_bytes`] for
+    /// an example.
+    ///
+    /// [`pending_count`]: crate::Collector::pending_count
+    /// [`CollectorBuilder::counters`]: crate::CollectorBuilder::counters
+    /// [`live_bytes`]: crate::Collector::live_bytes
+    #[cfg(feature = "byte-stats")]
+    pub fn pending_bytes(&self) -> usize {
+        unsafe { (*self.inner).pending_bytes.load(Ordering::Relaxed) }
+    }
+
+    /// Gets the number of times a collection pass such as [`collect`] has run
+    /// to completion, i.e. has fully drained the queue.
+    ///
+    /// A collection pass may already be in progress when the epoch is read,
+    /// so once the epoch has advanced by at least two, everything that was
+    /// queued for dropping before it was first read has been dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::Collector;
+    ///
+    /// let mut collector = Collector::new();
+    /// assert_eq!(collector.collection_epoch(), 0);
+    ///
+    /// collector.collect();
+    /// assert_eq!(collector.collection_epoch(), 1);
+    /// ```
+    ///
+    /// [`collect`]: crate::Collector::collect
+    pub fn collection_epoch(&self) -> usize {
+        unsafe { (*self.inner).epoch.load(Ordering::Acquire) }
+    }
+
+    /// Takes a machine-readable snapshot of this `Collector`'s state.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let collector = Collector::new();
+    /// let x = Owned::new(&collector.handle(), 3);
+    ///
+    /// let stats = collector.stats();
+    /// assert_eq!(stats.allocations, Some(1));
+    /// ```
+    pub fn stats(&self) -> CollectorStats {
+        let counters = unsafe { (*self.inner).counters };
+        CollectorStats {
+            handles: if counters { Some(self.handle_count()) } else { None },
+            allocations: if counters { Some(self.alloc_count()) } else { None },
+            epoch: self.collection_epoch(),
+            #[cfg(feature = "diagnostics")]
+            live_handles: self.live_handles(),
+            #[cfg(feature = "track-allocations")]
+            live_allocations: self.live_allocations(),
+        }
+    }
+
+    /// Gets the source locations at which each live [`Handle`] to this
+    /// `Collector` was created, in order of creation. Only available with the
+    /// `diagnostics` feature.
+    ///
+    /// This is useful for tracking down the [`Handle`] responsible when
+    /// [`try_cleanup`] fails.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::Collector;
+    ///
+    /// let collector = Collector::new();
+    /// let handle = collector.handle();
+    ///
+    /// let live = collector.live_handles();
+    /// assert_eq!(live.len(), 1);
+    /// assert_eq!(live[0].line(), line!() - 4);
+    /// ```
+    ///
+    /// [`Handle`]: crate::Handle
+    /// [`try_cleanup`]: crate::Collector::try_cleanup
+    #[cfg(feature = "diagnostics")]
+    pub fn live_handles(&self) -> Vec<&'static Location<'static>> {
+        unsafe { (*self.inner).handle_registry.lock().live.values().copied().collect() }
+    }
+
+    /// Gets every live allocation associated with this `Collector`, in order
+    /// of address. Only available with the `track-allocations` feature.
+    ///
+    /// This answers the question of what is still alive when [`try_cleanup`]
+    /// fails. Allocations which have been queued for dropping but not yet
+    /// collected are included. Allocations made through [`Owned::new`] and
+    /// [`Shared::new`] are attributed to their caller.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let collector = Collector::new();
+    /// let x = Owned::new(&collector.handle(), 3u32);
+    ///
+    /// let live = collector.live_allocations();
+    /// assert_eq!(live.len(), 1);
+    /// assert_eq!(live[0].type_name, "u32");
+    /// assert_eq!(live[0].location.line(), line!() - 5);
+    /// ```
+    ///
+    /// [`try_cleanup`]: crate::Collector::try_cleanup
+    /// [`Owned::new`]: crate::Owned::new
+    /// [`Shared::new`]: crate::Shared::new
+    #[cfg(feature = "track-allocations")]
+    pub fn live_allocations(&self) -> Vec<LiveAllocation> {
+        unsafe { (*self.inner).alloc_registry.lock().values().copied().collect() }
+    }
+
+    /// Gets the distinct tags of the live allocations associated with this
+    /// `Collector`, in sorted order. Tags are given when allocating, e.g.
+    /// with [`Owned::new_tagged`]. Only available with the
+    /// `track-allocations` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned, Shared};
+    ///
+    /// let collector = Collector::new();
+    /// let handle = collector.handle();
+    /// let a = Owned::new_tagged(&handle, "reverb", [0.0f32; 64]);
+    /// let b = Shared::new_tagged(&handle, "sampler", [0.0f32; 64]);
+    /// let c = Owned::new_tagged(&handle, "reverb", [0.0f32; 64]);
+    ///
+    /// assert_eq!(collector.live_tags(), ["reverb", "sampler"]);
+    /// ```
+    ///
+    /// [`Owned::new_tagged`]: crate::Owned::new_tagged
+    #[cfg(feature = "track-allocations")]
+    pub fn live_tags(&self) -> Vec<&'static str> {
+        let registry = unsafe { (*self.inner).alloc_registry.lock() };
+        let mut tags: Vec<_> = registry.values().filter_map(|allocation| allocation.tag).collect();
+        tags.sort_unstable();
+        tags.dedup();
+        tags
+    }
+
+    /// Gets the number and total size of the live allocations associated
+    /// with this `Collector`, grouped by type and sorted by type name. Only
+    /// available with the `track-allocations` feature.
+    ///
+    /// When [`alloc_count`] won't reach zero, this shows at a glance what
+    /// kind of object is being leaked. [`live_allocations`] then gives the
+    /// individual allocation sites.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let collector = Collector::new();
+    /// let handle = collector.handle();
+    /// let a = Owned::new(&handle, 1u32);
+    /// let b = Owned::new(&handle, 2u32);
+    /// let c = Owned::new(&handle, [0u8; 64]);
+    ///
+    /// let stats = collector.stats_by_type();
+    /// assert_eq!(stats.len(), 2);
+    /// assert_eq!(stats[1].type_name, "u32");
+    /// assert_eq!(stats[1].count, 2);
+    /// ```
+    ///
+    /// [`alloc_count`]: crate::Collector::alloc_count
+    /// [`live_allocations`]: crate::Collector::live_allocations
+    #[cfg(feature = "track-allocations")]
+    pub fn stats_by_type(&self) -> Vec<TypeStats> {
+        let mut by_type = BTreeMap::<&'static str, TypeStats>::new();
+        for allocation in unsafe { (*self.inner).alloc_registry.lock().values() } {
+            let stats = by_type.entry(allocation.type_name).or_insert(TypeStats {
+                type_name: allocation.type_name,
+                count: 0,
+                bytes: 0,
+            });
+            stats.count += 1;
+            stats.bytes += allocation.size;
+        }
+
+        by_type.into_values().collect()
+    }
+
+    /// Gets the number of allocations made and retired by each thread, in the
+    /// order in which the threads first did either. Only available with the
+    /// `thread-stats` feature.
+    ///
+    /// This makes it possible to verify that a real-time thread never
+    /// allocates, and to see which threads generate the most garbage. Note
+    /// that with this feature enabled, the first allocation or retirement on
+    /// each thread allocates in order to register it, unless the thread
+    /// registers itself beforehand with [`Handle::register_thread_stats`].
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let collector = Collector::new();
+    /// let handle = collector.handle();
+    ///
+    /// std::thread::Builder::new()
+    ///     .name("audio".into())
+    ///     .spawn(move || drop(Owned::new(&handle, 3)))
+    ///     .unwrap()
+    ///     .join()
+    ///     .unwrap();
+    ///
+    /// let stats = collector.thread_stats();
+    /// assert_eq!(stats[0].name.as_deref(), Some("audio"));
+    /// assert_eq!(stats[0].allocations, 1);
+    /// assert_eq!(stats[0].retired, 1);
+    /// ```
+    ///
+    /// [`Handle::register_thread_stats`]: crate::Handle::register_thread_stats
+    #[cfg(feature = "thread-stats")]
+    pub fn thread_stats(&self) -> Vec<ThreadStats> {
+        unsafe { (*self.inner).thread_stats.snapshot() }
+    }
+
+    /// Returns whether [`try_cleanup`] would currently succeed, i.e. whether
+    /// there are no live [`Handle`]s or allocations associated with this
+    /// `Collector` and counters are enabled (see
+    /// [`CollectorBuilder::counters`]).
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    /// drop(Owned::new(&handle, 3));
+    /// assert!(!collector.can_cleanup());
+    ///
+    /// drop(handle);
+    /// collector.collect();
+    /// assert!(collector.can_cleanup());
+    /// ```
+    ///
+    /// [`try_cleanup`]: crate::Collector::try_cleanup
+    /// [`Handle`]: crate::Handle
+    /// [`CollectorBuilder::counters`]: crate::CollectorBuilder::counters
+    pub fn can_cleanup(&self) -> bool {
+        unsafe {
+            (*self.inner).counters
+                && (*self.inner).handles.load(Ordering::Acquire) == 0
+                && (*self.inner).allocs.load(Ordering::Acquire) == 0
+        }
+    }
+
+    /// Attempts to free all resources associated with this `Collector`. This
+    /// method will fail if there are any live [`Handle`]s or allocations
+    /// associated with it, or if counters are disabled (see
+    /// [`CollectorBuilder::counters`]), returning a [`CleanupError`] which
+    /// holds the original `Collector`.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Handle, Owned};
+    /// use core::mem::drop;
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    /// let x = Owned::new(&handle, 3);
+    ///
+    /// let error = collector.try_cleanup().unwrap_err();
+    /// assert_eq!(error.live_handles, 1);
+    /// assert_eq!(error.live_allocs, 1);
+    /// let mut collector = error.collector;
+    ///
+    /// drop(handle);
+    /// drop(x);
+    /// collector.collect();
+    ///
+    /// assert!(collector.try_cleanup().is_ok());
+    /// ```
+    ///
+    /// [`Handle`]: crate::Handle
+    /// [`CollectorBuilder::counters`]: crate::CollectorBuilder::counters
+    /// [`CleanupError`]: crate::CleanupError
+    pub fn try_cleanup(mut self) -> Result<(), CleanupError> {
+        if self.can_cleanup() {
+            unsafe {
+                self.free();
+            }
+            core::mem::forget(self);
+
+            return Ok(());
+        }
+
+        Err(CleanupError {
+            live_handles: self.handle_count(),
+            live_allocs: self.alloc_count(),
+            collector: self,
+        })
+    }
+
+    /// Resets a fully drained `Collector` to the state of a newly built one,
+    /// so that it can be reused instead of being cleaned up and replaced. Its
+    /// configuration and internal allocations are kept, as is its
+    /// [`collection_epoch`], which never goes backwards. Peak counts and any
+    /// per-thread statistics start over.
+    ///
+    /// Like [`try_cleanup`], this fails and returns false if there are any
+    /// live [`Handle`]s or allocations associated with the `Collector`, or if
+    /// counters are disabled.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let mut collector = Collector::new();
+    ///
+    /// for _ in 0..3 {
+    ///     let handle = collector.handle();
+    ///     drop(Owned::new(&handle, [0.0f32; 256]));
+    ///     drop(handle);
+    ///
+    ///     collector.collect();
+    ///     assert!(collector.reset());
+    /// }
+    /// ```
+    ///
+    /// [`collection_epoch`]: crate::Collector::collection_epoch
+    /// [`try_cleanup`]: crate::Collector::try_cleanup
+    /// [`Handle`]: crate::Handle
+    pub fn reset(&mut self) -> bool {
+        if !self.can_cleanup() {
+            return false;
+        }
+
+        // With no handles left, nothing else can be touching these. The
+        // queues themselves are already empty.
+        let inner = unsafe { &*self.inner };
+        inner.peak_pending.store(0, Ordering::Relaxed);
+        inner.peak_allocs.store(0, Ordering::Relaxed);
+        #[cfg(feature = "latency-stats")]
+        inner.last_latency.set(None);
+        inner.pending.store(0, Ordering::Relaxed);
+        #[cfg(feature = "thread-stats")]
+        inner.thread_stats.clear();
+        #[cfg(feature = "std")]
+        unsafe {
+            inner.waiter.clear();
+        }
+
+        true
+    }
+
+    /// Returns a human-readable summary of this `Collector`'s state and
+    /// configuration, suitable for logging. Only available with the `std`
+    /// feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let collector = Collector::new();
+    /// let handle = collector.handle();
+    /// let x = Owned::new(&handle, 3);
+    ///
+    /// println!("{}", collector.report());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn report(&self) -> StatusReport<'_> {
+        StatusReport { collector: self }
+    }
+
+    /// Returns a human-readable list of everything keeping this `Collector`
+    /// alive, for logging when [`try_cleanup`] fails. Only available with the
+    /// `diagnostics` feature.
+    ///
+    /// The report lists the creation site of each live [`Handle`]. With the
+    /// `track-allocations` feature, it also lists the live allocations by
+    /// type, followed by each allocation's type, allocation site and tag.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let collector = Collector::new();
+    /// let handle = collector.handle();
+    /// let x = Owned::new_tagged(&handle, "reverb", 3);
+    ///
+    /// if let Err(error) = collector.try_cleanup() {
+    ///     eprintln!("{}", error.collector.leak_report());
+    /// }
+    /// ```
+    ///
+    /// [`try_cleanup`]: crate::Collector::try_cleanup
+    /// [`Handle`]: crate::Handle
+    #[cfg(feature = "diagnostics")]
+    pub fn leak_report(&self) -> LeakReport<'_> {
+        LeakReport { collector: self }
+    }
+
+    /// Frees all resources associated with this `Collector` without checking
+    /// for live [`Handle`]s or allocations.
+    ///
+    /// This is primarily intended for `Collector`s constructed with counters
+    /// disabled (see [`CollectorBuilder::counters`]), for which
+    /// [`try_cleanup`] always fails.
+    ///
+    /// # Safety
+    ///
+    /// There must be no live [`Handle`]s or allocations associated with this
+    /// `Collector`, including allocations still in the drop queue.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let mut collector = Collector::builder().counters(false).build();
+    /// let handle = collector.handle();
+    /// let x = Owned::new(&handle, 3);
+    ///
+    /// drop(x);
+    /// drop(handle);
+    /// collector.collect();
+    ///
+    /// unsafe {
+    ///     collector.cleanup_unchecked();
+    /// }
+    /// ```
+    ///
+    /// [`Handle`]: crate::Handle
+    /// [`CollectorBuilder::counters`]: crate::CollectorBuilder::counters
+    /// [`try_cleanup`]: crate::Collector::try_cleanup
+    pub unsafe fn cleanup_unchecked(mut self) {
+        self.free();
+        core::mem::forget(self);
+    }
+
+    /// Frees all resources associated with this `Collector` regardless of any
+    /// remaining [`Handle`]s or allocations, for shutdown sequences in which
+    /// those are known to be dead, e.g. because the threads owning them have
+    /// already exited without dropping them.
+    ///
+    /// If `drain` is true, everything remaining in the drop queue is dropped
+    /// first, inline on the calling thread even if a [`DropExecutor`] is
+    /// installed. Otherwise, it is leaked.
+    ///
+    /// # Safety
+    ///
+    /// No remaining [`Handle`] or allocation associated with this `Collector`
+    /// may ever be used or dropped afterwards, including by being moved into
+    /// the drop queue. Any [`DropJob`]s previously handed to a
+    /// [`DropExecutor`] must already have been run.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let collector = Collector::new();
+    /// let handle = collector.handle();
+    /// drop(Owned::new(&handle, 3));
+    ///
+    /// // The handle's owner went away without dropping it.
+    /// std::mem::forget(handle);
+    ///
+    /// unsafe {
+    ///     collector.force_cleanup(true);
+    /// }
+    /// ```
+    ///
+    /// [`Handle`]: crate::Handle
+    /// [`DropExecutor`]: crate::DropExecutor
+    /// [`DropJob`]: crate::DropJob
+    pub unsafe fn force_cleanup(mut self, drain: bool) {
+        if drain {
+            self.executor = None;
+            while self.collect_one() {}
+        }
+
+        self.free();
+        core::mem::forget(self);
+    }
+
+    unsafe fn free(&mut self) {
+        // Callers forget `self` afterwards, so these would otherwise leak.
+        self.executor = None;
+        self.profiler = None;
+
+        let lanes = core::mem::take(&mut self.lanes);
+        for lane in lanes.iter() {
+            let _ = Box::from_raw(lane.stub as *mut Node<()>);
+        }
+        let _ = Box::from_raw(self.inner);
+    }
+}
+
+/// Statistics for a single collection pass, returned by
+/// [`Collector::collect_report`].
+///
+/// [`Collector::collect_report`]: crate::Collector::collect_report
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct CollectReport {
+    /// The number of allocations removed from the drop queue.
+    pub dropped: usize,
+    /// The number of bytes of node memory released. Nodes constructed with
+    /// [`Node::init_in`] release their own memory and are not included, nor
+    /// are [`Pool`] slots, which are reused rather than released. Always 0 if
+    /// counters are disabled.
+    ///
+    /// If a [`DropExecutor`] is installed, this counts memory released by any
+    /// jobs which ran during the pass, including jobs from earlier passes.
+    ///
+    /// [`Node::init_in`]: crate::Node::init_in
+    /// [`Pool`]: crate::Pool
+    /// [`DropExecutor`]: crate::DropExecutor
+    pub bytes: usize,
+    /// The time taken by the pass.
+    pub elapsed: Duration,
+}
+
+/// The future returned by [`Collector::collect_async`].
+///
+/// [`Collector::collect_async`]: crate::Collector::collect_async
+#[cfg(feature = "async")]
+pub struct CollectAsync<'a> {
+    collector: &'a mut Collector,
+}
+
+#[cfg(feature = "async")]
+impl core::future::Future for CollectAsync<'_> {
+    type Output = ();
+
+    fn poll(
+        mut self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<()> {
+        let collector = &mut *self.collector;
+        if !collector.queue_is_empty() || !collector.wait_for(Waiter::Task(cx.waker().clone())) {
+            collector.collect();
+            return core::task::Poll::Ready(());
+        }
+
+        core::task::Poll::Pending
+    }
+}
+
+/// A human-readable list of a [`Collector`]'s live [`Handle`]s and
+/// allocations, returned by [`Collector::leak_report`].
+///
+/// [`Collector`]: crate::Collector
+/// [`Handle`]: crate::Handle
+/// [`Collector::leak_report`]: crate::Collector::leak_report
+#[cfg(feature = "diagnostics")]
+pub struct LeakReport<'a> {
+    collector: &'a Collector,
+}
+
+#[cfg(feature = "diagnostics")]
+impl core::fmt::Display for LeakReport<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let collector = self.collector;
+
+        if unsafe { (*collector.inner).counters } {
+            writeln!(
+                f,
+                "basedrop leak report: {} live handles, {} live allocations",
+                collector.handle_count(),
+                collector.alloc_count(),
+            )?;
+        } else {
+            writeln!(f, "basedrop leak report: counters disabled")?;
+        }
+
+        for location in collector.live_handles() {
+            writeln!(f, "  handle created at {}", location)?;
+        }
+
+        #[cfg(feature = "track-allocations")]
+        {
+            for stats in collector.stats_by_type() {
+                writeln!(f, "  {} x {} ({} bytes)", stats.count, stats.type_name, stats.bytes)?;
+            }
+            for allocation in collector.live_allocations() {
+                write!(f, "  {} allocated at {}", allocation.type_name, allocation.location)?;
+                if let Some(tag) = allocation.tag {
+                    write!(f, " [{}]", tag)?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        #[cfg(not(feature = "track-allocations"))]
+        if collector.alloc_count() > 0 {
+            writeln!(f, "  enable the track-allocations feature to list live allocations")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A human-readable summary of a [`Collector`]'s state, returned by
+/// [`Collector::report`].
+///
+/// [`Collector`]: crate::Collector
+/// [`Collector::report`]: crate::Collector::report
+#[cfg(feature = "std")]
+pub struct StatusReport<'a> {
+    collector: &'a Collector,
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for StatusReport<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let collector = self.collector;
+
+        writeln!(f, "basedrop collector status:")?;
+        if unsafe { (*collector.inner).counters } {
+            writeln!(f, "  live handles: {}", collector.handle_count())?;
+            writeln!(f, "  live allocations: {}", collector.alloc_count())?;
+            writeln!(f, "  pending allocations: {}", collector.pending_count())?;
+            #[cfg(feature = "byte-stats")]
+            writeln!(f, "  live bytes: {}", collector.live_bytes())?;
+            #[cfg(feature = "byte-stats")]
+            writeln!(f, "  pending bytes: {}", collector.pending_bytes())?;
+        } else {
+            writeln!(f, "  live handles: untracked")?;
+            writeln!(f, "  live allocations: untracked")?;
+            writeln!(f, "  pending allocations: untracked")?;
+        }
+        writeln!(f, "  collection epoch: {}", collector.collection_epoch())?;
+        writeln!(f, "  drop policy: {:?}", collector.drop_policy)?;
+        writeln!(
+            f,
+            "  executor: {}",
+            if collector.executor.is_some() { "custom" } else { "inline" }
+        )?;
+        writeln!(f, "  queue shards: {}", collector.lanes.len() - NORMAL)?;
+        writeln!(
+            f,
+            "  profiler: {}",
+            if collector.profiler.is_some() { "installed" } else { "none" }
+        )?;
+        writeln!(
+            f,
+            "  allocator: {}",
+            if unsafe { (*collector.inner).allocator.is_some() } { "custom" } else { "global" }
+        )?;
+        writeln!(
+            f,
+            "  on_alloc hook: {}",
+            if unsafe { (*collector.inner).on_alloc.is_some() } { "installed" } else { "none" }
+        )?;
+        writeln!(
+            f,
+            "  on_drop hook: {}",
+            if unsafe { (*collector.inner).on_drop.is_some() } { "installed" } else { "none" }
+        )?;
+        writeln!(
+            f,
+            "  watchdog: {}",
+            if unsafe { (*collector.inner).watchdog.is_some() } { "installed" } else { "none" }
+        )?;
+        writeln!(
+            f,
+            "  writer stall hook: {}",
+            if unsafe { (*collector.inner).on_writer_stall.is_some() } { "installed" } else { "none" }
+        )?;
+        write!(
+            f,
+            "  backlog hook: {}",
+            if unsafe { (*collector.inner).on_backlog.is_some() } { "installed" } else { "none" }
+        )
+    }
+}
+
+impl Drop for Collector {
+    fn drop(&mut self) {
+        if let DropPolicy::Leak = self.drop_policy {
+            return;
+        }
+
+        self.collect();
+        if self.can_cleanup() {
+            unsafe {
+                self.free();
+            }
+
+            return;
+        }
+
+        match self.drop_policy {
+            DropPolicy::Report(report) => report(self),
+            DropPolicy::Panic => panic!(
+                "Collector dropped with {} live handles and {} live allocations",
+                self.handle_count(),
+                self.alloc_count(),
+            ),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+
+    use alloc::sync::Arc;
+    use core::sync::atomic::AtomicUsize;
+
+    struct Test(Arc<AtomicUsize>);
+
+    impl Drop for Test {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn collector() {
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        let collector = Collector::new();
+        let handle = collector.handle();
+
+        let node = Node::alloc(&handle, ());
+        let error = collector.try_cleanup().unwrap_err();
+        assert_eq!((error.live_handles, error.live_allocs), (1, 1));
+        assert_eq!(
+            std::string::ToString::to_string(&error),
+            "collector still has 1 live handles and 1 live allocations",
+        );
+        let mut collector = error.collector;
+        unsafe {
+            Node::queue_drop(node);
+        }
+
+        let mut threads = alloc::vec![];
+        for _ in 0..100 {
+            let handle = handle.clone();
+            let counter = counter.clone();
+            threads.push(std::thread::spawn(move || {
+                for _ in 0..100 {
+                    let node = Node::alloc(&handle, Test(counter.clone()));
+                    unsafe {
+                        Node::queue_drop(node);
+                    }
+                }
+            }));
+        }
+
+        for _ in 0..100 {
+            collector.collect();
+        }
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        collector.collect();
+
+        for (lane, tail) in collector.lanes.iter().zip(unsafe { (*collector.inner).tails.iter() }) {
+            let tail = tail.load(Ordering::Relaxed);
+            assert!(lane.head == tail);
+            assert!(lane.head == lane.stub);
+            let next = unsafe { (*lane.head).link.next.load(Ordering::Relaxed) };
+            assert!(next.is_null());
+        }
+
+        assert!(counter.load(Ordering::Relaxed) == 10000);
+
+        core::mem::drop(handle);
+
+        let result = collector.try_cleanup();
+        assert!(result.is_ok());
+    }
+
+    /// Counts the bytes currently allocated by each thread, so that tests can
+    /// check for leaks without interference from tests on other threads.
+    #[cfg(feature = "std")]
+    mod counting {
+        use std::alloc::{GlobalAlloc, Layout, System};
+        use std::cell::Cell;
+
+        std::thread_local! {
+            static LIVE: Cell<isize> = const { Cell::new(0) };
+        }
+
+        struct Counting;
+
+        unsafe impl GlobalAlloc for Counting {
+            unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                let _ = LIVE.try_with(|live| live.set(live.get() + layout.size() as isize));
+                System.alloc(layout)
+            }
+
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                let _ = LIVE.try_with(|live| live.set(live.get() - layout.size() as isize));
+                System.dealloc(ptr, layout)
+            }
+        }
+
+        #[global_allocator]
+        static ALLOCATOR: Counting = Counting;
+
+        pub(super) fn live_bytes() -> isize {
+            LIVE.with(|live| live.get())
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn cleanup_frees_everything() {
+        let before = counting::live_bytes();
+        for shards in 1..4 {
+            let collector = Collector::builder().shards(shards).build();
+            assert!(collector.try_cleanup().is_ok());
+
+            let collector = Collector::builder().shards(shards).build();
+            unsafe { collector.cleanup_unchecked() };
+
+            let collector = Collector::builder().shards(shards).build();
+            unsafe { collector.force_cleanup(true) };
+        }
+        assert_eq!(counting::live_bytes(), before);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn shards() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let mut collector = Collector::builder().shards(4).build();
+        let handle = collector.handle();
+        assert_eq!(collector.lanes.len(), 5);
+
+        let threads: alloc::vec::Vec<_> = (0..16)
+            .map(|_| {
+                let handle = handle.clone();
+                let counter = counter.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        unsafe { Node::queue_drop(Node::alloc(&handle, Test(counter.clone()))) };
+                    }
+                })
+            })
+            .collect();
+
+        while collector.collect_n(7) > 0 {}
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        unsafe { Node::queue_drop_urgent(Node::alloc(&handle, Test(counter.clone()))) };
+        collector.collect();
+
+        assert_eq!(counter.load(Ordering::Relaxed), 1601);
+        assert_eq!(collector.pending_count(), 0);
+        assert!(collector.queue_is_empty());
+
+        core::mem::drop(handle);
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[test]
+    fn queue_drop_list() {
+        use alloc::vec::Vec;
+
+        static BACKLOG: AtomicUsize = AtomicUsize::new(0);
+
+        let counter = Arc::new(AtomicUsize::new(0));
+        let mut collector = Collector::builder()
+            .on_backlog(3, |info| BACKLOG.store(info.pending, Ordering::Relaxed))
+            .build();
+        let handle = collector.handle();
+        let mut other = Collector::new();
+        let other_handle = other.handle();
+
+        // Runs of nodes from different collectors are queued separately.
+        let nodes: Vec<_> = (0..6)
+            .map(|i| {
+                let handle = if i % 3 == 2 { &other_handle } else { &handle };
+                Node::alloc(handle, Test(counter.clone()))
+            })
+            .collect();
+        unsafe { Node::queue_drop_list(nodes) };
+        assert_eq!(collector.pending_count(), 4);
+        assert_eq!(other.pending_count(), 2);
+        // The backlog hook fires even though the count jumps past it.
+        assert_eq!(BACKLOG.load(Ordering::Relaxed), 4);
+
+        unsafe { Node::queue_drop_list(Vec::<*mut Node<Test>>::new()) };
+        collector.collect();
+        other.collect();
+        assert_eq!(counter.load(Ordering::Relaxed), 6);
+        assert_eq!(collector.alloc_count(), 0);
+        assert_eq!(other.alloc_count(), 0);
+    }
+
+    #[cfg(feature = "diagnostics")]
+    #[test]
+    #[should_panic(expected = "expected a live node, found a node queued for dropping")]
+    fn queue_drop_twice() {
+        let collector = Collector::new();
+        let node = Node::alloc(&collector.handle(), 3);
+
+        unsafe {
+            Node::queue_drop(node);
+            Node::queue_drop(node);
+        }
+    }
+
+    #[cfg(feature = "track-allocations")]
+    #[test]
+    fn stats_by_type() {
+        use crate::{Owned, SharedSlice};
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        let a = Owned::new(&handle, 1u64);
+        let b = Owned::new(&handle, 2u64);
+        let c = SharedSlice::from_slice(&handle, &[0u8; 10]);
+
+        let stats = collector.stats_by_type();
+        assert_eq!(stats.len(), 2);
+        let u64_stats = stats.iter().find(|stats| stats.type_name == "u64").unwrap();
+        assert_eq!(u64_stats.count, 2);
+        assert_eq!(u64_stats.bytes, 2 * core::mem::size_of::<Node<u64>>());
+
+        drop((a, b, c, handle));
+        collector.collect();
+        assert!(collector.stats_by_type().is_empty());
+    }
+
+    #[cfg(feature = "track-allocations")]
+    #[test]
+    fn live_allocations() {
+        use crate::{Owned, Shared};
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+        let x = Owned::new(&handle, 1u8);
+        let y = Shared::new(&handle, 2u16);
+        let line = line!() - 1;
+
+        Shared::leak(Shared::clone(&y));
+        let live = collector.live_allocations();
+        assert_eq!(live.len(), 1);
+        assert_eq!(live[0].type_name, "u8");
+        assert_eq!(live[0].location.line(), line - 1);
+
+        core::mem::drop(x);
+        assert_eq!(collector.live_allocations().len(), 1);
+        collector.collect();
+        assert!(collector.live_allocations().is_empty());
+
+        core::mem::drop(y);
+        core::mem::drop(handle);
+    }
+
+    #[cfg(feature = "diagnostics")]
+    #[test]
+    fn leak_report() {
+        use crate::Owned;
+        use alloc::string::ToString;
+
+        let collector = Collector::new();
+        let handle = collector.handle();
+        let x = Owned::new_tagged(&handle, "reverb", 1u32);
+
+        let error = collector.try_cleanup().unwrap_err();
+        let report = error.collector.leak_report().to_string();
+        assert!(report.starts_with("basedrop leak report: 1 live handles, 1 live allocations"));
+        assert!(report.contains("handle created at"));
+        #[cfg(feature = "track-allocations")]
+        {
+            assert!(report.contains("1 x u32"));
+            assert!(report.contains("[reverb]"));
+        }
+
+        let mut collector = error.collector;
+        core::mem::drop((x, handle));
+        collector.collect();
+        assert_eq!(collector.leak_report().to_string().lines().count(), 1);
+    }
+
+    #[cfg(feature = "track-allocations")]
+    #[test]
+    fn live_tags() {
+        use crate::{Owned, Shared};
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+        let x = Owned::new_tagged(&handle, "b", 1u8);
+        let line = line!() - 1;
+        let y = Shared::new_tagged(&handle, "a", 2u16);
+        let z = Owned::new(&handle, 3u32);
+
+        assert_eq!(collector.live_tags(), ["a", "b"]);
+        let live = collector.live_allocations();
+        let tagged = live.iter().find(|allocation| allocation.tag == Some("b")).unwrap();
+        assert_eq!(tagged.location.line(), line);
+
+        core::mem::drop((x, y, z, handle));
+        collector.collect();
+        assert!(collector.live_tags().is_empty());
+    }
+
+    #[cfg(feature = "diagnostics")]
+    #[test]
+    fn live_handles() {
+        let collector = Collector::new();
+        let handle = collector.handle();
+        let clone = handle.clone();
+        let line = line!() - 1;
+
+        let live = collector.live_handles();
+        assert_eq!(live.len(), 2);
+        assert_eq!(live[1].line(), line);
+        assert_eq!(live[1].file(), file!());
+
+        core::mem::drop(handle);
+        let live = collector.live_handles();
+        assert_eq!(live.len(), 1);
+        assert_eq!(live[0].line(), line);
+
+        core::mem::drop(clone);
+        assert!(collector.live_handles().is_empty());
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[cfg(feature = "thread-stats")]
+    #[test]
+    fn thread_stats() {
+        use crate::Owned;
+
+        let mut first = Collector::new();
+        let second = Collector::new();
+        first.handle().register_thread_stats();
+        assert_eq!(first.thread_stats().len(), 1);
+        assert_eq!(first.thread_stats()[0].allocations, 0);
+
+        core::mem::drop(Owned::new(&first.handle(), 1));
+        core::mem::drop(Owned::new(&second.handle(), 2));
+        core::mem::drop(Owned::new(&second.handle(), 3));
+        assert_eq!(first.thread_stats()[0].allocations, 1);
+        assert_eq!(second.thread_stats()[0].retired, 2);
+
+        first.collect();
+        assert!(first.reset());
+        assert!(first.thread_stats().is_empty());
+        core::mem::drop(Owned::new(&first.handle(), 4));
+        assert_eq!(first.thread_stats()[0].allocations, 1);
+        first.collect();
+    }
+
+    #[test]
+    fn urgent() {
+        struct Order(Arc<AtomicUsize>, usize);
+
+        impl Drop for Order {
+            fn drop(&mut self) {
+                assert_eq!(self.0.fetch_add(1, Ordering::Relaxed), self.1);
+            }
+        }
+
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        let normal = Node::alloc(&handle, Order(counter.clone(), 2));
+        let urgent1 = Node::alloc(&handle, Order(counter.clone(), 0));
+        let urgent2 = Node::alloc(&handle, Order(counter.clone(), 1));
+        unsafe {
+            Node::queue_drop(normal);
+            Node::queue_drop_urgent(urgent1);
+            Node::queue_drop_urgent(urgent2);
+        }
+
+        collector.collect();
+        assert_eq!(counter.load(Ordering::Relaxed), 3);
+
+        core::mem::drop(handle);
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn report() {
+        use alloc::string::ToString;
+
+        let collector = Collector::new();
+        let handle = collector.handle();
+        let report = collector.report().to_string();
+        assert!(report.contains("live handles: 1"));
+        assert!(report.contains("live allocations: 0"));
+        assert!(report.contains("drop policy: Collect"));
+
+        core::mem::drop(handle);
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[test]
+    fn counters_disabled() {
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        let mut collector = Collector::builder().counters(false).build();
+        let handle = collector.handle();
+        let node = Node::alloc(&handle, Test(counter.clone()));
+        assert_eq!(collector.handle_count(), 0);
+        assert_eq!(collector.alloc_count(), 0);
+
+        unsafe {
+            Node::queue_drop(node);
+        }
+        core::mem::drop(handle);
+        collector.collect();
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+
+        let collector = collector.try_cleanup().unwrap_err().collector;
+        unsafe {
+            collector.cleanup_unchecked();
+        }
+    }
+
+    #[test]
+    fn profiler() {
+        use crate::Owned;
+
+        struct Passes(Arc<AtomicUsize>);
+
+        impl Profiler for Passes {
+            fn end(&mut self, pass: &PassInfo) {
+                assert!(pass.completed);
+                assert_eq!(pass.live_allocations, Some(0));
+                self.0.fetch_add(pass.dropped, Ordering::Relaxed);
+            }
+        }
+
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let mut collector = Collector::builder().profiler(Passes(dropped.clone())).build();
+
+        core::mem::drop(Owned::new(collector.handle_ref(), 1));
+        core::mem::drop(Owned::new(collector.handle_ref(), 2));
+        collector.collect();
+        collector.collect();
+        assert_eq!(dropped.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn collect_into() {
+        use crate::Owned;
+        use alloc::vec::Vec;
+
+        let counter = Arc::new(AtomicUsize::new(0));
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+        for _ in 0..3 {
+            core::mem::drop(Owned::new(&handle, Test(counter.clone())));
+        }
+
+        let mut jobs = Vec::new();
+        assert_eq!(collector.collect_into(&mut jobs), 3);
+        assert!(collector.queue_is_empty());
+        assert_eq!(counter.load(Ordering::Relaxed), 0);
+        assert_eq!(collector.alloc_count(), 3);
+
+        jobs.pop().unwrap().run();
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+        core::mem::drop(jobs);
+        assert_eq!(counter.load(Ordering::Relaxed), 3);
+
+        core::mem::drop(handle);
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[test]
+    fn set_executor() {
+        use crate::Owned;
+
+        let executed = Arc::new(AtomicUsize::new(0));
+        let mut collector = Collector::new();
+
+        let counter = executed.clone();
+        let executor = move |job: DropJob| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            job.run();
+        };
+        assert!(collector.set_executor(Some(Box::new(executor))).is_none());
+        core::mem::drop(Owned::new(collector.handle_ref(), 1));
+        collector.collect();
+        assert_eq!(executed.load(Ordering::Relaxed), 1);
+
+        assert!(collector.set_executor(None).is_some());
+        core::mem::drop(Owned::new(collector.handle_ref(), 2));
+        collector.collect();
+        assert_eq!(executed.load(Ordering::Relaxed), 1);
+        assert_eq!(collector.alloc_count(), 0);
+    }
+
+    #[test]
+    fn batched() {
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+        unsafe {
+            for _ in 0..3 {
+                Node::queue_drop(Node::alloc(&handle, Test(counter.clone())));
+            }
+            for i in 0..2u32 {
+                Node::queue_drop(Node::alloc(&handle, i));
+            }
+            Node::queue_drop(Node::alloc(&handle, Test(counter.clone())));
+        }
+
+        assert_eq!(collector.collect_batched(), 6);
+        assert_eq!(counter.load(Ordering::Relaxed), 4);
+        assert_eq!(collector.alloc_count(), 0);
+    }
+
+    #[test]
+    fn force_cleanup() {
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        let collector = Collector::new();
+        let handle = collector.handle();
+        let node = Node::alloc(&handle, Test(counter.clone()));
+        unsafe { Node::queue_drop(node) };
+        core::mem::forget(handle);
+
+        unsafe { collector.force_cleanup(true) };
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn max_pending() {
+        let mut collector = Collector::builder().max_pending(2).build();
+        let handle = collector.handle();
+
+        let a = Node::alloc(&handle, 1);
+        let b = Node::alloc(&handle, 2);
+        let c = Node::alloc(&handle, 3);
+        unsafe {
+            Node::queue_drop_urgent(a);
+            assert!(Node::try_queue_drop(b).is_ok());
+            assert_eq!(Node::try_queue_drop(c), Err(QueueFull));
+        }
+        assert_eq!(collector.pending_count(), 2);
+        assert!(handle.is_congested());
+
+        assert!(collector.collect_one());
+        assert!(!handle.is_congested());
+        unsafe { Node::queue_drop(c) };
+        assert_eq!(collector.pending_count(), 2);
+
+        collector.collect();
+        assert_eq!(collector.pending_count(), 0);
+        assert_eq!(collector.alloc_count(), 0);
+    }
+
+    #[test]
+    fn collect_report() {
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        let ticks = core::cell::Cell::new(0);
+        let clock = || {
+            ticks.set(ticks.get() + 1);
+            Duration::from_millis(ticks.get())
+        };
+
+        unsafe {
+            Node::queue_drop(Node::alloc(&handle, [0u8; 64]));
+            Node::queue_drop(Node::alloc(&handle, [0u8; 64]));
+        }
+        let report = collector.collect_report(&clock);
+        assert_eq!(report.dropped, 2);
+        assert_eq!(report.bytes, 2 * core::mem::size_of::<Node<[u8; 64]>>());
+        assert_eq!(report.elapsed, Duration::from_millis(1));
+
+        let report = collector.collect_report(&clock);
+        assert_eq!((report.dropped, report.bytes), (0, 0));
+    }
+
+    #[cfg(feature = "byte-stats")]
+    #[test]
+    fn byte_stats() {
+        use crate::{Owned, Shared, SharedSlice};
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        let a = Owned::new(&handle, [0u8; 100]);
+        let b = Shared::new(&handle, 1u64);
+        let c = SharedSlice::from_slice(&handle, &[0u32; 50]);
+        let live = collector.live_bytes();
+        assert!(live >= 100 + 8 + 200);
+
+        drop(a);
+        let size = core::mem::size_of::<Node<[u8; 100]>>();
+        assert_eq!(collector.pending_bytes(), size);
+        assert_eq!(collector.live_bytes(), live);
+        collector.collect();
+        assert_eq!(collector.pending_bytes(), 0);
+        assert_eq!(collector.live_bytes(), live - size);
+
+        drop((b, c));
+        collector.collect();
+        assert_eq!(collector.live_bytes(), 0);
+        assert_eq!(collector.pending_bytes(), 0);
+    }
+
+    #[test]
+    fn backlog() {
+        static BACKLOGS: AtomicUsize = AtomicUsize::new(0);
+
+        let mut collector = Collector::builder()
+            .counters(false)
+            .on_backlog(2, |info| {
+                assert_eq!(info.pending, 2);
+                BACKLOGS.fetch_add(1, Ordering::Relaxed);
+            })
+            .build();
+        let handle = collector.handle();
+
+        for _ in 0..2 {
+            unsafe {
+                Node::queue_drop(Node::alloc(&handle, 1));
+                assert_eq!(BACKLOGS.load(Ordering::Relaxed), 0);
+                assert!(Node::try_queue_drop(Node::alloc(&handle, 2)).is_ok());
+                Node::queue_drop_urgent(Node::alloc(&handle, 3));
+                assert_eq!(BACKLOGS.load(Ordering::Relaxed), 1);
+            }
+            assert_eq!(collector.peak_pending_count(), 3);
+
+            collector.collect();
+            assert_eq!(collector.peak_pending_count(), 3);
+            BACKLOGS.store(0, Ordering::Relaxed);
+        }
+
+        drop(handle);
+        unsafe { collector.cleanup_unchecked() };
+    }
+
+    #[test]
+    fn peak_alloc_count() {
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        let nodes = [Node::alloc(&handle, 1), Node::alloc(&handle, 2)];
+        for node in nodes {
+            unsafe { Node::queue_drop(node) };
+        }
+        collector.collect();
+        assert_eq!(collector.alloc_count(), 0);
+        assert_eq!(collector.peak_alloc_count(), 2);
+
+        drop(handle);
+        let epoch = collector.collection_epoch();
+        assert!(collector.reset());
+        assert_eq!(collector.peak_alloc_count(), 0);
+        assert_eq!(collector.collection_epoch(), epoch);
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[cfg(feature = "latency-stats")]
+    #[test]
+    fn latency_stats() {
+        static NOW: AtomicUsize = AtomicUsize::new(0);
+
+        let mut collector = Collector::builder()
+            .reclamation_clock(|| Duration::from_millis(NOW.load(Ordering::Relaxed) as u64))
+            .build();
+        let handle = collector.handle();
+
+        unsafe {
+            Node::queue_drop(Node::alloc(&handle, 1));
+            NOW.store(10, Ordering::Relaxed);
+            Node::queue_drop(Node::alloc(&handle, 2));
+            NOW.store(30, Ordering::Relaxed);
+        }
+        assert_eq!(collector.latency_stats(), None);
+
+        collector.collect();
+        let latency = collector.latency_stats().unwrap();
+        assert_eq!(latency.count, 2);
+        assert_eq!(latency.max, Duration::from_millis(30));
+        assert_eq!(latency.mean, Duration::from_millis(25));
+
+        // Passes which drop nothing keep the previous results.
+        collector.collect();
+        assert_eq!(collector.latency_stats(), Some(latency));
+    }
+
+    #[test]
+    fn watchdog() {
+        static NOW: AtomicUsize = AtomicUsize::new(0);
+        static OVERDUE: AtomicUsize = AtomicUsize::new(0);
+
+        let clock = || Duration::from_millis(NOW.load(Ordering::Relaxed) as u64);
+        let mut collector = Collector::builder()
+            .counters(false)
+            .watchdog(clock, Duration::from_millis(10), |info| {
+                assert_eq!(info.pending, 2);
+                assert_eq!(info.waited, Duration::from_millis(20));
+                OVERDUE.fetch_add(1, Ordering::Relaxed);
+            })
+            .build();
+        let handle = collector.handle();
+
+        for _ in 0..2 {
+            NOW.fetch_add(100, Ordering::Relaxed);
+            unsafe { Node::queue_drop(Node::alloc(&handle, 1)) };
+            NOW.fetch_add(5, Ordering::Relaxed);
+            assert!(!handle.is_overdue());
+
+            NOW.fetch_add(15, Ordering::Relaxed);
+            assert!(collector.is_overdue());
+            unsafe { Node::queue_drop(Node::alloc(&handle, 2)) };
+            unsafe { Node::queue_drop(Node::alloc(&handle, 3)) };
+            assert_eq!(OVERDUE.swap(0, Ordering::Relaxed), 1);
+
+            collector.collect();
+            assert!(!collector.is_overdue());
+        }
+
+        drop(handle);
+        unsafe { collector.cleanup_unchecked() };
+    }
+
+    #[test]
+    fn watchdog_sustained_load() {
+        static NOW: AtomicUsize = AtomicUsize::new(0);
+        static OVERDUE: AtomicUsize = AtomicUsize::new(0);
+
+        let clock = || Duration::from_millis(NOW.load(Ordering::Relaxed) as u64);
+        let mut collector = Collector::builder()
+            .watchdog(clock, Duration::from_millis(50), |_| {
+                OVERDUE.fetch_add(1, Ordering::Relaxed);
+            })
+            .build();
+        let handle = collector.handle();
+
+        // The queue never empties, but each allocation is collected within
+        // 20ms of being queued.
+        for i in 0..4 {
+            unsafe { Node::queue_drop(Node::alloc(&handle, i)) };
+        }
+        for i in 0..100 {
+            NOW.fetch_add(5, Ordering::Relaxed);
+            unsafe { Node::queue_drop(Node::alloc(&handle, i)) };
+            assert_eq!(collector.collect_n(1), 1);
+            assert!(!collector.is_overdue());
+        }
+        assert_eq!(OVERDUE.load(Ordering::Relaxed), 0);
+
+        // Once the collector stops making progress, it is reported.
+        NOW.fetch_add(100, Ordering::Relaxed);
+        assert!(collector.is_overdue());
+        unsafe { Node::queue_drop(Node::alloc(&handle, 0)) };
+        assert_eq!(OVERDUE.load(Ordering::Relaxed), 1);
+
+        collector.collect();
+        assert!(!collector.is_overdue());
+
+        drop(handle);
+        unsafe { collector.cleanup_unchecked() };
+    }
+
+    #[test]
+    fn alloc_hooks() {
+        use crate::{Owned, Shared, SharedSlice};
+
+        static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+        static ALLOC_BYTES: AtomicUsize = AtomicUsize::new(0);
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+        static DROP_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+        let mut collector = Collector::builder()
+            .on_alloc(|info| {
+                ALLOCS.fetch_add(1, Ordering::Relaxed);
+                ALLOC_BYTES.fetch_add(info.size, Ordering::Relaxed);
+            })
+            .on_drop(|info| {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+                DROP_BYTES.fetch_add(info.size, Ordering::Relaxed);
+            })
+            .build();
+        let handle = collector.handle();
+
+        drop(Owned::new(&handle, 1u8));
+        drop(Shared::new(&handle, 2u16));
+        drop(SharedSlice::from_slice(&handle, &[3u32; 4]));
+        assert_eq!(ALLOCS.load(Ordering::Relaxed), 3);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 0);
+
+        collector.collect();
+        assert_eq!(DROPS.load(Ordering::Relaxed), 3);
+        assert_eq!(DROP_BYTES.load(Ordering::Relaxed), ALLOC_BYTES.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn pending_count() {
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        unsafe {
+            Node::queue_drop(Node::alloc(&handle, 1));
+            Node::queue_drop_urgent(Node::alloc(&handle, 2));
+            assert!(Node::try_queue_drop(Node::alloc(&handle, 3)).is_ok());
+        }
+        assert_eq!(handle.pending_count(), 3);
+
+        assert!(collector.collect_one());
+        assert_eq!(collector.pending_count(), 2);
+        collector.collect();
+        assert_eq!(collector.pending_count(), 0);
+
+        let mut collector = Collector::builder().counters(false).build();
+        let handle = collector.handle();
+        unsafe { Node::queue_drop(Node::alloc(&handle, 1)) };
+        assert_eq!(collector.pending_count(), 0);
+        collector.collect();
+        drop(handle);
+        unsafe { collector.cleanup_unchecked() };
+    }
+
+    #[test]
+    fn queue_free() {
+        use crate::Owned;
+
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        let mut collector = Collector::new();
+        let owned = Owned::new(&collector.handle(), Test(counter.clone()));
+        let test = Owned::into_inner(owned);
+
+        collector.collect();
+        assert_eq!(collector.alloc_count(), 0);
+        assert_eq!(counter.load(Ordering::Relaxed), 0);
+
+        drop(test);
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn init_in() {
+        use crate::Owned;
+        use core::mem::MaybeUninit;
+
+        static RELEASED: AtomicUsize = AtomicUsize::new(0);
+
+        struct Release;
+
+        impl<T> NodeDealloc<T> for Release {
+            unsafe fn dealloc(node: *mut Node<T>) {
+                drop(Box::from_raw(node as *mut MaybeUninit<Node<T>>));
+                RELEASED.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        fn place<T: Send + 'static>(handle: &Handle, data: T) -> Owned<T> {
+            let ptr = Box::into_raw(Box::new(MaybeUninit::<Node<T>>::uninit()));
+            unsafe { Owned::from_node(Node::init_in::<Release>(ptr as *mut Node<T>, handle, data)) }
+        }
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        drop(place(&handle, 1));
+        collector.collect();
+        assert_eq!(RELEASED.load(Ordering::Relaxed), 1);
+
+        // Moving the contents out still releases the memory through `Release`.
+        assert_eq!(Owned::into_inner(place(&handle, 2)), 2);
+        collector.collect();
+        assert_eq!(RELEASED.load(Ordering::Relaxed), 2);
+
+        let uninit = place(&handle, MaybeUninit::new(3));
+        drop(unsafe { Owned::assume_init(uninit) });
+        collector.collect();
+        assert_eq!(RELEASED.load(Ordering::Relaxed), 3);
+
+        // A leaked node is never released, since the reference to its
+        // contents lives forever, but it no longer counts as live.
+        let leaked: &'static mut i32 = Owned::leak(place(&handle, 4));
+        *leaked = 5;
+        collector.collect();
+        assert_eq!(RELEASED.load(Ordering::Relaxed), 3);
+        assert_eq!(collector.alloc_count(), 0);
+
+        drop(handle);
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[test]
+    fn defer_fn() {
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        let test = Test(counter.clone());
+        handle.defer_fn({
+            let counter = counter.clone();
+            move || {
+                counter.fetch_add(10, Ordering::Relaxed);
+                drop(test);
+            }
+        });
+        assert_eq!(counter.load(Ordering::Relaxed), 0);
+
+        collector.collect();
+        assert_eq!(counter.load(Ordering::Relaxed), 11);
+        assert_eq!(collector.alloc_count(), 0);
+    }
+
+    #[test]
+    fn try_alloc() {
+        let collector = Collector::new();
+        let handle = collector.handle();
+
+        let layout = Layout::from_size_align(isize::MAX as usize / 2, 16).unwrap();
+        let result = unsafe { try_alloc_raw(&handle, layout, "huge", free_node::<()>) };
+        assert_eq!(result, Err(AllocError));
+        assert_eq!(collector.alloc_count(), 0);
+
+        let node = Node::try_alloc(&handle, 3).unwrap();
+        assert_eq!(collector.alloc_count(), 1);
+        unsafe { Node::queue_drop(node) };
+    }
+
+    #[test]
+    fn allocator() {
+        struct Counting {
+            live: Arc<AtomicUsize>,
+            fail: bool,
+        }
+
+        unsafe impl NodeAllocator for Counting {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+                if self.fail {
+                    return Err(AllocError);
+                }
+                self.live.fetch_add(1, Ordering::Relaxed);
+                NonNull::new(unsafe { alloc::alloc::alloc(layout) }).ok_or(AllocError)
+            }
+
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                self.live.fetch_sub(1, Ordering::Relaxed);
+                alloc::alloc::dealloc(ptr.as_ptr(), layout);
+            }
+        }
+
+        let live = Arc::new(AtomicUsize::new(0));
+        let mut collector = Collector::new_in(Counting { live: live.clone(), fail: false });
+        let handle = collector.handle();
+
+        let a = Node::alloc(&handle, 1u8);
+        let b = Node::alloc(&handle, [0u64; 16]);
+        assert_eq!(live.load(Ordering::Relaxed), 2);
+
+        unsafe {
+            Node::queue_drop(a);
+            Node::queue_drop(b);
+        }
+        collector.collect();
+        assert_eq!(live.load(Ordering::Relaxed), 0);
+
+        let failing = Collector::new_in(Counting { live, fail: true });
+        assert_eq!(Node::try_alloc(&failing.handle(), 3).err(), Some(AllocError));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn collect_async() {
+        use core::future::Future;
+        use core::task::{Context, Poll, Waker};
+        use std::task::Wake;
+
+        struct Wakes(AtomicUsize);
+
+        impl Wake for Wakes {
+            fn wake(self: Arc<Self>) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let wakes = Arc::new(Wakes(AtomicUsize::new(0)));
+        let waker = Waker::from(wakes.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        let mut future = collector.collect_async();
+        assert_eq!(core::pin::Pin::new(&mut future).poll(&mut cx), Poll::Pending);
+        unsafe { Node::queue_drop(Node::alloc(&handle, 1)) };
+        unsafe { Node::queue_drop(Node::alloc(&handle, 2)) };
+        assert_eq!(wakes.0.load(Ordering::Relaxed), 1);
+        assert_eq!(core::pin::Pin::new(&mut future).poll(&mut cx), Poll::Ready(()));
+        assert_eq!(collector.alloc_count(), 0);
+
+        collector.register_waker(&waker);
+        unsafe { Node::queue_drop(Node::alloc(&handle, 3)) };
+        assert_eq!(wakes.0.load(Ordering::Relaxed), 2);
+        collector.register_waker(&waker);
+        assert_eq!(wakes.0.load(Ordering::Relaxed), 3);
+
+        drop(handle);
+        collector.collect();
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn collect_catching() {
+        use crate::{Owned, Shared};
+
+        struct Faulty;
+
+        impl Drop for Faulty {
+            fn drop(&mut self) {
+                panic!("faulty");
+            }
+        }
+
+        let counter = Arc::new(AtomicUsize::new(0));
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        core::mem::drop(Owned::new(&handle, Test(counter.clone())));
+        core::mem::drop(Owned::new(&handle, Faulty));
+        core::mem::drop(Shared::new(&handle, Faulty));
+        core::mem::drop(Owned::new(&handle, Test(counter.clone())));
+
+        let panics = collector.collect_catching();
+        assert_eq!(panics.len(), 2);
+        assert_eq!(counter.load(Ordering::Relaxed), 2);
+        assert_eq!(collector.alloc_count(), 0);
+        assert!(collector.queue_is_empty());
+
+        core::mem::drop(handle);
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn collect_blocking() {
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+        let producer = std::thread::spawn({
+            let counter = counter.clone();
+            move || {
+                for _ in 0..100 {
+                    unsafe { Node::queue_drop(Node::alloc(&handle, Test(counter.clone()))) };
+                    std::thread::sleep(Duration::from_micros(50));
+                }
+            }
+        });
+
+        while counter.load(Ordering::Relaxed) < 100 {
+            collector.collect_blocking();
+        }
+        producer.join().unwrap();
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[test]
+    fn drop_policy() {
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        // The default policy collects.
+        let collector = Collector::new();
+        let handle = collector.handle();
+        let node = Node::alloc(&handle, Test(counter.clone()));
+        unsafe {
+            Node::queue_drop(node);
+        }
+        core::mem::drop(handle);
+        core::mem::drop(collector);
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+
+        let collector = Collector::builder().drop_policy(DropPolicy::Leak).build();
+        let handle = collector.handle();
+        let node = Node::alloc(&handle, Test(counter.clone()));
+        unsafe {
+            Node::queue_drop(node);
+        }
+        core::mem::drop(handle);
+        core::mem::drop(collector);
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+
+        static REPORTED: AtomicUsize = AtomicUsize::new(0);
+        fn report(collector: &Collector) {
+            REPORTED.store(collector.handle_count(), Ordering::Relaxed);
+        }
+
+        let collector = Collector::builder().drop_policy(DropPolicy::Report(report)).build();
+        let handle = collector.handle();
+        core::mem::drop(collector);
+        assert_eq!(REPORTED.load(Ordering::Relaxed), 1);
+        core::mem::drop(handle);
+
+        let collector = Collector::builder().drop_policy(DropPolicy::Panic).build();
+        let handle = collector.handle();
+        let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(move || {
+            core::mem::drop(collector);
+        }));
+        assert!(result.is_err());
+        core::mem::drop(handle);
     }
 }
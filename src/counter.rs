@@ -0,0 +1,71 @@
+use crate::padded::CachePadded;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// The number of stripes in a [`StripedCounter`].
+const STRIPES: usize = 16;
+
+/// A counter split across several cache lines, so that threads updating it
+/// concurrently usually touch different ones. Reading it sums all stripes.
+///
+/// Stripes wrap around freely, since an increment and its matching decrement
+/// may land on different stripes; only the sum is meaningful. The sum is not
+/// a linearizable snapshot while increments are still possible: it can only
+/// be relied upon to reach zero once no more increments can happen.
+pub(crate) struct StripedCounter {
+    // Each stripe has its own cache line, so that updates to neighboring
+    // stripes don't contend with each other.
+    stripes: [CachePadded<AtomicUsize>; STRIPES],
+}
+
+impl StripedCounter {
+    pub(crate) fn new() -> StripedCounter {
+        StripedCounter {
+            stripes: Default::default(),
+        }
+    }
+
+    pub(crate) fn increment(&self, ordering: Ordering) {
+        self.add(1, ordering);
+    }
+
+    pub(crate) fn add(&self, n: usize, ordering: Ordering) {
+        self.stripes[stripe()].fetch_add(n, ordering);
+    }
+
+    pub(crate) fn decrement(&self, ordering: Ordering) {
+        self.sub(1, ordering);
+    }
+
+    pub(crate) fn sub(&self, n: usize, ordering: Ordering) {
+        self.stripes[stripe()].fetch_sub(n, ordering);
+    }
+
+    pub(crate) fn load(&self, ordering: Ordering) -> usize {
+        self.stripes
+            .iter()
+            .fold(0usize, |sum, stripe| sum.wrapping_add(stripe.load(ordering)))
+    }
+}
+
+/// Picks the stripe for the current thread.
+#[cfg(feature = "std")]
+pub(crate) fn stripe() -> usize {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    std::thread_local! {
+        static STRIPE: usize = NEXT.fetch_add(1, Ordering::Relaxed) % STRIPES;
+    }
+
+    STRIPE.try_with(|stripe| *stripe).unwrap_or(0)
+}
+
+/// Picks the stripe for the current thread. Without thread-local storage,
+/// this hashes the address of the current stack frame, which differs between
+/// threads since each has its own stack.
+#[cfg(not(feature = "std"))]
+pub(crate) fn stripe() -> usize {
+    let local = 0u8;
+    let address = &local as *const u8 as usize;
+    ((address >> 16).wrapping_mul(0x9e37_79b9) >> 8) & (STRIPES - 1)
+}
@@ -0,0 +1,128 @@
+use core::ops::{Deref, DerefMut};
+
+/// A value bundled with a dependency which must outlive it.
+///
+/// The [`Collector`] drops allocations in roughly the order in which they
+/// were queued, so when two allocations are released by different threads,
+/// there is no telling which of them will be dropped first. When one has to
+/// be destroyed before the other, e.g. a stream reader before the file
+/// mapping backing it, the dependent value can be stored in a `Dependent`
+/// along with a pointer to its dependency. The dependency is only released
+/// once the value has been dropped, so as long as it is reference-counted
+/// (e.g. a [`Shared`]), it can't be dropped before the value, no matter
+/// where its other pointers are released.
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, Dependent, Owned, Shared};
+///
+/// struct Mapping;
+/// struct Reader;
+///
+/// let mut collector = Collector::new();
+/// let handle = collector.handle();
+///
+/// let mapping = Shared::new(&handle, Mapping);
+/// let reader = Owned::new(&handle, Dependent::new(Reader, mapping.clone()));
+///
+/// // The mapping may be released first, but it is still dropped after the
+/// // reader.
+/// drop(mapping);
+/// drop(reader);
+/// collector.collect();
+/// ```
+///
+/// [`Collector`]: crate::Collector
+/// [`Shared`]: crate::Shared
+pub struct Dependent<T, D> {
+    // Fields are dropped in declaration order.
+    value: T,
+    dependency: D,
+}
+
+impl<T, D> Dependent<T, D> {
+    /// Bundles `value` with the `dependency` which must outlive it.
+    pub fn new(value: T, dependency: D) -> Dependent<T, D> {
+        Dependent { value, dependency }
+    }
+
+    /// Gets a reference to the dependency.
+    pub fn dependency(&self) -> &D {
+        &self.dependency
+    }
+
+    /// Separates the value from its dependency.
+    pub fn into_inner(self) -> (T, D) {
+        (self.value, self.dependency)
+    }
+}
+
+impl<T, D> Deref for Dependent<T, D> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T, D> DerefMut for Dependent<T, D> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Collector, Dependent, Owned, Shared};
+
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    extern crate alloc;
+    use alloc::sync::Arc;
+
+    struct Recorded {
+        order: Arc<AtomicUsize>,
+        dropped_at: Arc<AtomicUsize>,
+    }
+
+    impl Drop for Recorded {
+        fn drop(&mut self) {
+            let order = self.order.fetch_add(1, Ordering::Relaxed);
+            self.dropped_at.store(order, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn dependent() {
+        let order = Arc::new(AtomicUsize::new(0));
+        let dependency_dropped = Arc::new(AtomicUsize::new(usize::MAX));
+        let value_dropped = Arc::new(AtomicUsize::new(usize::MAX));
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        let dependency = Shared::new(
+            &handle,
+            Recorded {
+                order: order.clone(),
+                dropped_at: dependency_dropped.clone(),
+            },
+        );
+        let value = Recorded {
+            order: order.clone(),
+            dropped_at: value_dropped.clone(),
+        };
+        let dependent = Owned::new(&handle, Dependent::new(value, dependency.clone()));
+
+        // Releasing the dependency first queues nothing, since the dependent
+        // value still holds a pointer to it.
+        drop(dependency);
+        collector.collect();
+        assert_eq!(order.load(Ordering::Relaxed), 0);
+
+        drop(dependent);
+        collector.collect();
+        assert_eq!(value_dropped.load(Ordering::Relaxed), 0);
+        assert_eq!(dependency_dropped.load(Ordering::Relaxed), 1);
+    }
+}
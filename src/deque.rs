@@ -0,0 +1,394 @@
+use crate::{Handle, Node, Owned, Shared};
+
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr::NonNull;
+use core::sync::atomic::{fence, AtomicIsize, AtomicPtr, AtomicUsize, Ordering};
+
+extern crate alloc;
+use alloc::boxed::Box;
+
+/// A circular array of slots. Slots are moved in and out bitwise, so a
+/// `Buffer` never drops the values it holds.
+struct Buffer<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    /// The buffer this one replaced, if it hasn't been released yet.
+    retired: *mut Node<Buffer<T>>,
+}
+
+unsafe impl<T: Send> Send for Buffer<T> {}
+
+impl<T> Buffer<T> {
+    fn alloc(handle: &Handle, capacity: usize) -> *mut Node<Buffer<T>>
+    where
+        T: Send + 'static,
+    {
+        let slots = (0..capacity).map(|_| UnsafeCell::new(MaybeUninit::uninit())).collect();
+        let buffer = Owned::new(handle, Buffer { slots, retired: core::ptr::null_mut() });
+        let node = buffer.node.as_ptr();
+        core::mem::forget(buffer);
+        node
+    }
+
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn slot(&self, index: isize) -> *mut MaybeUninit<T> {
+        self.slots[index as usize & (self.capacity() - 1)].get()
+    }
+
+    /// Copies a slot out without assuming that it is initialized, since a
+    /// stealer may read a slot that the worker is overwriting. The caller
+    /// must only call `assume_init` once it has claimed the value.
+    unsafe fn read(&self, index: isize) -> MaybeUninit<T> {
+        self.slot(index).read()
+    }
+
+    unsafe fn write(&self, index: isize, value: T) {
+        (*self.slot(index)).write(value);
+    }
+}
+
+/// Queues a buffer and every buffer it retired for collection.
+fn release_buffers<T>(mut node: *mut Node<Buffer<T>>) {
+    while !node.is_null() {
+        unsafe {
+            let next = (*node).data.retired;
+            drop(Owned { node: NonNull::new_unchecked(node), phantom: PhantomData });
+            node = next;
+        }
+    }
+}
+
+/// The state shared between a [`Worker`] and its [`Stealer`]s.
+struct Deque<T> {
+    top: AtomicIsize,
+    bottom: AtomicIsize,
+    buffer: AtomicPtr<Node<Buffer<T>>>,
+    /// The number of stealers currently accessing `buffer`.
+    stealers: AtomicUsize,
+}
+
+unsafe impl<T: Send> Send for Deque<T> {}
+unsafe impl<T: Send> Sync for Deque<T> {}
+
+impl<T> Drop for Deque<T> {
+    fn drop(&mut self) {
+        let buffer = *self.buffer.get_mut();
+        let top = *self.top.get_mut();
+        let bottom = *self.bottom.get_mut();
+
+        unsafe {
+            for index in top..bottom {
+                drop((*buffer).data.read(index).assume_init());
+            }
+        }
+
+        release_buffers(buffer);
+    }
+}
+
+/// Creates a work-stealing deque, returning its owning [`Worker`] and a
+/// [`Stealer`] which can be cloned and sent to other threads.
+///
+/// The [`Worker`] pushes and pops values at one end of the deque in LIFO
+/// order, while [`Stealer`]s take values from the other end in FIFO order.
+/// This is the usual building block for distributing work such as graph
+/// nodes across a pool of real-time worker threads.
+///
+/// `capacity` is rounded up to a power of two. When the [`Worker`] pushes
+/// more values than fit, it allocates a buffer twice as large; the old
+/// buffer may still be in use by [`Stealer`]s, so it is released to the
+/// [`Collector`] once no [`Stealer`] can be accessing it anymore. Choosing a
+/// large enough `capacity` up front avoids allocating on the worker thread.
+/// None of the other operations block or allocate.
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, work_deque};
+///
+/// let mut collector = Collector::new();
+/// let (mut worker, stealer) = work_deque(&collector.handle(), 64);
+///
+/// worker.push(1);
+/// worker.push(2);
+/// worker.push(3);
+///
+/// let thief = std::thread::spawn(move || stealer.steal());
+/// assert_eq!(thief.join().unwrap(), Some(1));
+/// assert_eq!(worker.pop(), Some(3));
+///
+/// collector.collect();
+/// ```
+///
+/// [`Worker`]: crate::Worker
+/// [`Stealer`]: crate::Stealer
+/// [`Collector`]: crate::Collector
+pub fn work_deque<T: Send + 'static>(handle: &Handle, capacity: usize) -> (Worker<T>, Stealer<T>) {
+    let buffer = Buffer::alloc(handle, capacity.max(1).next_power_of_two());
+    let deque = Shared::new(handle, Deque {
+        top: AtomicIsize::new(0),
+        bottom: AtomicIsize::new(0),
+        buffer: AtomicPtr::new(buffer),
+        stealers: AtomicUsize::new(0),
+    });
+
+    let worker = Worker {
+        handle: handle.clone(),
+        deque: deque.clone(),
+        buffer,
+    };
+
+    (worker, Stealer { deque })
+}
+
+/// The owning end of a [`work_deque`], which pushes and pops values in LIFO
+/// order.
+///
+/// [`work_deque`]: crate::work_deque
+pub struct Worker<T> {
+    handle: Handle,
+    deque: Shared<Deque<T>>,
+    /// A cached copy of `deque.buffer`, which only the `Worker` modifies.
+    buffer: *mut Node<Buffer<T>>,
+}
+
+unsafe impl<T: Send> Send for Worker<T> {}
+
+impl<T: Send + 'static> Worker<T> {
+    /// Pushes a value onto the deque. This allocates if the deque is full.
+    pub fn push(&mut self, value: T) {
+        let bottom = self.deque.bottom.load(Ordering::Relaxed);
+        let top = self.deque.top.load(Ordering::Acquire);
+
+        let mut buffer = unsafe { &(*self.buffer).data };
+        if bottom.wrapping_sub(top) as usize >= buffer.capacity() {
+            self.grow(top, bottom);
+            buffer = unsafe { &(*self.buffer).data };
+        } else {
+            self.release_retired();
+        }
+
+        unsafe {
+            buffer.write(bottom, value);
+        }
+        fence(Ordering::Release);
+        self.deque.bottom.store(bottom.wrapping_add(1), Ordering::Relaxed);
+    }
+
+    fn grow(&mut self, top: isize, bottom: isize) {
+        let old = self.buffer;
+        let new = Buffer::alloc(&self.handle, unsafe { (*old).data.capacity() } * 2);
+
+        unsafe {
+            for index in top..bottom {
+                *(*new).data.slot(index) = (*old).data.read(index);
+            }
+            (*new).data.retired = old;
+        }
+
+        self.buffer = new;
+        self.deque.buffer.store(new, Ordering::Release);
+    }
+}
+
+impl<T> Worker<T> {
+    /// Pops the most recently pushed value off of the deque.
+    pub fn pop(&mut self) -> Option<T> {
+        self.release_retired();
+
+        let buffer = unsafe { &(*self.buffer).data };
+        let bottom = self.deque.bottom.load(Ordering::Relaxed).wrapping_sub(1);
+        self.deque.bottom.store(bottom, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+        let top = self.deque.top.load(Ordering::Relaxed);
+
+        if bottom.wrapping_sub(top) < 0 {
+            self.deque.bottom.store(bottom.wrapping_add(1), Ordering::Relaxed);
+            return None;
+        }
+
+        let value = unsafe { buffer.read(bottom) };
+        if bottom == top {
+            // This is the last value, so we have to race any stealers for it.
+            let won = self
+                .deque
+                .top
+                .compare_exchange(top, top.wrapping_add(1), Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok();
+            self.deque.bottom.store(bottom.wrapping_add(1), Ordering::Relaxed);
+            if !won {
+                return None;
+            }
+        }
+
+        Some(unsafe { value.assume_init() })
+    }
+
+    /// Returns the number of values in the deque.
+    pub fn len(&self) -> usize {
+        let bottom = self.deque.bottom.load(Ordering::Relaxed);
+        let top = self.deque.top.load(Ordering::Relaxed);
+        bottom.wrapping_sub(top).max(0) as usize
+    }
+
+    /// Returns `true` if the deque is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Releases buffers replaced by `grow` to the collector once no stealer
+    /// can still be accessing them.
+    fn release_retired(&mut self) {
+        let retired = unsafe { (*self.buffer).data.retired };
+        if retired.is_null() {
+            return;
+        }
+
+        // As in `SharedCell::replace`, a read-modify-write on `stealers`
+        // ensures that every stealer which saw an old buffer has finished
+        // with it, and that every later stealer will see the current one.
+        if self.deque.stealers.fetch_add(0, Ordering::AcqRel) == 0 {
+            unsafe {
+                (*self.buffer).data.retired = core::ptr::null_mut();
+            }
+            release_buffers(retired);
+        }
+    }
+}
+
+/// A handle for stealing values from a [`work_deque`] in FIFO order.
+///
+/// [`work_deque`]: crate::work_deque
+pub struct Stealer<T> {
+    deque: Shared<Deque<T>>,
+}
+
+unsafe impl<T: Send> Send for Stealer<T> {}
+unsafe impl<T: Send> Sync for Stealer<T> {}
+
+impl<T> Stealer<T> {
+    /// Steals the least recently pushed value from the deque. Returns `None`
+    /// if the deque is empty.
+    pub fn steal(&self) -> Option<T> {
+        loop {
+            let top = self.deque.top.load(Ordering::Acquire);
+            fence(Ordering::SeqCst);
+            let bottom = self.deque.bottom.load(Ordering::Acquire);
+
+            if bottom.wrapping_sub(top) <= 0 {
+                return None;
+            }
+
+            self.deque.stealers.fetch_add(1, Ordering::Acquire);
+            let buffer = self.deque.buffer.load(Ordering::Acquire);
+            let value = unsafe { (*buffer).data.read(top) };
+            self.deque.stealers.fetch_sub(1, Ordering::Release);
+
+            if self
+                .deque
+                .top
+                .compare_exchange(top, top.wrapping_add(1), Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok()
+            {
+                return Some(unsafe { value.assume_init() });
+            }
+
+            // Another thread took this value first, and the slot may already
+            // have been overwritten, so the copy is discarded uninspected.
+        }
+    }
+}
+
+impl<T> Clone for Stealer<T> {
+    fn clone(&self) -> Self {
+        Stealer {
+            deque: self.deque.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{work_deque, Collector};
+
+    extern crate alloc;
+    extern crate std;
+
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn work_deque_order() {
+        let mut collector = Collector::new();
+        let (mut worker, stealer) = work_deque(&collector.handle(), 2);
+
+        for i in 0..10 {
+            worker.push(i);
+        }
+        assert_eq!(worker.len(), 10);
+
+        assert_eq!(stealer.steal(), Some(0));
+        assert_eq!(stealer.steal(), Some(1));
+        assert_eq!(worker.pop(), Some(9));
+        assert_eq!(worker.pop(), Some(8));
+        assert_eq!(worker.len(), 6);
+
+        drop(worker);
+        drop(stealer);
+        collector.collect();
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[test]
+    fn work_deque_threads() {
+        static POPPED: AtomicUsize = AtomicUsize::new(0);
+        const COUNT: usize = 10_000;
+
+        let mut collector = Collector::new();
+        let (mut worker, stealer) = work_deque(&collector.handle(), 1);
+
+        let thieves: Vec<_> = (0..3)
+            .map(|_| {
+                let stealer = stealer.clone();
+                std::thread::spawn(move || {
+                    let mut sum = 0;
+                    while POPPED.load(Ordering::Relaxed) < COUNT {
+                        if let Some(value) = stealer.steal() {
+                            sum += value;
+                            POPPED.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                    sum
+                })
+            })
+            .collect();
+
+        let mut sum = 0;
+        for i in 0..COUNT {
+            worker.push(i);
+            if i % 3 == 0 {
+                if let Some(value) = worker.pop() {
+                    sum += value;
+                    POPPED.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+        while let Some(value) = worker.pop() {
+            sum += value;
+            POPPED.fetch_add(1, Ordering::Relaxed);
+        }
+
+        for thief in thieves {
+            sum += thief.join().unwrap();
+        }
+        assert_eq!(sum, COUNT * (COUNT - 1) / 2);
+
+        drop(worker);
+        drop(stealer);
+        collector.collect();
+        assert!(collector.try_cleanup().is_ok());
+    }
+}
@@ -0,0 +1,95 @@
+use crate::collector::{CollectorInner, NodeHeader};
+
+use core::sync::atomic::Ordering;
+
+/// A queued allocation which has been removed from the drop queue by a
+/// [`Collector`] and is ready to be dropped.
+///
+/// Running a `DropJob`, either explicitly via [`run`] or by dropping it, drops
+/// the allocation's contents and frees it. Until then, the allocation is still
+/// counted by [`Collector::alloc_count`].
+///
+/// [`Collector`]: crate::Collector
+/// [`run`]: crate::DropJob::run
+/// [`Collector::alloc_count`]: crate::Collector::alloc_count
+pub struct DropJob {
+    node: *mut NodeHeader,
+    collector: *mut CollectorInner,
+}
+
+// Only `Send` data can be placed in a `Node`.
+unsafe impl Send for DropJob {}
+
+impl DropJob {
+    pub(crate) unsafe fn new(node: *mut NodeHeader, collector: *mut CollectorInner) -> DropJob {
+        DropJob { node, collector }
+    }
+
+    /// Drops and frees the allocation.
+    pub fn run(self) {}
+}
+
+impl Drop for DropJob {
+    fn drop(&mut self) {
+        unsafe {
+            if ((*self.node).drop)(self.node) && (*self.collector).counters {
+                (*self.collector).allocs.decrement(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Determines where a [`Collector`] runs the destructors of collected
+/// allocations.
+///
+/// By default, a [`Collector`] drops each allocation inline on the thread
+/// calling [`collect`]. When individual drops are expensive (closing files,
+/// tearing down large trees), an executor can be installed with
+/// [`CollectorBuilder::executor`] to hand them off to a thread pool instead,
+/// and replaced later with [`Collector::set_executor`].
+///
+/// `DropExecutor` is implemented for closures taking a [`DropJob`], so e.g.
+/// a `rayon` thread pool can be used with
+/// `.executor(|job: DropJob| rayon::spawn(move || job.run()))`.
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, DropJob, Owned};
+/// use std::sync::mpsc::channel;
+///
+/// let (sender, receiver) = channel::<DropJob>();
+/// let worker = std::thread::spawn(move || {
+///     for job in receiver {
+///         job.run();
+///     }
+/// });
+///
+/// let mut collector = Collector::builder()
+///     .executor(move |job| sender.send(job).unwrap())
+///     .build();
+///
+/// let x = Owned::new(&collector.handle(), vec![0.0f32; 4096]);
+/// drop(x);
+/// collector.collect();
+///
+/// drop(collector);
+/// worker.join().unwrap();
+/// ```
+///
+/// [`Collector`]: crate::Collector
+/// [`collect`]: crate::Collector::collect
+/// [`CollectorBuilder::executor`]: crate::CollectorBuilder::executor
+/// [`Collector::set_executor`]: crate::Collector::set_executor
+/// [`DropJob`]: crate::DropJob
+pub trait DropExecutor: Send {
+    /// Arranges for the given [`DropJob`] to be run.
+    ///
+    /// [`DropJob`]: crate::DropJob
+    fn execute(&mut self, job: DropJob);
+}
+
+impl<F: FnMut(DropJob) + Send> DropExecutor for F {
+    fn execute(&mut self, job: DropJob) {
+        self(job)
+    }
+}
@@ -0,0 +1,333 @@
+use crate::{Handle, Node, Owned, Shared};
+
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicPtr, AtomicU8, AtomicUsize, Ordering};
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+/// Neither side is accessing the buffer.
+const IDLE: u8 = 0;
+/// The real-time side holds a [`BufferGuard`].
+const ACTIVE: u8 = 1;
+/// The non-real-time side is copying the buffer into a larger one.
+const GROWING: u8 = 2;
+
+/// The state shared between a [`GrowableBuffer`] and its [`BufferGrower`].
+struct State<T> {
+    state: AtomicU8,
+    buffer: AtomicPtr<Node<Vec<T>>>,
+    // The buffer's length, kept separately so that the grower can read it
+    // without touching the buffer while the real-time side has it borrowed.
+    // Only written by the grower.
+    len: AtomicUsize,
+}
+
+unsafe impl<T: Send> Send for State<T> {}
+unsafe impl<T: Send> Sync for State<T> {}
+
+impl<T> Drop for State<T> {
+    fn drop(&mut self) {
+        let buffer = *self.buffer.get_mut();
+        drop(Owned { node: unsafe { NonNull::new_unchecked(buffer) }, phantom: PhantomData });
+    }
+}
+
+/// Creates a buffer of `len` copies of `value` which is accessed from a
+/// real-time thread but grown from a non-real-time thread, returning its
+/// real-time and non-real-time halves.
+///
+/// The [`GrowableBuffer`] reads and writes the buffer's contents within its
+/// current length without blocking or allocating. The [`BufferGrower`] grows
+/// it by allocating a larger buffer, copying the contents over, and swapping
+/// it in, after which the old buffer is reclaimed by the [`Collector`].
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, growable_buffer};
+///
+/// let mut collector = Collector::new();
+/// let (grower, mut buffer) = growable_buffer(&collector.handle(), 2, 0.0f32);
+///
+/// // On the audio thread:
+/// if let Some(mut samples) = buffer.lock() {
+///     samples[1] = 1.0;
+/// }
+///
+/// // On another thread, when more room is needed:
+/// grower.grow(4, 0.0);
+///
+/// assert_eq!(&*buffer.lock().unwrap(), &[0.0, 1.0, 0.0, 0.0]);
+/// collector.collect();
+/// ```
+///
+/// [`GrowableBuffer`]: crate::GrowableBuffer
+/// [`BufferGrower`]: crate::BufferGrower
+/// [`Collector`]: crate::Collector
+pub fn growable_buffer<T: Clone + Send + 'static>(
+    handle: &Handle,
+    len: usize,
+    value: T,
+) -> (BufferGrower<T>, GrowableBuffer<T>) {
+    let buffer = Owned::new(handle, alloc::vec![value; len]);
+    let node = buffer.node.as_ptr();
+    core::mem::forget(buffer);
+
+    let state = Shared::new(handle, State {
+        state: AtomicU8::new(IDLE),
+        buffer: AtomicPtr::new(node),
+        len: AtomicUsize::new(len),
+    });
+
+    let grower = BufferGrower {
+        handle: handle.clone(),
+        state: state.clone(),
+    };
+
+    (grower, GrowableBuffer { state })
+}
+
+/// The real-time half of a [`growable_buffer`].
+///
+/// None of `GrowableBuffer`'s methods block or allocate.
+///
+/// [`growable_buffer`]: crate::growable_buffer
+pub struct GrowableBuffer<T> {
+    state: Shared<State<T>>,
+}
+
+unsafe impl<T: Send> Send for GrowableBuffer<T> {}
+
+impl<T> GrowableBuffer<T> {
+    /// Gets access to the buffer's contents. Returns `None` if the
+    /// [`BufferGrower`] is in the middle of growing the buffer.
+    ///
+    /// While the returned guard is held, the [`BufferGrower`] has to wait to
+    /// grow the buffer, so it should be dropped promptly.
+    ///
+    /// [`BufferGrower`]: crate::BufferGrower
+    pub fn lock(&mut self) -> Option<BufferGuard<'_, T>> {
+        self.state
+            .state
+            .compare_exchange(IDLE, ACTIVE, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+
+        let buffer = unsafe { &mut (*self.state.buffer.load(Ordering::Relaxed)).data };
+        Some(BufferGuard {
+            state: &self.state,
+            ptr: buffer.as_mut_ptr(),
+            len: buffer.len(),
+            phantom: PhantomData,
+        })
+    }
+}
+
+/// Access to the contents of a [`GrowableBuffer`], returned by
+/// [`GrowableBuffer::lock`].
+///
+/// [`GrowableBuffer`]: crate::GrowableBuffer
+/// [`GrowableBuffer::lock`]: crate::GrowableBuffer::lock
+pub struct BufferGuard<'a, T> {
+    state: &'a State<T>,
+    // A raw pointer rather than a reference, since the buffer node itself is
+    // shared with the grower.
+    ptr: *mut T,
+    len: usize,
+    phantom: PhantomData<&'a mut [T]>,
+}
+
+unsafe impl<T: Send> Send for BufferGuard<'_, T> {}
+unsafe impl<T: Send + Sync> Sync for BufferGuard<'_, T> {}
+
+impl<T> Deref for BufferGuard<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { core::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<T> DerefMut for BufferGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { core::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl<T> Drop for BufferGuard<'_, T> {
+    fn drop(&mut self) {
+        self.state.state.store(IDLE, Ordering::Release);
+    }
+}
+
+/// The non-real-time half of a [`growable_buffer`].
+///
+/// [`growable_buffer`]: crate::growable_buffer
+pub struct BufferGrower<T> {
+    handle: Handle,
+    state: Shared<State<T>>,
+}
+
+unsafe impl<T: Send> Send for BufferGrower<T> {}
+
+impl<T: Clone + Send + 'static> BufferGrower<T> {
+    /// Grows the buffer to `len` elements, filling the new elements with
+    /// copies of `value`. Does nothing if the buffer is already at least
+    /// `len` elements long.
+    ///
+    /// This allocates, and waits for the [`GrowableBuffer`] to release any
+    /// [`BufferGuard`] it holds, so it should not be called from a real-time
+    /// thread.
+    ///
+    /// [`GrowableBuffer`]: crate::GrowableBuffer
+    /// [`BufferGuard`]: crate::BufferGuard
+    pub fn grow(&self, len: usize, value: T) {
+        if len <= self.len() {
+            return;
+        }
+
+        let mut new = Owned::new(&self.handle, Vec::with_capacity(len));
+
+        while self
+            .state
+            .state
+            .compare_exchange_weak(IDLE, GROWING, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+
+        // Returns the state to idle on every exit, including a panic from
+        // `T::clone` while copying.
+        let growing = Growing(&self.state.state);
+
+        // Another call to `grow` may have grown the buffer past `len` while
+        // this one was waiting.
+        if len <= self.len() {
+            return;
+        }
+
+        // Only the grower replaces the buffer, so it can be loaded without
+        // synchronizing with the real-time side.
+        let old = self.state.buffer.load(Ordering::Relaxed);
+        new.extend_from_slice(unsafe { &(*old).data });
+        new.resize(len, value);
+
+        let node = new.node.as_ptr();
+        core::mem::forget(new);
+        self.state.buffer.store(node, Ordering::Relaxed);
+        self.state.len.store(len, Ordering::Relaxed);
+        drop(growing);
+
+        drop(Owned { node: unsafe { NonNull::new_unchecked(old) }, phantom: PhantomData });
+    }
+}
+
+/// Releases the [`GROWING`] state when dropped.
+struct Growing<'a>(&'a AtomicU8);
+
+impl Drop for Growing<'_> {
+    fn drop(&mut self) {
+        self.0.store(IDLE, Ordering::Release);
+    }
+}
+
+impl<T> BufferGrower<T> {
+    /// Returns the current length of the buffer.
+    pub fn len(&self) -> usize {
+        self.state.len.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` if the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{growable_buffer, Collector};
+
+    extern crate std;
+
+    #[test]
+    fn grow_while_locked() {
+        let mut collector = Collector::new();
+        let (grower, mut buffer) = growable_buffer(&collector.handle(), 1, 0);
+
+        let mut guard = buffer.lock().unwrap();
+        guard[0] = 1;
+
+        let thread = std::thread::spawn(move || {
+            grower.grow(3, 2);
+            grower
+        });
+
+        // Growing must wait until the guard is released.
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert!(!thread.is_finished());
+        guard[0] = 3;
+        drop(guard);
+
+        let grower = thread.join().unwrap();
+        assert_eq!(grower.len(), 3);
+        assert_eq!(&*buffer.lock().unwrap(), &[3, 2, 2]);
+
+        collector.collect();
+        assert_eq!(collector.alloc_count(), 2);
+
+        drop(grower);
+        drop(buffer);
+        collector.collect();
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[test]
+    fn grow_smaller() {
+        let mut collector = Collector::new();
+        let (grower, mut buffer) = growable_buffer(&collector.handle(), 1, 0);
+
+        grower.grow(3, 1);
+        grower.grow(2, 2);
+        assert_eq!(grower.len(), 3);
+        assert_eq!(&*buffer.lock().unwrap(), &[0, 1, 1]);
+
+        drop(grower);
+        drop(buffer);
+        collector.collect();
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[test]
+    fn grow_panic() {
+        struct Bomb(bool);
+
+        impl Clone for Bomb {
+            fn clone(&self) -> Bomb {
+                if self.0 {
+                    panic!();
+                }
+                Bomb(false)
+            }
+        }
+
+        let mut collector = Collector::new();
+        let (grower, mut buffer) = growable_buffer(&collector.handle(), 1, Bomb(false));
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            grower.grow(3, Bomb(true));
+        }));
+        assert!(result.is_err());
+
+        // The failed grow must not leave the buffer locked.
+        assert_eq!(buffer.lock().unwrap().len(), 1);
+        grower.grow(2, Bomb(false));
+        assert_eq!(buffer.lock().unwrap().len(), 2);
+
+        drop(grower);
+        drop(buffer);
+        collector.collect();
+        assert!(collector.try_cleanup().is_ok());
+    }
+}
@@ -1,28 +1,122 @@
-//! Memory-management tools for real-time audio and other latency-critical scenarios.
-//!
-//! - [`Owned`] and [`Shared`] are smart pointers analogous to `Box` and `Arc`
-//! which add their contents to a queue for deferred collection when dropped.
-//! - [`Collector`] is used to process the drop queue.
-//! - [`Node`] provides a lower-level interface for implementing custom smart
-//!   pointers or data structures.
-//! - [`SharedCell`] implements a mutable memory location holding a [`Shared`]
-//!   pointer that can be used by multiple readers and writers in a thread-safe
-//!   manner.
-//!
-//! [`Owned`]: crate::Owned
-//! [`Shared`]: crate::Shared
-//! [`Collector`]: crate::Collector
-//! [`Node`]: crate::Node
-//! [`SharedCell`]: crate::SharedCell
-
-#![no_std]
-
-mod collector;
-mod owned;
-mod shared;
-mod shared_cell;
-
-pub use collector::*;
-pub use owned::*;
-pub use shared::*;
-pub use shared_cell::*;
+//! Memory-management tools for real-time audio and other latency-critical scenarios.
+//!
+//! - [`Owned`] and [`Shared`] are smart pointers analogous to `Box` and `Arc`
+//!   which add their contents to a queue for deferred collection when dropped.
+//! - [`Collector`] is used to process the drop queue.
+//! - [`Node`] provides a lower-level interface for implementing custom smart
+//!   pointers or data structures.
+//! - [`SharedCell`] implements a mutable memory location holding a [`Shared`]
+//!   pointer that can be used by multiple readers and writers in a thread-safe
+//!   manner.
+//!
+//! # Diagnostics on embedded targets
+//!
+//! basedrop doesn't depend on any logging framework. Instead, the hooks
+//! installed with [`CollectorBuilder::on_alloc`],
+//! [`CollectorBuilder::on_writer_stall`] and [`CollectorBuilder::profiler`]
+//! report allocations, stalled writers, and per-pass collection counts. None
+//! of them require `std`, so on `no_std` targets they can forward to e.g.
+//! `defmt`, while [`Collector::stats`] provides a snapshot of the collector's
+//! counters on demand.
+//!
+//! [`Owned`]: crate::Owned
+//! [`Shared`]: crate::Shared
+//! [`Collector`]: crate::Collector
+//! [`Node`]: crate::Node
+//! [`SharedCell`]: crate::SharedCell
+//! [`CollectorBuilder::on_alloc`]: crate::CollectorBuilder::on_alloc
+//! [`CollectorBuilder::on_writer_stall`]: crate::CollectorBuilder::on_writer_stall
+//! [`CollectorBuilder::profiler`]: crate::CollectorBuilder::profiler
+//! [`Collector::stats`]: crate::Collector::stats
+
+#![no_std]
+#![cfg_attr(feature = "nightly", feature(coerce_unsized, dispatch_from_dyn, unsize))]
+
+#[cfg(feature = "std")]
+extern crate std;
+
+mod allocator;
+mod any;
+mod arc_compat;
+mod clock;
+mod collector;
+mod counter;
+mod dependent;
+mod deque;
+mod executor;
+mod growable;
+mod lock;
+mod lru;
+#[cfg(feature = "std")]
+mod os;
+mod owned;
+mod padded;
+mod pool;
+mod profiler;
+mod published;
+mod schedule;
+mod shared;
+mod shared_cell;
+mod slice;
+#[cfg(any(feature = "diagnostics", feature = "thread-stats"))]
+mod spin;
+mod stats;
+mod sync_collector;
+#[cfg(feature = "std")]
+mod thread;
+
+pub use allocator::*;
+pub use any::*;
+pub use arc_compat::*;
+pub use clock::*;
+pub use collector::*;
+pub use dependent::*;
+pub use deque::*;
+pub use executor::*;
+pub use growable::*;
+pub use lock::*;
+pub use lru::*;
+#[cfg(feature = "std")]
+pub use os::*;
+pub use owned::*;
+pub use pool::*;
+pub use profiler::*;
+pub use published::*;
+pub use schedule::*;
+pub use shared::*;
+pub use shared_cell::*;
+pub use slice::*;
+pub use stats::*;
+pub use sync_collector::*;
+#[cfg(feature = "std")]
+pub use thread::*;
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Collector, Handle, Owned, Pool, Pooled, RecyclePool, Shared, SharedCell, SyncCollector,
+        UniqueShared,
+    };
+
+    use core::cell::Cell;
+    use core::panic::{RefUnwindSafe, UnwindSafe};
+
+    fn assert_unwind_safe<T: UnwindSafe + RefUnwindSafe + Unpin>() {}
+    fn assert_unwind_safe_owned<T: UnwindSafe + Unpin>() {}
+
+    #[test]
+    fn auto_traits() {
+        assert_unwind_safe::<Collector>();
+        assert_unwind_safe::<Handle>();
+        assert_unwind_safe::<Owned<i32>>();
+        assert_unwind_safe::<Shared<i32>>();
+        assert_unwind_safe::<UniqueShared<i32>>();
+        assert_unwind_safe::<Pool<i32>>();
+        assert_unwind_safe::<Pooled<i32>>();
+        assert_unwind_safe::<RecyclePool<i32>>();
+        assert_unwind_safe::<SharedCell<i32>>();
+        assert_unwind_safe::<SyncCollector>();
+
+        assert_unwind_safe_owned::<Owned<Cell<i32>>>();
+    }
+}
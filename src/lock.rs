@@ -0,0 +1,359 @@
+use crate::{Handle, Shared};
+
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Backs off while waiting for a lock held by another thread.
+fn relax() {
+    #[cfg(feature = "std")]
+    std::thread::yield_now();
+    #[cfg(not(feature = "std"))]
+    core::hint::spin_loop();
+}
+
+struct MutexInner<T> {
+    locked: AtomicBool,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for MutexInner<T> {}
+unsafe impl<T: Send> Sync for MutexInner<T> {}
+
+/// A reference-counted mutex with deferred collection.
+///
+/// `SharedMutex` combines a [`Shared`] allocation with an embedded lock, for
+/// data which is mostly read but occasionally needs guarded mutation in
+/// place. Non-real-time threads can wait for the lock with [`lock`], while
+/// real-time threads use [`try_lock`], which never blocks. Like a [`Shared`],
+/// cloning a `SharedMutex` produces another reference to the same data, which
+/// is added to the [`Collector`]'s drop queue once the last reference is
+/// dropped.
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, SharedMutex};
+///
+/// let collector = Collector::new();
+/// let params = SharedMutex::new(&collector.handle(), [0.0f32; 8]);
+///
+/// // On the UI thread:
+/// params.lock()[3] = 1.0;
+///
+/// // On the audio thread:
+/// if let Some(guard) = params.try_lock() {
+///     assert_eq!(guard[3], 1.0);
+/// };
+/// ```
+///
+/// [`Shared`]: crate::Shared
+/// [`lock`]: crate::SharedMutex::lock
+/// [`try_lock`]: crate::SharedMutex::try_lock
+/// [`Collector`]: crate::Collector
+pub struct SharedMutex<T> {
+    inner: Shared<MutexInner<T>>,
+}
+
+impl<T: Send + 'static> SharedMutex<T> {
+    /// Constructs a new `SharedMutex<T>`.
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn new(handle: &Handle, data: T) -> SharedMutex<T> {
+        SharedMutex {
+            inner: Shared::new(handle, MutexInner {
+                locked: AtomicBool::new(false),
+                data: UnsafeCell::new(data),
+            }),
+        }
+    }
+}
+
+impl<T> SharedMutex<T> {
+    /// Acquires the lock, waiting for it to be released if another thread
+    /// holds it. This should not be called from a real-time thread.
+    pub fn lock(&self) -> SharedMutexGuard<'_, T> {
+        loop {
+            if let Some(guard) = self.try_lock() {
+                return guard;
+            }
+            relax();
+        }
+    }
+
+    /// Attempts to acquire the lock without blocking. Returns `None` if
+    /// another thread holds it.
+    pub fn try_lock(&self) -> Option<SharedMutexGuard<'_, T>> {
+        self.inner
+            .locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+
+        Some(SharedMutexGuard {
+            inner: &self.inner,
+            phantom: PhantomData,
+        })
+    }
+}
+
+impl<T> Clone for SharedMutex<T> {
+    fn clone(&self) -> Self {
+        SharedMutex {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// A guard providing access to the contents of a [`SharedMutex`], which
+/// releases the lock when dropped.
+///
+/// Like `std::sync::MutexGuard`, the guard can only be shared between threads
+/// if `T: Sync`:
+///
+/// ```compile_fail
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<basedrop::SharedMutexGuard<'static, std::cell::Cell<i32>>>();
+/// ```
+///
+/// [`SharedMutex`]: crate::SharedMutex
+pub struct SharedMutexGuard<'a, T> {
+    inner: &'a MutexInner<T>,
+    // `MutexInner<T>` is `Sync` whenever `T: Send`, but sharing the guard
+    // shares `&T`, which also requires `T: Sync`.
+    phantom: PhantomData<&'a mut T>,
+}
+
+unsafe impl<T: Sync> Sync for SharedMutexGuard<'_, T> {}
+
+impl<T> Deref for SharedMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.inner.data.get() }
+    }
+}
+
+impl<T> DerefMut for SharedMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.inner.data.get() }
+    }
+}
+
+impl<T> Drop for SharedMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.inner.locked.store(false, Ordering::Release);
+    }
+}
+
+/// Set in an [`RwLockInner`]'s state while a writer holds the lock. The
+/// remaining bits count the readers.
+const WRITER: usize = !(usize::MAX >> 1);
+
+struct RwLockInner<T> {
+    state: AtomicUsize,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for RwLockInner<T> {}
+unsafe impl<T: Send + Sync> Sync for RwLockInner<T> {}
+
+/// A reference-counted reader-writer lock with deferred collection.
+///
+/// `SharedRwLock` is the reader-writer counterpart of [`SharedMutex`]:
+/// non-real-time threads can wait for access with [`read`] and [`write`],
+/// while real-time threads use [`try_read`] and [`try_write`], which never
+/// block. Writers are not given priority over readers, so a steady stream of
+/// readers can keep a writer waiting.
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, SharedRwLock};
+///
+/// let collector = Collector::new();
+/// let table = SharedRwLock::new(&collector.handle(), vec![0.0f32; 1024]);
+///
+/// // On the UI thread:
+/// table.write()[0] = 1.0;
+///
+/// // On the audio thread:
+/// if let Some(guard) = table.try_read() {
+///     assert_eq!(guard[0], 1.0);
+/// };
+/// ```
+///
+/// [`SharedMutex`]: crate::SharedMutex
+/// [`read`]: crate::SharedRwLock::read
+/// [`write`]: crate::SharedRwLock::write
+/// [`try_read`]: crate::SharedRwLock::try_read
+/// [`try_write`]: crate::SharedRwLock::try_write
+pub struct SharedRwLock<T> {
+    inner: Shared<RwLockInner<T>>,
+}
+
+impl<T: Send + Sync + 'static> SharedRwLock<T> {
+    /// Constructs a new `SharedRwLock<T>`.
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn new(handle: &Handle, data: T) -> SharedRwLock<T> {
+        SharedRwLock {
+            inner: Shared::new(handle, RwLockInner {
+                state: AtomicUsize::new(0),
+                data: UnsafeCell::new(data),
+            }),
+        }
+    }
+}
+
+impl<T> SharedRwLock<T> {
+    /// Acquires shared read access, waiting for any writer to release the
+    /// lock. This should not be called from a real-time thread.
+    pub fn read(&self) -> SharedReadGuard<'_, T> {
+        loop {
+            if let Some(guard) = self.try_read() {
+                return guard;
+            }
+            relax();
+        }
+    }
+
+    /// Attempts to acquire shared read access without blocking. Returns
+    /// `None` if a writer holds the lock.
+    pub fn try_read(&self) -> Option<SharedReadGuard<'_, T>> {
+        let mut state = self.inner.state.load(Ordering::Relaxed);
+        loop {
+            if state & WRITER != 0 {
+                return None;
+            }
+
+            match self.inner.state.compare_exchange_weak(
+                state,
+                state + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(SharedReadGuard { inner: &self.inner }),
+                Err(current) => state = current,
+            }
+        }
+    }
+
+    /// Acquires exclusive write access, waiting for all readers and any
+    /// writer to release the lock. This should not be called from a real-time
+    /// thread.
+    pub fn write(&self) -> SharedWriteGuard<'_, T> {
+        loop {
+            if let Some(guard) = self.try_write() {
+                return guard;
+            }
+            relax();
+        }
+    }
+
+    /// Attempts to acquire exclusive write access without blocking. Returns
+    /// `None` if any reader or writer holds the lock.
+    pub fn try_write(&self) -> Option<SharedWriteGuard<'_, T>> {
+        self.inner
+            .state
+            .compare_exchange(0, WRITER, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+
+        Some(SharedWriteGuard { inner: &self.inner })
+    }
+}
+
+impl<T> Clone for SharedRwLock<T> {
+    fn clone(&self) -> Self {
+        SharedRwLock {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// A guard providing shared access to the contents of a [`SharedRwLock`].
+///
+/// [`SharedRwLock`]: crate::SharedRwLock
+pub struct SharedReadGuard<'a, T> {
+    inner: &'a RwLockInner<T>,
+}
+
+impl<T> Deref for SharedReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.inner.data.get() }
+    }
+}
+
+impl<T> Drop for SharedReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.inner.state.fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// A guard providing exclusive access to the contents of a [`SharedRwLock`].
+///
+/// [`SharedRwLock`]: crate::SharedRwLock
+pub struct SharedWriteGuard<'a, T> {
+    inner: &'a RwLockInner<T>,
+}
+
+impl<T> Deref for SharedWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.inner.data.get() }
+    }
+}
+
+impl<T> DerefMut for SharedWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.inner.data.get() }
+    }
+}
+
+impl<T> Drop for SharedWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.inner.state.store(0, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Collector, SharedMutex, SharedRwLock};
+
+    #[test]
+    fn shared_mutex() {
+        let mut collector = Collector::new();
+        let mutex = SharedMutex::new(&collector.handle(), 1);
+        let clone = mutex.clone();
+
+        let mut guard = mutex.lock();
+        assert!(clone.try_lock().is_none());
+        *guard = 2;
+        drop(guard);
+        assert_eq!(*clone.try_lock().unwrap(), 2);
+
+        drop((mutex, clone));
+        collector.collect();
+        assert_eq!(collector.alloc_count(), 0);
+    }
+
+    #[test]
+    fn shared_rw_lock() {
+        let mut collector = Collector::new();
+        let lock = SharedRwLock::new(&collector.handle(), 1);
+
+        let a = lock.read();
+        let b = lock.try_read().unwrap();
+        assert!(lock.try_write().is_none());
+        drop((a, b));
+
+        let mut guard = lock.write();
+        assert!(lock.try_read().is_none());
+        *guard = 2;
+        drop(guard);
+        assert_eq!(*lock.read(), 2);
+
+        drop(lock);
+        collector.collect();
+        assert_eq!(collector.alloc_count(), 0);
+    }
+}
@@ -0,0 +1,298 @@
+use crate::{Handle, Shared, SharedCell};
+
+use core::borrow::Borrow;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+struct Entry<V> {
+    value: V,
+    /// The value of the cache's clock when this entry was last looked up.
+    last_used: AtomicUsize,
+}
+
+/// An immutable snapshot of the cache's contents, sorted by key.
+type Entries<K, V> = Vec<(K, Shared<Entry<V>>)>;
+
+/// The state shared between an [`LruCache`] and its [`LruReader`]s.
+struct State<K, V> {
+    // Counts lookups and insertions, wrapping on overflow, so that 32-bit
+    // targets without 64-bit atomics are supported. Entries are compared by
+    // their `age`, which stays correct across wrapping.
+    clock: AtomicUsize,
+    entries: SharedCell<Entries<K, V>>,
+}
+
+/// Returns how many ticks before `now` an entry was last used. Entries used
+/// concurrently, after `now` was read, count as just used.
+fn age(now: usize, last_used: usize) -> usize {
+    let age = now.wrapping_sub(last_used);
+    if age > usize::MAX / 2 {
+        0
+    } else {
+        age
+    }
+}
+
+impl<K: Ord, V> State<K, V> {
+    fn get<Q>(&self, key: &Q) -> Option<LruEntry<V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let entries = self.entries.get();
+        let index = entries.binary_search_by(|(k, _)| k.borrow().cmp(key)).ok()?;
+        let entry = Shared::clone(&entries[index].1);
+
+        let now = self.clock.fetch_add(1, Ordering::Relaxed);
+        entry.last_used.store(now, Ordering::Relaxed);
+
+        Some(LruEntry(entry))
+    }
+}
+
+/// Creates a least-recently-used cache holding at most `capacity` entries,
+/// returning its writing half and a reading half which can be cloned and
+/// sent to other threads.
+///
+/// Inserting and removing entries happens on non-real-time threads through
+/// the [`LruCache`]. Lookups through an [`LruReader`] never block or
+/// allocate, so they can be performed on a real-time thread. Each lookup
+/// marks the entry as recently used. When an insertion would exceed the
+/// capacity, the least recently used entry is evicted; it is reclaimed by the
+/// [`Collector`] once no reader holds it anymore.
+///
+/// Every change to the cache publishes a new sorted snapshot of its entries,
+/// making changes linear-time in the number of entries. This suits caches of
+/// a modest number of large values, such as rendered wavetables or resampled
+/// clips.
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, lru_cache};
+///
+/// let mut collector = Collector::new();
+/// let (mut cache, reader) = lru_cache(&collector.handle(), 2);
+///
+/// cache.insert("sine", vec![0.0f32; 2048]);
+/// cache.insert("saw", vec![0.0f32; 2048]);
+///
+/// // On the audio thread:
+/// let table = reader.get("sine").unwrap();
+/// assert_eq!(table.len(), 2048);
+///
+/// // "saw" is now the least recently used entry, so it is evicted.
+/// cache.insert("square", vec![0.0f32; 2048]);
+/// assert!(reader.get("saw").is_none());
+///
+/// collector.collect();
+/// ```
+///
+/// [`LruCache`]: crate::LruCache
+/// [`LruReader`]: crate::LruReader
+/// [`Collector`]: crate::Collector
+pub fn lru_cache<K, V>(handle: &Handle, capacity: usize) -> (LruCache<K, V>, LruReader<K, V>)
+where
+    K: Ord + Clone + Send + Sync + 'static,
+    V: Send + Sync + 'static,
+{
+    let state = Shared::new(handle, State {
+        clock: AtomicUsize::new(0),
+        entries: SharedCell::new(Shared::new(handle, Vec::new())),
+    });
+
+    let cache = LruCache {
+        handle: handle.clone(),
+        capacity,
+        state: state.clone(),
+    };
+
+    (cache, LruReader { state })
+}
+
+/// The writing half of an [`lru_cache`], used from non-real-time threads.
+///
+/// [`lru_cache`]: crate::lru_cache
+pub struct LruCache<K, V> {
+    handle: Handle,
+    capacity: usize,
+    state: Shared<State<K, V>>,
+}
+
+impl<K, V> LruCache<K, V>
+where
+    K: Ord + Clone + Send + Sync + 'static,
+    V: Send + Sync + 'static,
+{
+    /// Inserts an entry into the cache, replacing any existing entry with the
+    /// same key and evicting the least recently used entry if the cache is
+    /// full. The new entry counts as the most recently used one.
+    pub fn insert(&mut self, key: K, value: V) {
+        let now = self.state.clock.fetch_add(1, Ordering::Relaxed);
+        let entry = Shared::new(&self.handle, Entry {
+            value,
+            last_used: AtomicUsize::new(now),
+        });
+
+        let mut entries = self.snapshot();
+        match entries.binary_search_by(|(k, _)| k.cmp(&key)) {
+            Ok(index) => entries[index].1 = entry,
+            Err(index) => {
+                entries.insert(index, (key, entry));
+                while entries.len() > self.capacity {
+                    let oldest = entries
+                        .iter()
+                        .enumerate()
+                        .max_by_key(|(_, (_, entry))| {
+                            age(now, entry.last_used.load(Ordering::Relaxed))
+                        })
+                        .map(|(index, _)| index)
+                        .unwrap();
+                    entries.remove(oldest);
+                }
+            }
+        }
+
+        self.publish(entries);
+    }
+
+    /// Removes an entry from the cache, returning whether it was present.
+    pub fn remove<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut entries = self.snapshot();
+        match entries.binary_search_by(|(k, _)| k.borrow().cmp(key)) {
+            Ok(index) => {
+                entries.remove(index);
+                self.publish(entries);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Removes every entry from the cache.
+    pub fn clear(&mut self) {
+        self.publish(Vec::new());
+    }
+
+    fn snapshot(&self) -> Entries<K, V> {
+        let entries = self.state.entries.get();
+        let mut snapshot = Vec::with_capacity(entries.len() + 1);
+        snapshot.extend(entries.iter().map(|(k, entry)| (k.clone(), Shared::clone(entry))));
+        snapshot
+    }
+
+    fn publish(&self, entries: Entries<K, V>) {
+        self.state.entries.set(Shared::new(&self.handle, entries));
+    }
+}
+
+impl<K: Ord, V> LruCache<K, V> {
+    /// Looks up an entry, marking it as recently used.
+    pub fn get<Q>(&self, key: &Q) -> Option<LruEntry<V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.state.get(key)
+    }
+
+    /// Returns the number of entries in the cache.
+    pub fn len(&self) -> usize {
+        self.state.entries.get().len()
+    }
+
+    /// Returns `true` if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the maximum number of entries in the cache.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+/// The reading half of an [`lru_cache`], which can be used from real-time
+/// threads.
+///
+/// None of `LruReader`'s methods block or allocate.
+///
+/// [`lru_cache`]: crate::lru_cache
+pub struct LruReader<K, V> {
+    state: Shared<State<K, V>>,
+}
+
+impl<K: Ord, V> LruReader<K, V> {
+    /// Looks up an entry, marking it as recently used.
+    pub fn get<Q>(&self, key: &Q) -> Option<LruEntry<V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.state.get(key)
+    }
+}
+
+impl<K, V> Clone for LruReader<K, V> {
+    fn clone(&self) -> Self {
+        LruReader {
+            state: self.state.clone(),
+        }
+    }
+}
+
+/// A reference to a value in an [`lru_cache`], which keeps the value alive
+/// even if it is evicted.
+///
+/// [`lru_cache`]: crate::lru_cache
+pub struct LruEntry<V>(Shared<Entry<V>>);
+
+impl<V> Deref for LruEntry<V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        &self.0.value
+    }
+}
+
+impl<V> Clone for LruEntry<V> {
+    fn clone(&self) -> Self {
+        LruEntry(self.0.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{lru_cache, Collector};
+
+    #[test]
+    fn eviction() {
+        let mut collector = Collector::new();
+        let (mut cache, reader) = lru_cache(&collector.handle(), 2);
+
+        cache.insert(1, 'a');
+        cache.insert(2, 'b');
+        let a = reader.get(&1).unwrap();
+        cache.insert(3, 'c');
+        assert_eq!(cache.len(), 2);
+        assert!(reader.get(&2).is_none());
+
+        cache.insert(1, 'd');
+        assert_eq!(*a, 'a');
+        assert_eq!(*reader.get(&1).unwrap(), 'd');
+        assert!(cache.remove(&3));
+        assert!(!cache.remove(&3));
+
+        drop(a);
+        drop(cache);
+        drop(reader);
+        collector.collect();
+        assert!(collector.try_cleanup().is_ok());
+    }
+}
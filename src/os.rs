@@ -0,0 +1,173 @@
+use crate::Handle;
+
+use std::net::{Shutdown, TcpStream};
+
+#[cfg(unix)]
+use core::ffi::{c_int, c_void};
+#[cfg(unix)]
+use std::os::fd::OwnedFd;
+#[cfg(windows)]
+use std::os::windows::io::OwnedHandle;
+
+#[cfg(unix)]
+extern "C" {
+    fn munmap(addr: *mut c_void, len: usize) -> c_int;
+}
+
+/// An owned memory mapping which is unmapped with `munmap` when dropped.
+///
+/// Wrapping a `Mapping` in an [`Owned`] ahead of time allows it to be
+/// discarded from a real-time thread without making any system calls, since
+/// the `munmap` call will happen on the collector thread.
+///
+/// [`Owned`]: crate::Owned
+#[cfg(unix)]
+pub struct Mapping {
+    addr: *mut c_void,
+    len: usize,
+}
+
+#[cfg(unix)]
+unsafe impl Send for Mapping {}
+#[cfg(unix)]
+unsafe impl Sync for Mapping {}
+
+#[cfg(unix)]
+impl Mapping {
+    /// Takes ownership of the memory mapping of `len` bytes at `addr`.
+    ///
+    /// # Safety
+    ///
+    /// `addr` and `len` must describe a memory mapping previously created
+    /// with `mmap`, which must not be unmapped or accessed through any other
+    /// means once the `Mapping` is dropped.
+    pub unsafe fn from_raw(addr: *mut c_void, len: usize) -> Mapping {
+        Mapping { addr, len }
+    }
+
+    /// Gets the starting address of the mapping.
+    pub fn as_ptr(&self) -> *mut c_void {
+        self.addr
+    }
+
+    /// Gets the length of the mapping in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the mapping has a length of zero.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe {
+            munmap(self.addr, self.len);
+        }
+    }
+}
+
+struct ShutdownOnDrop {
+    stream: TcpStream,
+    how: Shutdown,
+}
+
+impl Drop for ShutdownOnDrop {
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(self.how);
+    }
+}
+
+/// Helpers for handing operating system resources to the collector thread,
+/// so that the system calls needed to release them are made there. Only
+/// available with the `std` feature.
+///
+/// Note that each of these methods allocates a node for the resource. To
+/// discard a resource from a real-time thread, wrap it in an [`Owned`] ahead
+/// of time instead, e.g. `Owned<OwnedFd>` or `Owned<Mapping>`.
+///
+/// [`Owned`]: crate::Owned
+impl Handle {
+    /// Queues a file descriptor to be closed by the collector.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::Collector;
+    /// use std::fs::File;
+    /// use std::os::fd::OwnedFd;
+    ///
+    /// let mut collector = Collector::new();
+    /// let fd = OwnedFd::from(File::open("Cargo.toml").unwrap());
+    ///
+    /// collector.handle().defer_close(fd);
+    /// collector.collect();
+    /// ```
+    #[cfg(unix)]
+    pub fn defer_close(&self, fd: OwnedFd) {
+        self.defer(fd);
+    }
+
+    /// Queues a handle to be closed by the collector.
+    #[cfg(windows)]
+    pub fn defer_close(&self, handle: OwnedHandle) {
+        self.defer(handle);
+    }
+
+    /// Queues the memory mapping of `len` bytes at `addr` to be unmapped by
+    /// the collector.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements apply as for [`Mapping::from_raw`].
+    ///
+    /// [`Mapping::from_raw`]: crate::Mapping::from_raw
+    #[cfg(unix)]
+    pub unsafe fn defer_munmap(&self, addr: *mut c_void, len: usize) {
+        self.defer(Mapping::from_raw(addr, len));
+    }
+
+    /// Queues a TCP stream to be shut down with the given [`Shutdown`] mode
+    /// and then closed by the collector.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::Collector;
+    /// use std::net::{Shutdown, TcpListener, TcpStream};
+    ///
+    /// let mut collector = Collector::new();
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    ///
+    /// collector.handle().defer_shutdown(stream, Shutdown::Both);
+    /// collector.collect();
+    /// ```
+    ///
+    /// [`Shutdown`]: std::net::Shutdown
+    pub fn defer_shutdown(&self, stream: TcpStream, how: Shutdown) {
+        self.defer(ShutdownOnDrop { stream, how });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Collector;
+
+    #[cfg(unix)]
+    #[test]
+    fn defer_close() {
+        use std::fs::File;
+        use std::os::fd::OwnedFd;
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        handle.defer_close(OwnedFd::from(File::open("Cargo.toml").unwrap()));
+        assert_eq!(collector.alloc_count(), 1);
+
+        collector.collect();
+        assert_eq!(collector.alloc_count(), 0);
+    }
+}
@@ -1,9 +1,19 @@
-use crate::{Handle, Node};
+use crate::collector::{drop_node, Batch, NodeHeader, UninitGuard};
+use crate::{AllocError, Handle, Node};
 
+#[cfg(feature = "nightly")]
+use core::marker::Unsize;
 use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+#[cfg(feature = "nightly")]
+use core::ops::{CoerceUnsized, DispatchFromDyn};
 use core::ops::{Deref, DerefMut};
+use core::panic::{RefUnwindSafe, UnwindSafe};
 use core::ptr::NonNull;
 
+extern crate alloc;
+use alloc::boxed::Box;
+
 /// An owned smart pointer with deferred collection, analogous to `Box`.
 ///
 /// When an `Owned<T>` is dropped, its contents are added to the drop queue
@@ -11,15 +21,33 @@ use core::ptr::NonNull;
 /// As the collector may be on another thread, contents are required to be
 /// `Send + 'static`.
 ///
+/// The contents are allocated with the alignment of `T`, so a
+/// `#[repr(align(N))]` wrapper can be used to over-align them. For slices
+/// whose alignment is only known at runtime, see
+/// [`OwnedSlice::new_uninit_aligned`] and
+/// [`SharedSlice::new_uninit_aligned`].
+///
 /// [`Collector`]: crate::Collector
 /// [`Handle`]: crate::Handle
-pub struct Owned<T> {
-    node: NonNull<Node<T>>,
-    phantom: PhantomData<T>,
+/// [`OwnedSlice::new_uninit_aligned`]: crate::OwnedSlice::new_uninit_aligned
+/// [`SharedSlice::new_uninit_aligned`]: crate::SharedSlice::new_uninit_aligned
+pub struct Owned<T: ?Sized> {
+    pub(crate) node: NonNull<Node<T>>,
+    pub(crate) phantom: PhantomData<T>,
 }
 
-unsafe impl<T: Send> Send for Owned<T> {}
-unsafe impl<T: Sync> Sync for Owned<T> {}
+unsafe impl<T: ?Sized + Send> Send for Owned<T> {}
+unsafe impl<T: ?Sized + Sync> Sync for Owned<T> {}
+
+impl<T: ?Sized + UnwindSafe> UnwindSafe for Owned<T> {}
+impl<T: ?Sized + RefUnwindSafe> RefUnwindSafe for Owned<T> {}
+impl<T: ?Sized> Unpin for Owned<T> {}
+
+#[cfg(feature = "nightly")]
+impl<T: ?Sized + Unsize<U>, U: ?Sized> CoerceUnsized<Owned<U>> for Owned<T> {}
+
+#[cfg(feature = "nightly")]
+impl<T: ?Sized + Unsize<U>, U: ?Sized> DispatchFromDyn<Owned<U>> for Owned<T> {}
 
 impl<T: Send + 'static> Owned<T> {
     /// Constructs a new `Owned<T>`.
@@ -31,12 +59,377 @@ impl<T: Send + 'static> Owned<T> {
     /// let collector = Collector::new();
     /// let three = Owned::new(&collector.handle(), 3);
     /// ```
+    #[cfg_attr(feature = "track-allocations", track_caller)]
     pub fn new(handle: &Handle, data: T) -> Owned<T> {
         Owned {
             node: unsafe { NonNull::new_unchecked(Node::alloc(handle, data)) },
             phantom: PhantomData,
         }
     }
+
+    /// Constructs a new `Owned<T>` with a tag identifying the subsystem which
+    /// made it. With the `track-allocations` feature, the tag is recorded
+    /// along with the allocation, and [`Collector::live_tags`] lists the
+    /// tags of everything still alive, which narrows down leaks to the
+    /// subsystem responsible. Otherwise, this is equivalent to [`new`].
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let collector = Collector::new();
+    /// let buffer = Owned::new_tagged(&collector.handle(), "reverb", [0.0f32; 64]);
+    /// ```
+    ///
+    /// [`Collector::live_tags`]: crate::Collector::live_tags
+    /// [`new`]: crate::Owned::new
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn new_tagged(handle: &Handle, tag: &'static str, data: T) -> Owned<T> {
+        let owned = Owned::new(handle, data);
+        unsafe { Node::set_tag(owned.node.as_ptr(), tag) };
+        owned
+    }
+
+    /// Constructs a new `Owned<T>` by initializing its contents in place,
+    /// without first constructing them on the stack. This is useful for
+    /// values too large to be moved around safely, such as long delay lines.
+    ///
+    /// # Safety
+    ///
+    /// `f` must fully initialize the value before returning. If `f` panics,
+    /// the allocation is freed without dropping its contents.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    /// use std::mem::MaybeUninit;
+    ///
+    /// const LEN: usize = 1 << 16;
+    ///
+    /// let collector = Collector::new();
+    /// let delay_line = unsafe {
+    ///     Owned::new_with(&collector.handle(), |slot: &mut MaybeUninit<[f32; LEN]>| {
+    ///         let samples = slot.as_mut_ptr() as *mut f32;
+    ///         for i in 0..LEN {
+    ///             samples.add(i).write(0.0);
+    ///         }
+    ///     })
+    /// };
+    ///
+    /// assert_eq!(delay_line[LEN - 1], 0.0);
+    /// ```
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub unsafe fn new_with<F>(handle: &Handle, f: F) -> Owned<T>
+    where
+        F: FnOnce(&mut MaybeUninit<T>),
+    {
+        let node = Node::alloc_uninit(handle, drop_node::<T>);
+
+        let guard = UninitGuard(node);
+        f(&mut *(core::ptr::addr_of_mut!((*node).data) as *mut MaybeUninit<T>));
+        core::mem::forget(guard);
+
+        Owned {
+            node: NonNull::new_unchecked(node),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Constructs a new `Owned<T>` with uninitialized contents, analogous to
+    /// `Box::new_uninit`. The contents can be written through the returned
+    /// `Owned` and then marked as initialized with [`assume_init`].
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let collector = Collector::new();
+    /// let mut frame = Owned::<[f32; 1024]>::new_uninit(&collector.handle());
+    ///
+    /// let frame = unsafe {
+    ///     (frame.as_mut_ptr() as *mut f32).write_bytes(0, 1024);
+    ///     Owned::assume_init(frame)
+    /// };
+    /// assert_eq!(frame[1023], 0.0);
+    /// ```
+    ///
+    /// [`assume_init`]: crate::Owned::assume_init
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn new_uninit(handle: &Handle) -> Owned<MaybeUninit<T>> {
+        // Leaving a `MaybeUninit<T>` uninitialized is valid.
+        unsafe { Owned::new_with(handle, |_| {}) }
+    }
+
+    /// Constructs a new `Owned<T>`, returning an error instead of aborting if
+    /// memory can't be allocated. In that case, `data` is dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let collector = Collector::new();
+    /// let three = Owned::try_new(&collector.handle(), 3).unwrap();
+    /// ```
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn try_new(handle: &Handle, data: T) -> Result<Owned<T>, AllocError> {
+        Ok(Owned {
+            node: unsafe { NonNull::new_unchecked(Node::try_alloc(handle, data)?) },
+            phantom: PhantomData,
+        })
+    }
+
+    /// Consumes and leaks an `Owned<T>`, returning a mutable reference to its
+    /// contents which lives for the rest of the program, analogous to
+    /// `Box::leak`.
+    ///
+    /// The allocation is removed from its [`Collector`]'s allocation count,
+    /// so it won't prevent the [`Collector`] from being cleaned up.
+    /// Memory provided to [`Node::init_in`] is likewise never released, so
+    /// its `NodeDealloc` is not called.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let collector = Collector::new();
+    /// let handle = collector.handle();
+    /// let table: &'static mut [f32; 4] = Owned::leak(Owned::new(&handle, [0.0; 4]));
+    /// table[1] = 0.5;
+    ///
+    /// drop(handle);
+    /// assert!(collector.try_cleanup().is_ok());
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`Node::init_in`]: crate::Node::init_in
+    pub fn leak(this: Self) -> &'static mut T {
+        let node = this.node.as_ptr();
+        core::mem::forget(this);
+
+        unsafe {
+            Node::leak(node);
+            &mut (*node).data
+        }
+    }
+}
+
+impl<T: Send + 'static> Owned<MaybeUninit<T>> {
+    /// Converts an `Owned<MaybeUninit<T>>` into an `Owned<T>`, analogous to
+    /// `Box::assume_init`.
+    ///
+    /// # Safety
+    ///
+    /// The contents must have been fully initialized.
+    pub unsafe fn assume_init(this: Self) -> Owned<T> {
+        Owned::transmute(this)
+    }
+}
+
+impl<T: ?Sized + Send + 'static> Owned<Box<T>> {
+    /// Adopts an existing `Box<T>`, e.g. one produced by a third-party
+    /// library, so that it is dropped by the [`Collector`].
+    ///
+    /// The box's contents are not moved or copied. Instead, a small node
+    /// holding the box itself is allocated, and the box is dropped along with
+    /// the node when the returned `Owned` is collected. Since the contents
+    /// may be unsized, this also works for e.g. `Box<[T]>` and
+    /// `Box<dyn Trait + Send>`.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let mut collector = Collector::new();
+    /// let decoded: Box<[f32]> = vec![0.0; 48000].into_boxed_slice();
+    /// let ptr = decoded.as_ptr();
+    ///
+    /// let samples = Owned::from_box(&collector.handle(), decoded);
+    /// assert_eq!(samples.as_ptr(), ptr);
+    ///
+    /// drop(samples);
+    /// collector.collect();
+    /// assert_eq!(collector.alloc_count(), 0);
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn from_box(handle: &Handle, boxed: Box<T>) -> Owned<Box<T>> {
+        Owned::new(handle, boxed)
+    }
+}
+
+impl<T> Owned<T> {
+    /// Consumes an `Owned<T>`, moving its contents out. The emptied
+    /// allocation is added to the drop queue of its [`Collector`] to be
+    /// freed.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let mut collector = Collector::new();
+    /// let buffer = Owned::new(&collector.handle(), vec![0.0f32; 4096]);
+    ///
+    /// let buffer: Vec<f32> = Owned::into_inner(buffer);
+    /// assert_eq!(buffer.len(), 4096);
+    ///
+    /// collector.collect();
+    /// assert_eq!(collector.alloc_count(), 0);
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    pub fn into_inner(this: Self) -> T {
+        let node = this.node.as_ptr();
+        core::mem::forget(this);
+
+        unsafe {
+            let data = core::ptr::read(&(*node).data);
+            Node::queue_free(node);
+            data
+        }
+    }
+
+    /// Consumes an `Owned<T>`, returning a raw pointer to its contents,
+    /// analogous to `Box::into_raw`. The pointer must eventually be converted
+    /// back with [`from_raw`] to avoid leaking the allocation.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let collector = Collector::new();
+    /// let x = Owned::new(&collector.handle(), 3);
+    ///
+    /// let ptr = Owned::into_raw(x);
+    /// unsafe { *ptr += 1 };
+    ///
+    /// let x = unsafe { Owned::from_raw(ptr) };
+    /// assert_eq!(*x, 4);
+    /// ```
+    ///
+    /// [`from_raw`]: crate::Owned::from_raw
+    pub fn into_raw(this: Self) -> *mut T {
+        let node = this.node.as_ptr();
+        core::mem::forget(this);
+
+        unsafe { &mut (*node).data }
+    }
+
+    /// Reconstructs an `Owned<T>` from a pointer returned by [`into_raw`].
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by [`into_raw`] for the same type `T`,
+    /// and `from_raw` may only be called once for each such pointer.
+    ///
+    /// [`into_raw`]: crate::Owned::into_raw
+    pub unsafe fn from_raw(ptr: *mut T) -> Owned<T> {
+        let offset = core::mem::offset_of!(Node<T>, data);
+        let node = (ptr as *mut u8).sub(offset) as *mut Node<T>;
+
+        Owned {
+            node: NonNull::new_unchecked(node),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Reinterprets an `Owned<T>` as an `Owned<U>`, so that its contents are
+    /// dropped as a `U` from now on.
+    ///
+    /// # Safety
+    ///
+    /// `T` and `U` must have the same layout, and the contents must be a
+    /// valid `U`.
+    pub(crate) unsafe fn transmute<U>(this: Self) -> Owned<U> {
+        let node = this.node.as_ptr() as *mut Node<U>;
+        core::mem::forget(this);
+
+        Node::set_drop(node, drop_node::<U>);
+        Owned {
+            node: NonNull::new_unchecked(node),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Constructs an `Owned<T>` which takes ownership of an existing [`Node`],
+    /// e.g. one constructed in place with [`Node::init_in`]. Dropping the
+    /// `Owned` queues the `Node` as usual.
+    ///
+    /// # Safety
+    ///
+    /// `node` must point to a live `Node` which hasn't been queued, and
+    /// nothing else may access it or queue it afterwards.
+    ///
+    /// [`Node`]: crate::Node
+    /// [`Node::init_in`]: crate::Node::init_in
+    pub unsafe fn from_node(node: *mut Node<T>) -> Owned<T> {
+        Owned {
+            node: NonNull::new_unchecked(node),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Drops an `Owned<T>`, adding its contents to the high-priority drop
+    /// queue of its [`Collector`] so that it will be collected before any
+    /// other pending garbage.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let mut collector = Collector::new();
+    /// let small = Owned::new(&collector.handle(), 3);
+    /// let large = Owned::new(&collector.handle(), [0.0f32; 4096]);
+    ///
+    /// drop(small);
+    /// Owned::drop_urgent(large);
+    ///
+    /// assert!(collector.collect_one());
+    /// assert_eq!(collector.alloc_count(), 1);
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    pub fn drop_urgent(this: Self) {
+        let node = this.node.as_ptr();
+        core::mem::forget(this);
+
+        unsafe {
+            Node::queue_drop_urgent(node);
+        }
+    }
+
+    /// Drops every `Owned<T>` in `pointers`. Consecutive allocations from
+    /// the same [`Collector`] are added to its drop queue together with a
+    /// single atomic operation, which makes releasing a large collection much
+    /// cheaper than dropping each pointer in turn. See
+    /// [`Node::queue_drop_list`].
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    /// let voices: Vec<_> = (0..1000).map(|i| Owned::new(&handle, i)).collect();
+    ///
+    /// Owned::drop_all(voices);
+    /// collector.collect();
+    /// assert_eq!(collector.alloc_count(), 0);
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`Node::queue_drop_list`]: crate::Node::queue_drop_list
+    pub fn drop_all<I: IntoIterator<Item = Self>>(pointers: I) {
+        let mut batch = Batch::new();
+        for this in pointers {
+            let node = this.node.as_ptr();
+            core::mem::forget(this);
+
+            unsafe {
+                batch.push(node as *mut NodeHeader);
+            }
+        }
+    }
 }
 
 impl<T: Clone + Send + 'static> Clone for Owned<T> {
@@ -46,7 +439,7 @@ impl<T: Clone + Send + 'static> Clone for Owned<T> {
     }
 }
 
-impl<T> Deref for Owned<T> {
+impl<T: ?Sized> Deref for Owned<T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
@@ -54,13 +447,13 @@ impl<T> Deref for Owned<T> {
     }
 }
 
-impl<T> DerefMut for Owned<T> {
+impl<T: ?Sized> DerefMut for Owned<T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         unsafe { &mut self.node.as_mut().data }
     }
 }
 
-impl<T> Drop for Owned<T> {
+impl<T: ?Sized> Drop for Owned<T> {
     fn drop(&mut self) {
         unsafe {
             Node::queue_drop(self.node.as_ptr());
@@ -0,0 +1,28 @@
+use core::ops::Deref;
+
+/// Aligns a value to its own cache line, so that writes to it don't
+/// contend with accesses to neighboring data (false sharing).
+///
+/// 64 bytes is the cache line size on most current x86-64 and ARM cores.
+#[repr(align(64))]
+pub(crate) struct CachePadded<T>(T);
+
+impl<T> CachePadded<T> {
+    pub(crate) const fn new(value: T) -> CachePadded<T> {
+        CachePadded(value)
+    }
+}
+
+impl<T: Default> Default for CachePadded<T> {
+    fn default() -> CachePadded<T> {
+        CachePadded(T::default())
+    }
+}
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
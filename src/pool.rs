@@ -0,0 +1,491 @@
+use crate::collector::{free_node, Batch, NodeHeader};
+use crate::{Handle, Node};
+
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ops::{Deref, DerefMut};
+use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+/// The slot is available to be acquired.
+const FREE: u8 = 0;
+/// The slot holds a value, owned by a [`Pooled`] pointer or queued for
+/// collection.
+const IN_USE: u8 = 1;
+/// The slot was in use when its [`Pool`] was dropped, so it is freed instead
+/// of being returned once collected.
+const ORPHANED: u8 = 2;
+
+struct Slot<T> {
+    state: AtomicU8,
+    value: MaybeUninit<T>,
+}
+
+/// Drops the value in a pool slot and returns the slot to its pool, or frees
+/// it if the pool is gone.
+unsafe fn drop_pooled<T>(node: *mut NodeHeader) -> bool {
+    // The pool may be reading the slot's state concurrently, so the slot is
+    // only accessed through raw pointers to its fields.
+    let slot = core::ptr::addr_of_mut!((*(node as *mut Node<Slot<T>>)).data);
+    (*core::ptr::addr_of_mut!((*slot).value)).as_mut_ptr().drop_in_place();
+
+    NodeHeader::revive(node);
+    let state = &*core::ptr::addr_of!((*slot).state);
+    match state.compare_exchange(IN_USE, FREE, Ordering::Release, Ordering::Acquire) {
+        Ok(_) => false,
+        Err(_) => free_node::<Slot<T>>(node),
+    }
+}
+
+/// A fixed-capacity pool of preallocated nodes, for allocating on a
+/// real-time thread.
+///
+/// [`Owned`] and [`Shared`] make freeing memory safe on real-time threads,
+/// but constructing them still calls into the allocator. A `Pool<T>`
+/// allocates room for `capacity` values of `T` up front, on a non-real-time
+/// thread. Afterwards, [`acquire`] places a value in a free slot without
+/// blocking or allocating, and returns a [`Pooled`] pointer to it. When the
+/// `Pooled` pointer is dropped, the slot is added to the drop queue of the
+/// [`Collector`] as usual; collecting it drops the value and makes the slot
+/// available again.
+///
+/// Each slot counts as a live allocation of the [`Collector`] until the
+/// `Pool` is dropped and the slot has been collected.
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, Pool};
+///
+/// let mut collector = Collector::new();
+/// let pool = Pool::new(&collector.handle(), 2);
+///
+/// // On the audio thread:
+/// let a = pool.acquire([0.0f32; 64]).unwrap();
+/// let b = pool.acquire([0.0f32; 64]).unwrap();
+/// assert!(pool.acquire([0.0f32; 64]).is_err());
+/// drop((a, b));
+///
+/// collector.collect();
+/// assert_eq!(pool.available(), 2);
+/// ```
+///
+/// [`Owned`]: crate::Owned
+/// [`Shared`]: crate::Shared
+/// [`acquire`]: crate::Pool::acquire
+/// [`Pooled`]: crate::Pooled
+/// [`Collector`]: crate::Collector
+pub struct Pool<T> {
+    slots: Vec<NonNull<Node<Slot<T>>>>,
+    next: AtomicUsize,
+}
+
+unsafe impl<T: Send> Send for Pool<T> {}
+unsafe impl<T: Send> Sync for Pool<T> {}
+
+impl<T> UnwindSafe for Pool<T> {}
+impl<T> RefUnwindSafe for Pool<T> {}
+
+impl<T: Send + 'static> Pool<T> {
+    /// Constructs a new `Pool<T>`, allocating `capacity` slots. This should
+    /// not be called from a real-time thread.
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn new(handle: &Handle, capacity: usize) -> Pool<T> {
+        let slots = (0..capacity)
+            .map(|_| unsafe {
+                let node = Node::<Slot<T>>::alloc_uninit(handle, drop_pooled::<T>);
+                core::ptr::addr_of_mut!((*node).data.state).write(AtomicU8::new(FREE));
+                NonNull::new_unchecked(node)
+            })
+            .collect();
+
+        Pool {
+            slots,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Places `value` in a free slot, returning a [`Pooled`] pointer to it.
+    /// If no slot is free, `value` is returned as an error.
+    ///
+    /// This never blocks or allocates, and finishes after at most
+    /// `capacity` attempts, so it can be called from a real-time thread.
+    ///
+    /// [`Pooled`]: crate::Pooled
+    pub fn acquire(&self, value: T) -> Result<Pooled<T>, T> {
+        if let Some(node) = claim(&self.slots, &self.next) {
+            unsafe {
+                let data = core::ptr::addr_of_mut!((*node.as_ptr()).data.value);
+                (*data).as_mut_ptr().write(value);
+            }
+
+            return Ok(Pooled {
+                node,
+                phantom: PhantomData,
+            });
+        }
+
+        Err(value)
+    }
+}
+
+impl<T> Pool<T> {
+    /// Returns the number of slots in the pool.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the number of slots which are currently free. Slots are only
+    /// returned to the pool once the [`Collector`] has collected them.
+    ///
+    /// [`Collector`]: crate::Collector
+    pub fn available(&self) -> usize {
+        available(&self.slots)
+    }
+}
+
+impl<T> Drop for Pool<T> {
+    fn drop(&mut self) {
+        // Free slots are queued all at once.
+        let mut batch = Batch::new();
+        for node in &self.slots {
+            unsafe {
+                if orphan(*node) {
+                    Node::set_drop(node.as_ptr(), free_node::<Slot<T>>);
+                    batch.push(node.as_ptr() as *mut NodeHeader);
+                }
+            }
+        }
+    }
+}
+
+/// Marks a free slot as in use and returns it, trying each slot at most
+/// once.
+fn claim<T>(
+    slots: &[NonNull<Node<Slot<T>>>],
+    next: &AtomicUsize,
+) -> Option<NonNull<Node<Slot<T>>>> {
+    let start = next.fetch_add(1, Ordering::Relaxed);
+    for i in 0..slots.len() {
+        let node = slots[start.wrapping_add(i) % slots.len()];
+
+        let state = unsafe { &*core::ptr::addr_of!((*node.as_ptr()).data.state) };
+        if state.compare_exchange(FREE, IN_USE, Ordering::Acquire, Ordering::Relaxed).is_ok() {
+            return Some(node);
+        }
+    }
+
+    None
+}
+
+fn available<T>(slots: &[NonNull<Node<Slot<T>>>]) -> usize {
+    slots
+        .iter()
+        .filter(|node| unsafe {
+            (*core::ptr::addr_of!((*node.as_ptr()).data.state)).load(Ordering::Relaxed) == FREE
+        })
+        .count()
+}
+
+/// Marks a slot as orphaned when its pool is dropped. Returns true if the
+/// slot was free, in which case it is up to the pool to queue it; a slot
+/// which is in use is freed once it is collected.
+unsafe fn orphan<T>(node: NonNull<Node<Slot<T>>>) -> bool {
+    let state = &*core::ptr::addr_of!((*node.as_ptr()).data.state);
+    let mut current = FREE;
+    while let Err(actual) =
+        state.compare_exchange(current, ORPHANED, Ordering::Acquire, Ordering::Acquire)
+    {
+        current = actual;
+    }
+
+    current == FREE
+}
+
+/// Resets a value to a reusable state, so that a [`RecyclePool`] can hand it
+/// out again instead of dropping it.
+///
+/// [`RecyclePool`]: crate::RecyclePool
+pub trait Recycle {
+    /// Resets the value. This is called by the [`Collector`], not on the
+    /// thread which dropped the [`Pooled`] pointer, so it may e.g. clear a
+    /// buffer or release other resources.
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`Pooled`]: crate::Pooled
+    fn recycle(&mut self);
+}
+
+/// Recycles the value in a recycling pool slot and returns the slot to its
+/// pool, or drops and frees it if the pool is gone.
+unsafe fn recycle_pooled<T: Recycle>(node: *mut NodeHeader) -> bool {
+    let slot = core::ptr::addr_of_mut!((*(node as *mut Node<Slot<T>>)).data);
+    (*(*core::ptr::addr_of_mut!((*slot).value)).as_mut_ptr()).recycle();
+
+    NodeHeader::revive(node);
+    let state = &*core::ptr::addr_of!((*slot).state);
+    match state.compare_exchange(IN_USE, FREE, Ordering::Release, Ordering::Acquire) {
+        Ok(_) => false,
+        Err(_) => drop_recycled::<T>(node),
+    }
+}
+
+/// Drops the value in a recycling pool slot whose pool is gone, and frees
+/// the slot.
+unsafe fn drop_recycled<T>(node: *mut NodeHeader) -> bool {
+    let slot = core::ptr::addr_of_mut!((*(node as *mut Node<Slot<T>>)).data);
+    (*core::ptr::addr_of_mut!((*slot).value)).as_mut_ptr().drop_in_place();
+    free_node::<Slot<T>>(node)
+}
+
+/// A fixed-capacity pool of values which are recycled rather than dropped.
+///
+/// Like a [`Pool`], a `RecyclePool<T>` allocates all of its slots up front.
+/// Unlike a [`Pool`], it also fills them up front, using a function to
+/// construct each value. [`acquire`] hands out one of these values without
+/// blocking or allocating. When the returned [`Pooled`] pointer is dropped
+/// and collected, the [`Collector`] calls [`Recycle::recycle`] on the value
+/// instead of dropping it, and the slot becomes available again with the
+/// value still in place. Values are only dropped once the `RecyclePool`
+/// itself is dropped.
+///
+/// This suits objects which are identical from one use to the next, such as
+/// voices or audio buffers, for which going through the allocator every
+/// time would be wasted work.
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, Recycle, RecyclePool};
+///
+/// struct Buffer(Vec<f32>);
+///
+/// impl Recycle for Buffer {
+///     fn recycle(&mut self) {
+///         self.0.iter_mut().for_each(|sample| *sample = 0.0);
+///     }
+/// }
+///
+/// let mut collector = Collector::new();
+/// let pool = RecyclePool::new(&collector.handle(), 1, || Buffer(vec![0.0; 64]));
+///
+/// // On the audio thread:
+/// let mut buffer = pool.acquire().unwrap();
+/// buffer.0[0] = 1.0;
+/// assert!(pool.acquire().is_none());
+/// drop(buffer);
+///
+/// collector.collect();
+/// let buffer = pool.acquire().unwrap();
+/// assert_eq!(buffer.0[0], 0.0);
+/// ```
+///
+/// [`Pool`]: crate::Pool
+/// [`acquire`]: crate::RecyclePool::acquire
+/// [`Pooled`]: crate::Pooled
+/// [`Collector`]: crate::Collector
+/// [`Recycle::recycle`]: crate::Recycle::recycle
+pub struct RecyclePool<T> {
+    slots: Vec<NonNull<Node<Slot<T>>>>,
+    next: AtomicUsize,
+}
+
+unsafe impl<T: Send> Send for RecyclePool<T> {}
+unsafe impl<T: Send> Sync for RecyclePool<T> {}
+
+impl<T> UnwindSafe for RecyclePool<T> {}
+impl<T> RefUnwindSafe for RecyclePool<T> {}
+
+impl<T: Recycle + Send + 'static> RecyclePool<T> {
+    /// Constructs a new `RecyclePool<T>`, allocating `capacity` slots and
+    /// filling each with a value returned by `init`. This should not be
+    /// called from a real-time thread.
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn new<F: FnMut() -> T>(handle: &Handle, capacity: usize, mut init: F) -> RecyclePool<T> {
+        let slots = (0..capacity)
+            .map(|_| unsafe {
+                let node = Node::<Slot<T>>::alloc_uninit(handle, recycle_pooled::<T>);
+                core::ptr::addr_of_mut!((*node).data.state).write(AtomicU8::new(FREE));
+                let value = core::ptr::addr_of_mut!((*node).data.value);
+                (*value).as_mut_ptr().write(init());
+                NonNull::new_unchecked(node)
+            })
+            .collect();
+
+        RecyclePool {
+            slots,
+            next: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<T> RecyclePool<T> {
+    /// Returns a [`Pooled`] pointer to a recycled value, or `None` if all of
+    /// them are in use.
+    ///
+    /// This never blocks or allocates, and finishes after at most
+    /// `capacity` attempts, so it can be called from a real-time thread.
+    ///
+    /// [`Pooled`]: crate::Pooled
+    pub fn acquire(&self) -> Option<Pooled<T>> {
+        claim(&self.slots, &self.next).map(|node| Pooled {
+            node,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Returns the number of slots in the pool.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the number of values which are currently available. Values
+    /// are only returned to the pool once the [`Collector`] has recycled
+    /// them.
+    ///
+    /// [`Collector`]: crate::Collector
+    pub fn available(&self) -> usize {
+        available(&self.slots)
+    }
+}
+
+impl<T> Drop for RecyclePool<T> {
+    fn drop(&mut self) {
+        let mut batch = Batch::new();
+        for node in &self.slots {
+            unsafe {
+                if orphan(*node) {
+                    Node::set_drop(node.as_ptr(), drop_recycled::<T>);
+                    batch.push(node.as_ptr() as *mut NodeHeader);
+                }
+            }
+        }
+    }
+}
+
+/// A pointer to a value in a [`Pool`] or [`RecyclePool`], which returns its
+/// slot to the pool once it is dropped and collected.
+///
+/// [`Pool`]: crate::Pool
+/// [`RecyclePool`]: crate::RecyclePool
+pub struct Pooled<T> {
+    node: NonNull<Node<Slot<T>>>,
+    phantom: PhantomData<T>,
+}
+
+unsafe impl<T: Send> Send for Pooled<T> {}
+unsafe impl<T: Sync> Sync for Pooled<T> {}
+
+impl<T: UnwindSafe> UnwindSafe for Pooled<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Pooled<T> {}
+impl<T> Unpin for Pooled<T> {}
+
+impl<T> Deref for Pooled<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*(*core::ptr::addr_of!((*self.node.as_ptr()).data.value)).as_ptr() }
+    }
+}
+
+impl<T> DerefMut for Pooled<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe {
+            let value = core::ptr::addr_of_mut!((*self.node.as_ptr()).data.value);
+            &mut *(*value).as_mut_ptr()
+        }
+    }
+}
+
+impl<T> Drop for Pooled<T> {
+    fn drop(&mut self) {
+        unsafe {
+            Node::queue_drop(self.node.as_ptr());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Collector, Pool};
+
+    extern crate alloc;
+    use alloc::sync::Arc;
+
+    #[test]
+    fn pool() {
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+        let pool = Pool::new(&handle, 2);
+        let value = Arc::new(());
+
+        let a = pool.acquire(value.clone()).unwrap();
+        let b = pool.acquire(value.clone()).unwrap();
+        assert!(pool.acquire(value.clone()).is_err());
+        assert_eq!(Arc::strong_count(&value), 3);
+
+        drop(a);
+        assert_eq!(pool.available(), 0);
+        collector.collect();
+        assert_eq!(pool.available(), 1);
+        assert_eq!(Arc::strong_count(&value), 2);
+        assert_eq!(collector.alloc_count(), 2);
+
+        let c = pool.acquire(value.clone()).unwrap();
+        assert!(Arc::ptr_eq(&c, &value));
+
+        // Slots still in use when the pool is dropped are freed once they
+        // are collected.
+        drop((pool, b, c, handle));
+        collector.collect();
+        assert_eq!(Arc::strong_count(&value), 1);
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[test]
+    fn recycle_pool() {
+        use crate::{Recycle, RecyclePool};
+
+        struct Voice {
+            phase: f32,
+            _value: Arc<()>,
+        }
+
+        impl Recycle for Voice {
+            fn recycle(&mut self) {
+                self.phase = 0.0;
+            }
+        }
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+        let value = Arc::new(());
+        let pool = RecyclePool::new(&handle, 2, || Voice { phase: 0.0, _value: value.clone() });
+        assert_eq!(Arc::strong_count(&value), 3);
+
+        let mut a = pool.acquire().unwrap();
+        let b = pool.acquire().unwrap();
+        assert!(pool.acquire().is_none());
+        a.phase = 0.5;
+
+        drop(a);
+        collector.collect();
+        assert_eq!(pool.available(), 1);
+        assert_eq!(Arc::strong_count(&value), 3);
+
+        let c = pool.acquire().unwrap();
+        assert_eq!(c.phase, 0.0);
+
+        // Values are only dropped once the pool and all pointers into it are
+        // gone.
+        drop((pool, b));
+        collector.collect();
+        assert_eq!(Arc::strong_count(&value), 2);
+        drop((c, handle));
+        collector.collect();
+        assert_eq!(Arc::strong_count(&value), 1);
+        assert!(collector.try_cleanup().is_ok());
+    }
+}
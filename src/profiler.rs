@@ -0,0 +1,100 @@
+#[cfg(feature = "latency-stats")]
+use core::time::Duration;
+
+/// Receives timing and statistics for each collection pass, so that a
+/// [`Collector`]'s behavior can be shown on the same timeline as the rest of
+/// an application's profiling data.
+///
+/// A profiler can be installed with [`CollectorBuilder::profiler`]. Each call
+/// to [`Collector::collect`] or [`Collector::collect_until`] is bracketed by
+/// calls to [`begin`] and [`end`], which map naturally onto a profiler zone,
+/// while the [`PassInfo`] passed to [`end`] can be emitted as plots. For
+/// example, with Tracy or puffin, [`begin`] would open a zone named
+/// `"basedrop::collect"`, and [`end`] would close it and plot
+/// [`PassInfo::dropped`] and [`PassInfo::live_allocations`].
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, Owned, PassInfo, Profiler};
+/// use std::time::Instant;
+///
+/// struct Timer(Option<Instant>);
+///
+/// impl Profiler for Timer {
+///     fn begin(&mut self) {
+///         self.0 = Some(Instant::now());
+///     }
+///
+///     fn end(&mut self, pass: &PassInfo) {
+///         let elapsed = self.0.take().unwrap().elapsed();
+///         println!("dropped {} allocations in {:?}", pass.dropped, elapsed);
+///     }
+/// }
+///
+/// let mut collector = Collector::builder().profiler(Timer(None)).build();
+/// drop(Owned::new(&collector.handle(), 3));
+/// collector.collect();
+/// ```
+///
+/// [`Collector`]: crate::Collector
+/// [`CollectorBuilder::profiler`]: crate::CollectorBuilder::profiler
+/// [`Collector::collect`]: crate::Collector::collect
+/// [`Collector::collect_until`]: crate::Collector::collect_until
+/// [`begin`]: crate::Profiler::begin
+/// [`end`]: crate::Profiler::end
+/// [`PassInfo`]: crate::PassInfo
+/// [`PassInfo::dropped`]: crate::PassInfo::dropped
+/// [`PassInfo::live_allocations`]: crate::PassInfo::live_allocations
+pub trait Profiler: Send {
+    /// Called when a collection pass begins.
+    fn begin(&mut self) {}
+
+    /// Called when a collection pass ends.
+    fn end(&mut self, pass: &PassInfo);
+}
+
+/// Statistics for a single collection pass, passed to [`Profiler::end`].
+///
+/// [`Profiler::end`]: crate::Profiler::end
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct PassInfo {
+    /// The number of allocations removed from the drop queue.
+    pub dropped: usize,
+    /// Whether the drop queue was fully drained. This is false when
+    /// [`Collector::collect_until`] reached its deadline first.
+    ///
+    /// [`Collector::collect_until`]: crate::Collector::collect_until
+    pub completed: bool,
+    /// The number of allocations still live after the pass, or `None` if
+    /// counters are disabled.
+    pub live_allocations: Option<usize>,
+    /// The collection epoch after the pass.
+    pub epoch: usize,
+    /// How long the allocations dropped in the pass had been waiting in the
+    /// drop queue, or `None` if no clock was installed with
+    /// [`CollectorBuilder::reclamation_clock`] or nothing was dropped. Only
+    /// available with the `latency-stats` feature.
+    ///
+    /// [`CollectorBuilder::reclamation_clock`]: crate::CollectorBuilder::reclamation_clock
+    #[cfg(feature = "latency-stats")]
+    pub latency: Option<LatencyStats>,
+}
+
+/// How long allocations waited in the drop queue before being collected,
+/// as reported by [`Collector::latency_stats`] and [`PassInfo::latency`].
+/// Only available with the `latency-stats` feature.
+///
+/// [`Collector::latency_stats`]: crate::Collector::latency_stats
+/// [`PassInfo::latency`]: crate::PassInfo::latency
+#[cfg(feature = "latency-stats")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LatencyStats {
+    /// The number of allocations measured.
+    pub count: usize,
+    /// The longest time an allocation spent in the queue.
+    pub max: Duration,
+    /// The average time an allocation spent in the queue.
+    pub mean: Duration,
+}
@@ -0,0 +1,124 @@
+use crate::{Cache, Handle, Shared, SharedCell};
+
+/// A [`SharedCell`] bundled with the [`Handle`] used to allocate new values
+/// for it.
+///
+/// This covers the common pattern of state which is published by
+/// non-real-time threads and read by real-time threads: [`publish`] and
+/// [`update`] allocate and swap in a new value, while [`read`] and [`cache`]
+/// are as cheap as the corresponding [`SharedCell`] operations.
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, Published};
+///
+/// let mut collector = Collector::new();
+/// let gain = Published::new(&collector.handle(), 0.5f32);
+///
+/// // On the UI thread:
+/// gain.update(|gain| gain * 2.0);
+///
+/// // On the audio thread:
+/// assert_eq!(*gain.read(), 1.0);
+///
+/// collector.collect();
+/// ```
+///
+/// [`SharedCell`]: crate::SharedCell
+/// [`Handle`]: crate::Handle
+/// [`publish`]: crate::Published::publish
+/// [`update`]: crate::Published::update
+/// [`read`]: crate::Published::read
+/// [`cache`]: crate::Published::cache
+pub struct Published<T> {
+    handle: Handle,
+    cell: SharedCell<T>,
+}
+
+impl<T: Send + 'static> Published<T> {
+    /// Constructs a new `Published` containing `value`.
+    pub fn new(handle: &Handle, value: T) -> Published<T> {
+        Published {
+            handle: handle.clone(),
+            cell: SharedCell::new(Shared::new(handle, value)),
+        }
+    }
+
+    /// Allocates `value` and publishes it, replacing the current value. This
+    /// allocates, and so should not be called from a real-time thread.
+    pub fn publish(&self, value: T) {
+        self.cell.set(Shared::new(&self.handle, value));
+    }
+
+    /// Publishes a new value computed from the current one. This allocates,
+    /// and so should not be called from a real-time thread.
+    ///
+    /// The read and the write are not a single atomic operation, so if
+    /// multiple threads update the same `Published` concurrently, some
+    /// updates may be lost. Such writers must coordinate externally.
+    pub fn update<F: FnOnce(&T) -> T>(&self, f: F) {
+        let current = self.cell.get();
+        self.publish(f(&current));
+    }
+}
+
+impl<T> Published<T> {
+    /// Gets the current value.
+    pub fn read(&self) -> Shared<T> {
+        self.cell.get()
+    }
+
+    /// Creates a [`Cache`] of the current value, for a thread which reads it
+    /// repeatedly.
+    ///
+    /// [`Cache`]: crate::Cache
+    pub fn cache(&self) -> Cache<'_, T> {
+        self.cell.cache()
+    }
+
+    /// Gets the number of values which have been published since this
+    /// `Published` was constructed.
+    pub fn generation(&self) -> usize {
+        self.cell.generation()
+    }
+
+    /// Gets the [`Handle`] used to allocate published values.
+    ///
+    /// [`Handle`]: crate::Handle
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+
+    /// Gets the underlying [`SharedCell`].
+    ///
+    /// [`SharedCell`]: crate::SharedCell
+    pub fn cell(&self) -> &SharedCell<T> {
+        &self.cell
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Collector, Published};
+
+    #[test]
+    fn published() {
+        let mut collector = Collector::new();
+        let published = Published::new(&collector.handle(), 1);
+        let mut cache = published.cache();
+
+        published.publish(2);
+        published.update(|x| x + 1);
+        assert_eq!(published.generation(), 2);
+        assert_eq!(*published.read(), 3);
+        assert_eq!(**cache.load(), 3);
+
+        collector.collect();
+        assert_eq!(collector.alloc_count(), 1);
+
+        drop(cache);
+        drop(published);
+        collector.collect();
+        assert!(collector.try_cleanup().is_ok());
+    }
+}
@@ -0,0 +1,420 @@
+use crate::{Handle, Node, Owned, Shared};
+
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+struct Entry<T> {
+    time: u64,
+    // Breaks ties between equal timestamps. This is a `usize` so that 32-bit
+    // targets without 64-bit atomics are supported; there, wrapping around
+    // can misorder two events with the same timestamp which straddle it.
+    seq: usize,
+    next: *mut Node<Entry<T>>,
+    value: T,
+}
+
+unsafe impl<T: Send> Send for Entry<T> {}
+
+impl<T> Entry<T> {
+    fn key(&self) -> (u64, usize) {
+        (self.time, self.seq)
+    }
+}
+
+struct EntryPtr<T>(NonNull<Node<Entry<T>>>);
+
+unsafe impl<T: Send> Send for EntryPtr<T> {}
+
+impl<T> EntryPtr<T> {
+    fn key(&self) -> (u64, usize) {
+        unsafe { self.0.as_ref().data.key() }
+    }
+}
+
+/// The state shared between an [`EventScheduler`] and its [`EventQueue`]: a
+/// lock-free stack of newly scheduled entries.
+struct Incoming<T> {
+    head: AtomicPtr<Node<Entry<T>>>,
+    seq: AtomicUsize,
+}
+
+unsafe impl<T: Send> Send for Incoming<T> {}
+unsafe impl<T: Send> Sync for Incoming<T> {}
+
+impl<T> Drop for Incoming<T> {
+    fn drop(&mut self) {
+        release_list(*self.head.get_mut());
+    }
+}
+
+/// Queues every entry in a linked list for collection.
+fn release_list<T>(mut node: *mut Node<Entry<T>>) {
+    while !node.is_null() {
+        unsafe {
+            let next = (*node).data.next;
+            drop(Owned { node: NonNull::new_unchecked(node), phantom: PhantomData });
+            node = next;
+        }
+    }
+}
+
+/// Creates a queue of timestamped events, returning its scheduling and
+/// receiving halves.
+///
+/// Events are scheduled from non-real-time threads via the
+/// [`EventScheduler`], which allocates them, and are received in timestamp
+/// order on a real-time thread via the [`EventQueue`], which never blocks or
+/// allocates. Events scheduled with equal timestamps are received in the
+/// order in which they were scheduled. Received events, as well as any events
+/// still pending when the queue is dropped, are reclaimed by the
+/// [`Collector`].
+///
+/// There is no way to cancel an event once it has been scheduled. To retract
+/// one, schedule values which the real-time side can recognize as stale,
+/// e.g. by tagging them with a generation number, and discard those as they
+/// are received.
+///
+/// `capacity` is the number of pending events the [`EventQueue`] can keep
+/// ordered in its heap. Beyond that, events are kept in an unordered overflow
+/// list, which is correct but makes receiving them linear-time.
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, event_queue};
+///
+/// let mut collector = Collector::new();
+/// let (scheduler, mut queue) = event_queue(&collector.handle(), 64);
+///
+/// scheduler.schedule(256, "note off");
+/// scheduler.schedule(128, "note on");
+///
+/// // On the audio thread, once per block:
+/// let block_end = 192;
+/// while let Some(event) = queue.pop_due(block_end) {
+///     assert_eq!(event.time(), 128);
+///     assert_eq!(*event, "note on");
+/// }
+///
+/// collector.collect();
+/// ```
+///
+/// [`EventScheduler`]: crate::EventScheduler
+/// [`EventQueue`]: crate::EventQueue
+/// [`Collector`]: crate::Collector
+pub fn event_queue<T: Send + 'static>(
+    handle: &Handle,
+    capacity: usize,
+) -> (EventScheduler<T>, EventQueue<T>) {
+    let incoming = Shared::new(handle, Incoming {
+        head: AtomicPtr::new(core::ptr::null_mut()),
+        seq: AtomicUsize::new(0),
+    });
+
+    let scheduler = EventScheduler {
+        handle: handle.clone(),
+        incoming: incoming.clone(),
+    };
+
+    let queue = EventQueue {
+        incoming,
+        heap: Owned::new(handle, Vec::with_capacity(capacity)),
+        overflow: core::ptr::null_mut(),
+    };
+
+    (scheduler, queue)
+}
+
+/// The scheduling half of an [`event_queue`], used from non-real-time
+/// threads.
+///
+/// [`event_queue`]: crate::event_queue
+pub struct EventScheduler<T> {
+    handle: Handle,
+    incoming: Shared<Incoming<T>>,
+}
+
+impl<T: Send + 'static> EventScheduler<T> {
+    /// Schedules `value` to be received once the [`EventQueue`] reaches
+    /// `time`. This allocates, and so should not be called from a real-time
+    /// thread.
+    ///
+    /// [`EventQueue`]: crate::EventQueue
+    pub fn schedule(&self, time: u64, value: T) {
+        let seq = self.incoming.seq.fetch_add(1, Ordering::Relaxed);
+        let entry = Owned::new(&self.handle, Entry {
+            time,
+            seq,
+            next: core::ptr::null_mut(),
+            value,
+        });
+        let node = entry.node.as_ptr();
+        core::mem::forget(entry);
+
+        let mut head = self.incoming.head.load(Ordering::Relaxed);
+        loop {
+            unsafe {
+                (*node).data.next = head;
+            }
+
+            match self.incoming.head.compare_exchange_weak(
+                head,
+                node,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(current) => head = current,
+            }
+        }
+    }
+}
+
+impl<T> Clone for EventScheduler<T> {
+    fn clone(&self) -> Self {
+        EventScheduler {
+            handle: self.handle.clone(),
+            incoming: self.incoming.clone(),
+        }
+    }
+}
+
+/// The receiving half of an [`event_queue`], used from a real-time thread.
+///
+/// None of `EventQueue`'s methods block or allocate.
+///
+/// [`event_queue`]: crate::event_queue
+pub struct EventQueue<T> {
+    incoming: Shared<Incoming<T>>,
+    heap: Owned<Vec<EntryPtr<T>>>,
+    overflow: *mut Node<Entry<T>>,
+}
+
+unsafe impl<T: Send> Send for EventQueue<T> {}
+
+impl<T> EventQueue<T> {
+    /// Removes and returns the earliest pending event if its timestamp is at
+    /// or before `now`.
+    pub fn pop_due(&mut self, now: u64) -> Option<Event<T>> {
+        self.receive();
+
+        let heap_key = self.heap.first().map(|entry| entry.key());
+        let (overflow_prev, overflow_key) = self.overflow_min();
+
+        let node = match (heap_key, overflow_key) {
+            (Some(heap_key), Some(overflow_key)) if overflow_key < heap_key => {
+                self.take_overflow(overflow_prev, overflow_key.0, now)?
+            }
+            (None, Some(overflow_key)) => self.take_overflow(overflow_prev, overflow_key.0, now)?,
+            (Some(heap_key), _) => {
+                if heap_key.0 > now {
+                    return None;
+                }
+                self.heap_pop()
+            }
+            (None, None) => return None,
+        };
+
+        Some(Event {
+            entry: Owned { node, phantom: PhantomData },
+        })
+    }
+
+    /// Gets the timestamp of the earliest pending event, if any.
+    pub fn next_time(&mut self) -> Option<u64> {
+        self.receive();
+
+        let heap_time = self.heap.first().map(|entry| entry.key().0);
+        let overflow_time = self.overflow_min().1.map(|key| key.0);
+
+        match (heap_time, overflow_time) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Moves newly scheduled entries into the heap, or into the overflow list
+    /// if the heap is full.
+    fn receive(&mut self) {
+        let mut node = self.incoming.head.swap(core::ptr::null_mut(), Ordering::Acquire);
+        while !node.is_null() {
+            unsafe {
+                let next = (*node).data.next;
+
+                if self.heap.len() < self.heap.capacity() {
+                    self.heap_push(EntryPtr(NonNull::new_unchecked(node)));
+                } else {
+                    (*node).data.next = self.overflow;
+                    self.overflow = node;
+                }
+
+                node = next;
+            }
+        }
+    }
+
+    /// Finds the earliest entry in the overflow list, returning it along with
+    /// its predecessor (or null if it is at the front).
+    // `Option::is_none_or` would need Rust 1.82.
+    #[allow(clippy::unnecessary_map_or)]
+    fn overflow_min(&self) -> (*mut Node<Entry<T>>, Option<(u64, usize)>) {
+        let mut min_prev = core::ptr::null_mut();
+        let mut min_key = None;
+
+        let mut prev = core::ptr::null_mut();
+        let mut node = self.overflow;
+        while !node.is_null() {
+            unsafe {
+                let key = (*node).data.key();
+                if min_key.map_or(true, |min| key < min) {
+                    min_prev = prev;
+                    min_key = Some(key);
+                }
+
+                prev = node;
+                node = (*node).data.next;
+            }
+        }
+
+        (min_prev, min_key)
+    }
+
+    fn take_overflow(
+        &mut self,
+        prev: *mut Node<Entry<T>>,
+        time: u64,
+        now: u64,
+    ) -> Option<NonNull<Node<Entry<T>>>> {
+        if time > now {
+            return None;
+        }
+
+        unsafe {
+            let node = if prev.is_null() { self.overflow } else { (*prev).data.next };
+            let next = (*node).data.next;
+            if prev.is_null() {
+                self.overflow = next;
+            } else {
+                (*prev).data.next = next;
+            }
+
+            Some(NonNull::new_unchecked(node))
+        }
+    }
+
+    fn heap_push(&mut self, entry: EntryPtr<T>) {
+        let heap = &mut *self.heap;
+        heap.push(entry);
+
+        let mut i = heap.len() - 1;
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if heap[i].key() >= heap[parent].key() {
+                break;
+            }
+            heap.swap(i, parent);
+            i = parent;
+        }
+    }
+
+    fn heap_pop(&mut self) -> NonNull<Node<Entry<T>>> {
+        let heap = &mut *self.heap;
+        let top = heap.swap_remove(0);
+
+        let mut i = 0;
+        loop {
+            let left = 2 * i + 1;
+            let right = left + 1;
+
+            let mut min = i;
+            if left < heap.len() && heap[left].key() < heap[min].key() {
+                min = left;
+            }
+            if right < heap.len() && heap[right].key() < heap[min].key() {
+                min = right;
+            }
+            if min == i {
+                break;
+            }
+
+            heap.swap(i, min);
+            i = min;
+        }
+
+        top.0
+    }
+}
+
+impl<T> Drop for EventQueue<T> {
+    fn drop(&mut self) {
+        for entry in self.heap.drain(..) {
+            drop(Owned { node: entry.0, phantom: PhantomData });
+        }
+        release_list(self.overflow);
+    }
+}
+
+/// An event received from an [`EventQueue`]. Dropping it adds it to the
+/// [`Collector`]'s drop queue.
+///
+/// [`EventQueue`]: crate::EventQueue
+/// [`Collector`]: crate::Collector
+pub struct Event<T> {
+    entry: Owned<Entry<T>>,
+}
+
+impl<T> Event<T> {
+    /// Gets the timestamp this event was scheduled for.
+    pub fn time(&self) -> u64 {
+        self.entry.time
+    }
+}
+
+impl<T> Deref for Event<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.entry.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{event_queue, Collector};
+
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn event_queue_order() {
+        let mut collector = Collector::new();
+        let (scheduler, mut queue) = event_queue(&collector.handle(), 4);
+
+        for (time, value) in [(30, 0), (10, 1), (20, 2), (10, 3), (50, 4), (5, 5), (40, 6)] {
+            scheduler.schedule(time, value);
+        }
+
+        assert_eq!(queue.next_time(), Some(5));
+        assert!(queue.pop_due(4).is_none());
+
+        let mut received = Vec::new();
+        while let Some(event) = queue.pop_due(30) {
+            received.push((event.time(), *event));
+        }
+        assert_eq!(received, [(5, 5), (10, 1), (10, 3), (20, 2), (30, 0)]);
+        assert_eq!(queue.next_time(), Some(40));
+
+        // The two remaining events, the heap and the shared incoming list.
+        collector.collect();
+        assert_eq!(collector.alloc_count(), 4);
+
+        core::mem::drop(scheduler);
+        core::mem::drop(queue);
+        collector.collect();
+        assert_eq!(collector.alloc_count(), 0);
+    }
+}
@@ -1,7 +1,14 @@
-use crate::{Handle, Node};
+use crate::collector::{free_node, Batch, NodeHeader, UninitGuard};
+use crate::{AllocError, Handle, Node};
 
+#[cfg(feature = "nightly")]
+use core::marker::Unsize;
 use core::marker::PhantomData;
-use core::ops::Deref;
+#[cfg(feature = "nightly")]
+use core::ops::{CoerceUnsized, DispatchFromDyn};
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::ops::{Deref, DerefMut};
+use core::panic::{RefUnwindSafe, UnwindSafe};
 use core::ptr::NonNull;
 use core::sync::atomic::{AtomicUsize, Ordering, fence};
 
@@ -13,20 +20,71 @@ use core::sync::atomic::{AtomicUsize, Ordering, fence};
 /// allocated with. As the collector may be on another thread, contents are
 /// required to be `Send + 'static`.
 ///
+/// [`Weak`] pointers to the same allocation can be created with
+/// [`Shared::downgrade`].
+///
 /// [`Collector`]: crate::Collector
 /// [`Handle`]: crate::Handle
-pub struct Shared<T> {
+/// [`Weak`]: crate::Weak
+/// [`Shared::downgrade`]: crate::Shared::downgrade
+pub struct Shared<T: ?Sized> {
     pub(crate) node: NonNull<Node<SharedInner<T>>>,
     pub(crate) phantom: PhantomData<SharedInner<T>>,
 }
 
-pub(crate) struct SharedInner<T> {
+/// Set in the reference count of a leaked allocation, ensuring that the count
+/// never reaches zero.
+const LEAKED: usize = !(usize::MAX >> 1);
+
+/// Stored in the weak count while [`Shared::get_mut`] checks for uniqueness,
+/// preventing new `Weak` pointers from being created in the meantime.
+const LOCKED: usize = usize::MAX;
+
+// `repr(C)` so that `SharedInner<MaybeUninit<T>>` has the same layout as
+// `SharedInner<T>`, for `Shared::new_cyclic`.
+#[repr(C)]
+pub(crate) struct SharedInner<T: ?Sized> {
     count: AtomicUsize,
-    data: T,
+    /// The number of `Weak` pointers, plus one held collectively by the
+    /// `Shared` pointers until the contents have been dropped.
+    weak: AtomicUsize,
+    data: ManuallyDrop<T>,
 }
 
-unsafe impl<T: Send + Sync> Send for Shared<T> {}
-unsafe impl<T: Send + Sync> Sync for Shared<T> {}
+/// Drops the contents of a `Shared` allocation once its reference count has
+/// reached zero. The allocation itself is only freed if no `Weak` pointers
+/// remain; otherwise it is left in place, and the last `Weak` pointer to be
+/// dropped queues it again.
+unsafe fn drop_shared<T>(node: *mut NodeHeader) -> bool {
+    let node = node as *mut Node<SharedInner<T>>;
+
+    // The weak count can only be zero if the node was queued by the last
+    // `Weak` pointer, after the contents were already dropped.
+    if (*node).data.weak.load(Ordering::Acquire) != 0 {
+        ManuallyDrop::drop(&mut (*node).data.data);
+
+        NodeHeader::revive(node as *mut NodeHeader);
+        if (*node).data.weak.fetch_sub(1, Ordering::Release) != 1 {
+            return false;
+        }
+        fence(Ordering::Acquire);
+    }
+
+    free_node::<SharedInner<T>>(node as *mut NodeHeader)
+}
+
+unsafe impl<T: ?Sized + Send + Sync> Send for Shared<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for Shared<T> {}
+
+impl<T: ?Sized + RefUnwindSafe> UnwindSafe for Shared<T> {}
+impl<T: ?Sized + RefUnwindSafe> RefUnwindSafe for Shared<T> {}
+impl<T: ?Sized> Unpin for Shared<T> {}
+
+#[cfg(feature = "nightly")]
+impl<T: ?Sized + Unsize<U>, U: ?Sized> CoerceUnsized<Shared<U>> for Shared<T> {}
+
+#[cfg(feature = "nightly")]
+impl<T: ?Sized + Unsize<U>, U: ?Sized> DispatchFromDyn<Shared<U>> for Shared<T> {}
 
 impl<T: Send + 'static> Shared<T> {
     /// Constructs a new `Shared<T>`.
@@ -38,14 +96,239 @@ impl<T: Send + 'static> Shared<T> {
     /// let collector = Collector::new();
     /// let three = Shared::new(&collector.handle(), 3);
     /// ```
+    #[cfg_attr(feature = "track-allocations", track_caller)]
     pub fn new(handle: &Handle, data: T) -> Shared<T> {
         Shared {
             node: unsafe {
-                NonNull::new_unchecked(Node::alloc(handle, SharedInner {
-                    count: AtomicUsize::new(1),
-                    data,
-                }))
+                NonNull::new_unchecked(Node::alloc_with_drop(
+                    handle,
+                    SharedInner {
+                        count: AtomicUsize::new(1),
+                        weak: AtomicUsize::new(1),
+                        data: ManuallyDrop::new(data),
+                    },
+                    drop_shared::<T>,
+                ))
+            },
+            phantom: PhantomData,
+        }
+    }
+
+    /// Constructs a new `Shared<T>` with a tag identifying the subsystem
+    /// which made it. See [`Owned::new_tagged`] for details.
+    ///
+    /// [`Owned::new_tagged`]: crate::Owned::new_tagged
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn new_tagged(handle: &Handle, tag: &'static str, data: T) -> Shared<T> {
+        let shared = Shared::new(handle, data);
+        unsafe { Node::set_tag(shared.node.as_ptr(), tag) };
+        shared
+    }
+
+    /// Constructs a new `Shared<T>` by initializing its contents in place,
+    /// without first constructing them on the stack. See [`Owned::new_with`]
+    /// for details.
+    ///
+    /// # Safety
+    ///
+    /// `f` must fully initialize the value before returning. If `f` panics,
+    /// the allocation is freed without dropping its contents.
+    ///
+    /// [`Owned::new_with`]: crate::Owned::new_with
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub unsafe fn new_with<F>(handle: &Handle, f: F) -> Shared<T>
+    where
+        F: FnOnce(&mut MaybeUninit<T>),
+    {
+        let node = Node::<SharedInner<T>>::alloc_uninit(handle, drop_shared::<T>);
+        let inner = core::ptr::addr_of_mut!((*node).data);
+        core::ptr::addr_of_mut!((*inner).count).write(AtomicUsize::new(1));
+        core::ptr::addr_of_mut!((*inner).weak).write(AtomicUsize::new(1));
+
+        let guard = UninitGuard(node);
+        f(&mut *(core::ptr::addr_of_mut!((*inner).data) as *mut MaybeUninit<T>));
+        core::mem::forget(guard);
+
+        Shared {
+            node: NonNull::new_unchecked(node),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Constructs a new `Shared<T>` with uninitialized contents, analogous to
+    /// `Arc::new_uninit`. The contents can be written through
+    /// [`get_mut`] and then marked as initialized with [`assume_init`].
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Shared};
+    ///
+    /// let collector = Collector::new();
+    /// let mut frame = Shared::<[f32; 1024]>::new_uninit(&collector.handle());
+    ///
+    /// let frame = unsafe {
+    ///     let slot = Shared::get_mut(&mut frame).unwrap();
+    ///     (slot.as_mut_ptr() as *mut f32).write_bytes(0, 1024);
+    ///     Shared::assume_init(frame)
+    /// };
+    /// assert_eq!(frame[1023], 0.0);
+    /// ```
+    ///
+    /// [`get_mut`]: crate::Shared::get_mut
+    /// [`assume_init`]: crate::Shared::assume_init
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn new_uninit(handle: &Handle) -> Shared<MaybeUninit<T>> {
+        // Leaving a `MaybeUninit<T>` uninitialized is valid.
+        unsafe { Shared::new_with(handle, |_| {}) }
+    }
+
+    /// Constructs a new `Shared<T>`, returning an error instead of aborting
+    /// if memory can't be allocated. In that case, `data` is dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Shared};
+    ///
+    /// let collector = Collector::new();
+    /// let three = Shared::try_new(&collector.handle(), 3).unwrap();
+    /// ```
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn try_new(handle: &Handle, data: T) -> Result<Shared<T>, AllocError> {
+        let node = Node::try_alloc_with_drop(
+            handle,
+            SharedInner {
+                count: AtomicUsize::new(1),
+                weak: AtomicUsize::new(1),
+                data: ManuallyDrop::new(data),
+            },
+            drop_shared::<T>,
+        )?;
+
+        Ok(Shared {
+            node: unsafe { NonNull::new_unchecked(node) },
+            phantom: PhantomData,
+        })
+    }
+
+    /// Constructs a new `Shared<T>` whose contents can hold a [`Weak`]
+    /// pointer to the allocation itself, analogous to `Arc::new_cyclic`.
+    ///
+    /// `data_fn` is passed a [`Weak`] pointer to the allocation before the
+    /// contents have been constructed. It can be cloned and stored in the
+    /// contents, but calling [`Weak::upgrade`] on it returns `None` until
+    /// `new_cyclic` returns.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Shared, Weak};
+    ///
+    /// struct Observer {
+    ///     this: Weak<Observer>,
+    /// }
+    ///
+    /// let collector = Collector::new();
+    /// let observer = Shared::new_cyclic(&collector.handle(), |this| {
+    ///     assert!(this.upgrade().is_none());
+    ///     Observer { this: this.clone() }
+    /// });
+    ///
+    /// assert!(observer.this.upgrade().is_some());
+    /// ```
+    ///
+    /// [`Weak`]: crate::Weak
+    /// [`Weak::upgrade`]: crate::Weak::upgrade
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn new_cyclic<F>(handle: &Handle, data_fn: F) -> Shared<T>
+    where
+        F: FnOnce(&Weak<T>) -> T,
+    {
+        // The weak count starts out without the share held by `Shared`
+        // pointers, so that if `data_fn` panics, dropping the last `Weak`
+        // pointer frees the allocation without touching the contents.
+        let node = Node::alloc_with_drop(
+            handle,
+            SharedInner {
+                count: AtomicUsize::new(0),
+                weak: AtomicUsize::new(1),
+                data: ManuallyDrop::new(MaybeUninit::<T>::uninit()),
             },
+            drop_shared::<T>,
+        ) as *mut Node<SharedInner<T>>;
+
+        let weak = Weak {
+            node: unsafe { NonNull::new_unchecked(node) },
+            phantom: PhantomData,
+        };
+        let data = data_fn(&weak);
+
+        unsafe {
+            core::ptr::write(&mut (*node).data.data, ManuallyDrop::new(data));
+            (*node).data.weak.fetch_add(1, Ordering::Relaxed);
+            (*node).data.count.store(1, Ordering::Release);
+        }
+
+        Shared {
+            node: weak.node,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Consumes and leaks a `Shared<T>`, returning a reference to its
+    /// contents which lives for the rest of the program.
+    ///
+    /// The allocation will never be freed, even once all other `Shared`
+    /// pointers to it have been dropped. It is removed from its
+    /// [`Collector`]'s allocation count (once, no matter how many pointers to
+    /// it are leaked), so it won't prevent the [`Collector`] from being
+    /// cleaned up.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Shared};
+    ///
+    /// let collector = Collector::new();
+    /// let handle = collector.handle();
+    /// let x = Shared::new(&handle, 3);
+    /// let y = Shared::clone(&x);
+    ///
+    /// let three: &'static i32 = Shared::leak(x);
+    /// drop(y);
+    ///
+    /// drop(handle);
+    /// assert!(collector.try_cleanup().is_ok());
+    /// assert_eq!(*three, 3);
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    pub fn leak(this: Self) -> &'static T {
+        let node = this.node.as_ptr();
+        core::mem::forget(this);
+
+        unsafe {
+            let count = (*node).data.count.fetch_or(LEAKED, Ordering::Relaxed);
+            if count & LEAKED == 0 {
+                Node::leak(node);
+            }
+
+            &(*node).data.data
+        }
+    }
+}
+
+impl<T: Send + 'static> Shared<MaybeUninit<T>> {
+    /// Converts a `Shared<MaybeUninit<T>>` into a `Shared<T>`, analogous to
+    /// `Arc::assume_init`.
+    ///
+    /// # Safety
+    ///
+    /// The contents must have been fully initialized.
+    pub unsafe fn assume_init(this: Self) -> Shared<T> {
+        let node = this.node.as_ptr() as *mut Node<SharedInner<T>>;
+        core::mem::forget(this);
+
+        Node::set_drop(node, drop_shared::<T>);
+        Shared {
+            node: NonNull::new_unchecked(node),
             phantom: PhantomData,
         }
     }
@@ -53,8 +336,8 @@ impl<T: Send + 'static> Shared<T> {
 
 impl<T> Shared<T> {
     /// Returns a mutable reference to the contained value if there are no
-    /// other extant `Shared` pointers to the same allocation; otherwise
-    /// returns `None`.
+    /// other extant `Shared` or [`Weak`] pointers to the same allocation;
+    /// otherwise returns `None`.
     ///
     /// # Examples
     /// ```
@@ -69,18 +352,328 @@ impl<T> Shared<T> {
     /// let _y = Shared::clone(&x);
     /// assert!(Shared::get_mut(&mut x).is_none());
     /// ```
+    ///
+    /// [`Weak`]: crate::Weak
     pub fn get_mut(this: &mut Self) -> Option<&mut T> {
         unsafe {
-            if this.node.as_ref().data.count.load(Ordering::Acquire) == 1 {
-                Some(&mut this.node.as_mut().data.data)
+            let inner = this.node.as_ref();
+
+            // Lock the weak count, so that no other `Shared` pointer can be
+            // downgraded and dropped between the two checks.
+            if inner.data.weak.compare_exchange(1, LOCKED, Ordering::Acquire, Ordering::Relaxed).is_err() {
+                return None;
+            }
+            let unique = inner.data.count.load(Ordering::Acquire) == 1;
+            inner.data.weak.store(1, Ordering::Release);
+
+            if unique {
+                Some(&mut *this.node.as_mut().data.data)
             } else {
                 None
             }
         }
     }
+
+    /// Gets the number of `Shared` pointers to this allocation.
+    ///
+    /// Other threads may clone or drop pointers to the same allocation at
+    /// any time, so the result is only a snapshot, intended for diagnostics
+    /// and tests.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Shared};
+    ///
+    /// let collector = Collector::new();
+    /// let x = Shared::new(&collector.handle(), 3);
+    /// let y = Shared::clone(&x);
+    ///
+    /// assert_eq!(Shared::strong_count(&x), 2);
+    /// drop(y);
+    /// assert_eq!(Shared::strong_count(&x), 1);
+    /// ```
+    pub fn strong_count(this: &Self) -> usize {
+        unsafe { this.node.as_ref().data.count.load(Ordering::Relaxed) & !LEAKED }
+    }
+
+    /// Gets the number of [`Weak`] pointers to this allocation, analogous to
+    /// `Arc::weak_count`. As with [`strong_count`], the result is only a
+    /// snapshot.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Shared};
+    ///
+    /// let collector = Collector::new();
+    /// let x = Shared::new(&collector.handle(), 3);
+    /// assert_eq!(Shared::weak_count(&x), 0);
+    ///
+    /// let weak = Shared::downgrade(&x);
+    /// assert_eq!(Shared::weak_count(&x), 1);
+    /// drop(weak);
+    /// assert_eq!(Shared::weak_count(&x), 0);
+    /// ```
+    ///
+    /// [`Weak`]: crate::Weak
+    /// [`strong_count`]: crate::Shared::strong_count
+    pub fn weak_count(this: &Self) -> usize {
+        let weak = unsafe { this.node.as_ref().data.weak.load(Ordering::Relaxed) };
+        // The weak count is only locked while it is 1, i.e. while there are
+        // no `Weak` pointers. Otherwise, one is held by the `Shared` pointers.
+        if weak == LOCKED {
+            0
+        } else {
+            weak - 1
+        }
+    }
+
+    /// Returns `true` if two `Shared` pointers point to the same allocation,
+    /// analogous to `Arc::ptr_eq`.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Shared};
+    ///
+    /// let collector = Collector::new();
+    /// let x = Shared::new(&collector.handle(), 3);
+    /// let y = Shared::clone(&x);
+    /// let z = Shared::new(&collector.handle(), 3);
+    ///
+    /// assert!(Shared::ptr_eq(&x, &y));
+    /// assert!(!Shared::ptr_eq(&x, &z));
+    /// ```
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.node == other.node
+    }
+
+    /// Returns a raw pointer to the contained value. The pointer remains
+    /// valid for as long as any `Shared` pointer to the allocation exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Shared};
+    ///
+    /// let collector = Collector::new();
+    /// let x = Shared::new(&collector.handle(), 3);
+    ///
+    /// assert_eq!(Shared::as_ptr(&x), &*x as *const i32);
+    /// ```
+    pub fn as_ptr(this: &Self) -> *const T {
+        unsafe { &*this.node.as_ref().data.data as *const T }
+    }
+
+    /// Consumes a `Shared<T>`, returning a raw pointer to its contents,
+    /// analogous to `Arc::into_raw`. The reference count is not decremented,
+    /// so the pointer must eventually be converted back with [`from_raw`] (or
+    /// passed to [`decrement_strong_count`]) to avoid leaking the allocation.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Shared};
+    ///
+    /// let collector = Collector::new();
+    /// let x = Shared::new(&collector.handle(), 3);
+    ///
+    /// let ptr = Shared::into_raw(x);
+    /// let x = unsafe { Shared::from_raw(ptr) };
+    /// assert_eq!(*x, 3);
+    /// ```
+    ///
+    /// [`from_raw`]: crate::Shared::from_raw
+    /// [`decrement_strong_count`]: crate::Shared::decrement_strong_count
+    pub fn into_raw(this: Self) -> *const T {
+        let ptr = Shared::as_ptr(&this);
+        core::mem::forget(this);
+        ptr
+    }
+
+    /// Reconstructs a `Shared<T>` from a pointer returned by [`into_raw`],
+    /// taking over the reference it accounted for.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by [`Shared::into_raw`] for the same
+    /// type `T`, and each call to `from_raw` must be balanced by a previous
+    /// call to [`into_raw`] or [`increment_strong_count`] for the same
+    /// allocation.
+    ///
+    /// [`into_raw`]: crate::Shared::into_raw
+    /// [`Shared::into_raw`]: crate::Shared::into_raw
+    /// [`increment_strong_count`]: crate::Shared::increment_strong_count
+    pub unsafe fn from_raw(ptr: *const T) -> Shared<T> {
+        let offset = core::mem::offset_of!(Node<SharedInner<T>>, data)
+            + core::mem::offset_of!(SharedInner<T>, data);
+        let node = (ptr as *const u8).sub(offset) as *mut Node<SharedInner<T>>;
+
+        Shared {
+            node: NonNull::new_unchecked(node),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Increments the reference count of the allocation behind a pointer
+    /// returned by [`into_raw`], analogous to `Arc::increment_strong_count`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by [`into_raw`], and the allocation must
+    /// still be alive, i.e. the reference accounted for by that call must not
+    /// have been released yet.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Shared};
+    ///
+    /// let collector = Collector::new();
+    /// let ptr = Shared::into_raw(Shared::new(&collector.handle(), 3));
+    ///
+    /// unsafe {
+    ///     Shared::increment_strong_count(ptr);
+    ///     let x = Shared::from_raw(ptr);
+    ///     assert_eq!(Shared::strong_count(&x), 2);
+    ///     Shared::decrement_strong_count(ptr);
+    /// }
+    /// ```
+    ///
+    /// [`into_raw`]: crate::Shared::into_raw
+    pub unsafe fn increment_strong_count(ptr: *const T) {
+        let shared = ManuallyDrop::new(Shared::from_raw(ptr));
+        core::mem::forget(Shared::clone(&shared));
+    }
+
+    /// Decrements the reference count of the allocation behind a pointer
+    /// returned by [`into_raw`], analogous to `Arc::decrement_strong_count`.
+    /// If this releases the last reference, the contents are added to the
+    /// [`Collector`]'s drop queue.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements apply as for [`from_raw`].
+    ///
+    /// [`into_raw`]: crate::Shared::into_raw
+    /// [`Collector`]: crate::Collector
+    /// [`from_raw`]: crate::Shared::from_raw
+    pub unsafe fn decrement_strong_count(ptr: *const T) {
+        drop(Shared::from_raw(ptr));
+    }
+
+    /// Creates a new [`Weak`] pointer to this allocation.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Shared};
+    ///
+    /// let collector = Collector::new();
+    /// let x = Shared::new(&collector.handle(), 3);
+    ///
+    /// let weak = Shared::downgrade(&x);
+    /// assert_eq!(*weak.upgrade().unwrap(), 3);
+    /// ```
+    ///
+    /// [`Weak`]: crate::Weak
+    pub fn downgrade(this: &Self) -> Weak<T> {
+        unsafe {
+            let weak = &this.node.as_ref().data.weak;
+            let mut current = weak.load(Ordering::Relaxed);
+            loop {
+                if current == LOCKED {
+                    core::hint::spin_loop();
+                    current = weak.load(Ordering::Relaxed);
+                    continue;
+                }
+
+                match weak.compare_exchange_weak(
+                    current,
+                    current + 1,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => break,
+                    Err(actual) => current = actual,
+                }
+            }
+        }
+
+        Weak { node: this.node, phantom: PhantomData }
+    }
 }
 
-impl<T> Clone for Shared<T> {
+impl<T> Shared<T> {
+    /// Drops a `Shared<T>`. If this was the last `Shared` pointer to the
+    /// allocation, its contents are added to the high-priority drop queue of
+    /// its [`Collector`] so that they will be collected before any other
+    /// pending garbage.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Shared};
+    ///
+    /// let mut collector = Collector::new();
+    /// let x = Shared::new(&collector.handle(), [0.0f32; 4096]);
+    /// let y = Shared::clone(&x);
+    ///
+    /// Shared::drop_urgent(x);
+    /// collector.collect();
+    /// assert_eq!(collector.alloc_count(), 1);
+    ///
+    /// Shared::drop_urgent(y);
+    /// collector.collect();
+    /// assert_eq!(collector.alloc_count(), 0);
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    pub fn drop_urgent(this: Self) {
+        let node = this.node;
+        core::mem::forget(this);
+
+        unsafe {
+            let count = node.as_ref().data.count.fetch_sub(1, Ordering::Release);
+
+            if count == 1 {
+                fence(Ordering::Acquire);
+                Node::queue_drop_urgent(node.as_ptr());
+            }
+        }
+    }
+
+    /// Drops every `Shared<T>` in `pointers`. Allocations whose last pointer
+    /// this was are added to the drop queue in batches, as with
+    /// [`Owned::drop_all`].
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Shared};
+    ///
+    /// let mut collector = Collector::new();
+    /// let handle = collector.handle();
+    /// let voices: Vec<_> = (0..1000).map(|i| Shared::new(&handle, i)).collect();
+    /// let kept = Shared::clone(&voices[0]);
+    ///
+    /// Shared::drop_all(voices);
+    /// collector.collect();
+    /// assert_eq!(collector.alloc_count(), 1);
+    /// ```
+    ///
+    /// [`Owned::drop_all`]: crate::Owned::drop_all
+    pub fn drop_all<I: IntoIterator<Item = Self>>(pointers: I) {
+        let mut batch = Batch::new();
+        for this in pointers {
+            let node = this.node;
+            core::mem::forget(this);
+
+            unsafe {
+                let count = node.as_ref().data.count.fetch_sub(1, Ordering::Release);
+
+                if count == 1 {
+                    fence(Ordering::Acquire);
+                    batch.push(node.as_ptr() as *mut NodeHeader);
+                }
+            }
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for Shared<T> {
     fn clone(&self) -> Self {
         unsafe {
             self.node.as_ref().data.count.fetch_add(1, Ordering::Relaxed);
@@ -90,7 +683,7 @@ impl<T> Clone for Shared<T> {
     }
 }
 
-impl<T> Deref for Shared<T> {
+impl<T: ?Sized> Deref for Shared<T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
@@ -98,7 +691,7 @@ impl<T> Deref for Shared<T> {
     }
 }
 
-impl<T> Drop for Shared<T> {
+impl<T: ?Sized> Drop for Shared<T> {
     fn drop(&mut self) {
         unsafe {
             let count = self.node.as_ref().data.count.fetch_sub(1, Ordering::Release);
@@ -111,10 +704,207 @@ impl<T> Drop for Shared<T> {
     }
 }
 
+/// A uniquely owned allocation which can be converted into a [`Shared`]
+/// without copying.
+///
+/// An [`Owned<T>`] and a [`Shared<T>`] have different layouts, so converting
+/// one into the other requires a new allocation. A `UniqueShared<T>` is laid
+/// out like a [`Shared<T>`] with a reference count which is reserved but not
+/// yet in use. Like an [`Owned<T>`], it allows mutable access to its
+/// contents, and [`into_shared`] then turns it into a [`Shared<T>`] without
+/// allocating or moving the contents. If it is dropped instead, its contents
+/// are added to the [`Collector`]'s drop queue as usual.
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, SharedCell, UniqueShared};
+///
+/// let collector = Collector::new();
+/// let handle = collector.handle();
+///
+/// let mut table = UniqueShared::new(&handle, vec![0.0f32; 2048]);
+/// for (i, sample) in table.iter_mut().enumerate() {
+///     *sample = (i as f32 / 2048.0 * std::f32::consts::TAU).sin();
+/// }
+///
+/// let cell = SharedCell::new(UniqueShared::into_shared(table));
+/// ```
+///
+/// [`Shared`]: crate::Shared
+/// [`Shared<T>`]: crate::Shared
+/// [`Owned<T>`]: crate::Owned
+/// [`into_shared`]: crate::UniqueShared::into_shared
+/// [`Collector`]: crate::Collector
+pub struct UniqueShared<T> {
+    node: NonNull<Node<SharedInner<T>>>,
+    phantom: PhantomData<T>,
+}
+
+unsafe impl<T: Send> Send for UniqueShared<T> {}
+unsafe impl<T: Sync> Sync for UniqueShared<T> {}
+
+impl<T: UnwindSafe> UnwindSafe for UniqueShared<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for UniqueShared<T> {}
+impl<T> Unpin for UniqueShared<T> {}
+
+impl<T: Send + 'static> UniqueShared<T> {
+    /// Constructs a new `UniqueShared<T>`.
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn new(handle: &Handle, data: T) -> UniqueShared<T> {
+        let shared = Shared::new(handle, data);
+        let node = shared.node;
+        core::mem::forget(shared);
+
+        UniqueShared { node, phantom: PhantomData }
+    }
+}
+
+impl<T> UniqueShared<T> {
+    /// Converts a `UniqueShared<T>` into a [`Shared<T>`] in constant time,
+    /// without allocating.
+    ///
+    /// [`Shared<T>`]: crate::Shared
+    pub fn into_shared(this: Self) -> Shared<T> {
+        let node = this.node;
+        core::mem::forget(this);
+
+        // The reference count already accounts for this pointer.
+        Shared { node, phantom: PhantomData }
+    }
+}
+
+impl<T> Deref for UniqueShared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &self.node.as_ref().data.data }
+    }
+}
+
+impl<T> DerefMut for UniqueShared<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut self.node.as_mut().data.data }
+    }
+}
+
+impl<T> Drop for UniqueShared<T> {
+    fn drop(&mut self) {
+        unsafe {
+            Node::queue_drop(self.node.as_ptr());
+        }
+    }
+}
+
+/// A weak reference to a [`Shared`] allocation, analogous to
+/// `std::sync::Weak`.
+///
+/// A `Weak<T>` doesn't keep the contents of the allocation alive, which makes
+/// it suitable for breaking reference cycles, e.g. between the nodes of a
+/// graph and their parents. It can be converted back into a [`Shared`] with
+/// [`upgrade`], which fails once every [`Shared`] pointer has been dropped.
+///
+/// When the last [`Shared`] pointer is dropped, the contents are added to the
+/// [`Collector`]'s drop queue as usual. If any `Weak` pointers remain at the
+/// point when the contents are dropped, the allocation itself (which holds
+/// the reference counts) is kept around, and is added to the drop queue again
+/// to be freed once the last `Weak` pointer is dropped.
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, Shared};
+///
+/// let mut collector = Collector::new();
+/// let x = Shared::new(&collector.handle(), 3);
+/// let weak = Shared::downgrade(&x);
+///
+/// drop(x);
+/// assert!(weak.upgrade().is_none());
+///
+/// collector.collect();
+/// assert_eq!(collector.alloc_count(), 1);
+///
+/// drop(weak);
+/// collector.collect();
+/// assert_eq!(collector.alloc_count(), 0);
+/// ```
+///
+/// [`Shared`]: crate::Shared
+/// [`upgrade`]: crate::Weak::upgrade
+/// [`Collector`]: crate::Collector
+pub struct Weak<T> {
+    node: NonNull<Node<SharedInner<T>>>,
+    phantom: PhantomData<SharedInner<T>>,
+}
+
+unsafe impl<T: Send + Sync> Send for Weak<T> {}
+unsafe impl<T: Send + Sync> Sync for Weak<T> {}
+
+impl<T: RefUnwindSafe> UnwindSafe for Weak<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Weak<T> {}
+impl<T> Unpin for Weak<T> {}
+
+impl<T> Weak<T> {
+    /// Attempts to obtain a [`Shared`] pointer to the allocation, returning
+    /// `None` if every [`Shared`] pointer to it has already been dropped.
+    ///
+    /// This never blocks or allocates, so it can be called from a real-time
+    /// thread.
+    ///
+    /// [`Shared`]: crate::Shared
+    pub fn upgrade(&self) -> Option<Shared<T>> {
+        // Only the counts are accessed, since the contents may be in the
+        // middle of being dropped.
+        let count = unsafe { &(*self.node.as_ptr()).data.count };
+        let mut current = count.load(Ordering::Relaxed);
+        loop {
+            if current == 0 {
+                return None;
+            }
+
+            match count.compare_exchange_weak(
+                current,
+                current + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(Shared { node: self.node, phantom: PhantomData }),
+                Err(actual) => current = actual,
+            }
+        }
+    }
+}
+
+impl<T> Clone for Weak<T> {
+    fn clone(&self) -> Self {
+        unsafe {
+            (*self.node.as_ptr()).data.weak.fetch_add(1, Ordering::Relaxed);
+        }
+
+        Weak { node: self.node, phantom: PhantomData }
+    }
+}
+
+impl<T> Drop for Weak<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let node = self.node.as_ptr();
+            let weak = (*node).data.weak.fetch_sub(1, Ordering::Release);
+
+            // The contents have already been dropped, so all that remains is
+            // to free the allocation.
+            if weak == 1 {
+                fence(Ordering::Acquire);
+                Node::queue_drop(node);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{Collector, Shared};
+    use crate::{Collector, Shared, UniqueShared, Weak};
 
+    use core::mem::MaybeUninit;
     use core::sync::atomic::{AtomicUsize, Ordering};
 
     #[test]
@@ -158,7 +948,242 @@ mod tests {
         *Shared::get_mut(&mut x).unwrap() = 4;
         assert_eq!(*x, 4);
 
-        let _y = Shared::clone(&x);
+        let y = Shared::clone(&x);
         assert!(Shared::get_mut(&mut x).is_none());
+        assert_eq!(Shared::strong_count(&x), 2);
+
+        // A leaked pointer is still counted, but the leak flag isn't.
+        let _three = Shared::leak(y);
+        assert_eq!(Shared::strong_count(&x), 2);
+    }
+
+    #[test]
+    fn ptr_eq() {
+        let collector = Collector::new();
+        let x = Shared::new(&collector.handle(), 3);
+        let y = Shared::clone(&x);
+        let z = Shared::new(&collector.handle(), 3);
+
+        assert!(Shared::ptr_eq(&x, &y));
+        assert!(!Shared::ptr_eq(&x, &z));
+        assert_eq!(Shared::as_ptr(&x), Shared::as_ptr(&y));
+        assert_ne!(Shared::as_ptr(&x), Shared::as_ptr(&z));
+    }
+
+    #[test]
+    fn raw() {
+        let mut collector = Collector::new();
+        let x = Shared::new(&collector.handle(), [1u8, 2, 3]);
+
+        let ptr = Shared::into_raw(x);
+        unsafe {
+            assert_eq!(*ptr, [1, 2, 3]);
+            Shared::increment_strong_count(ptr);
+            Shared::increment_strong_count(ptr);
+            Shared::decrement_strong_count(ptr);
+
+            let x = Shared::from_raw(ptr);
+            assert_eq!(Shared::strong_count(&x), 2);
+            assert_eq!(Shared::into_raw(x), ptr);
+            Shared::decrement_strong_count(ptr);
+            Shared::decrement_strong_count(ptr);
+        }
+
+        collector.collect();
+        assert_eq!(collector.alloc_count(), 0);
+    }
+
+    #[test]
+    fn unique_shared() {
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        let mut unique = UniqueShared::new(&handle, [0u8; 4]);
+        unique[1] = 1;
+        let ptr = &*unique as *const [u8; 4];
+
+        let shared = UniqueShared::into_shared(unique);
+        assert_eq!(*shared, [0, 1, 0, 0]);
+        assert_eq!(Shared::as_ptr(&shared), ptr);
+        assert_eq!(Shared::strong_count(&shared), 1);
+
+        drop(shared);
+        drop(UniqueShared::new(&handle, 2));
+        collector.collect();
+        assert_eq!(collector.alloc_count(), 0);
+    }
+
+    #[cfg(feature = "nightly")]
+    #[test]
+    fn coerce_unsized() {
+        extern crate alloc;
+        use alloc::sync::Arc;
+        use crate::Owned;
+
+        trait Effect: Send + Sync {
+            fn gain(&self) -> f32;
+        }
+
+        struct Gain(f32, Arc<AtomicUsize>);
+
+        impl Effect for Gain {
+            fn gain(&self) -> f32 {
+                self.0
+            }
+        }
+
+        impl Drop for Gain {
+            fn drop(&mut self) {
+                self.1.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        let shared: Shared<dyn Effect> = Shared::new(&handle, Gain(0.5, counter.clone()));
+        let owned: Owned<dyn Effect> = Owned::new(&handle, Gain(2.0, counter.clone()));
+        assert_eq!(shared.clone().gain() * owned.gain(), 1.0);
+
+        drop((shared, owned));
+        collector.collect();
+        assert_eq!(counter.load(Ordering::Relaxed), 2);
+        assert_eq!(collector.alloc_count(), 0);
+    }
+
+    #[test]
+    fn new_cyclic() {
+        extern crate std;
+
+        struct Cyclic {
+            this: Weak<Cyclic>,
+        }
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        let cyclic = Shared::new_cyclic(&handle, |this| Cyclic { this: this.clone() });
+        assert!(cyclic.this.upgrade().is_some());
+        drop(cyclic);
+
+        let result = std::panic::catch_unwind(|| {
+            Shared::new_cyclic(&handle, |this| -> Cyclic {
+                let _this = this.clone();
+                panic!();
+            })
+        });
+        assert!(result.is_err());
+
+        drop(handle);
+        collector.collect();
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[test]
+    fn new_with() {
+        extern crate std;
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        let shared = unsafe {
+            Shared::new_with(&handle, |slot| {
+                *slot = MaybeUninit::new([1u8; 64]);
+            })
+        };
+        assert_eq!(shared[63], 1);
+        assert_eq!(Shared::strong_count(&shared), 1);
+        drop(shared);
+
+        let result = std::panic::catch_unwind(|| unsafe {
+            Shared::<[u8; 64]>::new_with(&handle, |_| panic!())
+        });
+        assert!(result.is_err());
+
+        drop(handle);
+        collector.collect();
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[test]
+    fn weak() {
+        extern crate alloc;
+        use alloc::sync::Arc;
+        use crate::SharedMutex;
+
+        struct Graph {
+            parent: Option<Weak<SharedMutex<Graph>>>,
+            children: alloc::vec::Vec<Shared<SharedMutex<Graph>>>,
+            dropped: Arc<AtomicUsize>,
+        }
+
+        impl Drop for Graph {
+            fn drop(&mut self) {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let dropped = Arc::new(AtomicUsize::new(0));
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        let new = |parent| {
+            Shared::new(&handle, SharedMutex::new(&handle, Graph {
+                parent,
+                children: alloc::vec::Vec::new(),
+                dropped: dropped.clone(),
+            }))
+        };
+
+        let root = new(None);
+        let child = new(Some(Shared::downgrade(&root)));
+        root.lock().children.push(child.clone());
+
+        let parent = child.lock().parent.as_ref().unwrap().upgrade().unwrap();
+        assert!(Shared::get_mut(&mut root.clone()).is_none());
+        drop(parent);
+
+        drop(root);
+        collector.collect();
+        assert_eq!(dropped.load(Ordering::Relaxed), 1);
+        assert!(child.lock().parent.as_ref().unwrap().upgrade().is_none());
+
+        drop(child);
+        collector.collect();
+        assert_eq!(dropped.load(Ordering::Relaxed), 2);
+
+        drop(handle);
+        collector.collect();
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[test]
+    fn drop_all() {
+        use crate::Owned;
+
+        extern crate alloc;
+        use alloc::vec::Vec;
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        let owned: Vec<_> = (0..10).map(|i| Owned::new(&handle, i)).collect();
+        let shared: Vec<_> = (0..10).map(|i| Shared::new(&handle, i)).collect();
+        let kept = shared[3].clone();
+        assert_eq!(collector.alloc_count(), 20);
+
+        Owned::drop_all(owned);
+        Shared::drop_all(shared);
+        assert_eq!(collector.pending_count(), 19);
+        collector.collect();
+        assert_eq!(collector.alloc_count(), 1);
+        assert_eq!(*kept, 3);
+
+        drop((kept, handle));
+        collector.collect();
+        assert!(collector.try_cleanup().is_ok());
     }
 }
@@ -1,17 +1,59 @@
 use crate::{Node, Shared, SharedInner};
 
+use core::fmt;
 use core::marker::PhantomData;
+use core::panic::{RefUnwindSafe, UnwindSafe};
 use core::ptr::NonNull;
-use core::sync::atomic::{fence, AtomicPtr, AtomicUsize, Ordering};
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
 
 /// A thread-safe shared mutable memory location that holds a [`Shared<T>`].
 ///
 /// `SharedCell` is designed to be low-overhead for readers at the expense of
 /// somewhat higher overhead for writers.
 ///
+/// # Signal safety
+///
+/// [`get`] consists solely of lock-free atomic operations, as does dropping
+/// a [`Shared<T>`] or [`Owned<T>`] (which only pushes onto the collector's
+/// drop queue), so both may be used from POSIX signal handlers and similarly
+/// constrained contexts, provided that:
+///
+/// - No [`Handle`] is created or dropped in the handler, since the
+///   `diagnostics` feature guards [`Handle`] bookkeeping with a spin lock.
+/// - Nothing is allocated in the handler, since allocating is not
+///   async-signal-safe.
+/// - The dropped value's destructor only runs later on the collector thread,
+///   so it is not subject to these restrictions.
+///
+/// Some features add work to dropping which voids this guarantee:
+///
+/// - `thread-stats` records each drop through a thread-local, and registers
+///   the thread on its first allocation or drop, which allocates and takes
+///   a spin lock.
+/// - `latency-stats` calls the [`Clock`] installed with
+///   [`CollectorBuilder::reclamation_clock`], which must then be
+///   async-signal-safe itself.
+///
+/// [`set`] and [`replace`] are *not* safe to call from a signal handler:
+/// they wait for in-progress calls to [`get`] to finish, so if the handler
+/// interrupts a thread in the middle of [`get`] on the same cell, the writer
+/// will spin forever.
+///
 /// [`Shared<T>`]: crate::Shared
+/// [`Owned<T>`]: crate::Owned
+/// [`Handle`]: crate::Handle
+/// [`Clock`]: crate::Clock
+/// [`CollectorBuilder::reclamation_clock`]: crate::CollectorBuilder::reclamation_clock
+/// [`get`]: crate::SharedCell::get
+/// [`set`]: crate::SharedCell::set
+/// [`replace`]: crate::SharedCell::replace
 pub struct SharedCell<T> {
     readers: AtomicUsize,
+    /// Incremented after each update, so that a [`Cache`] can tell whether
+    /// its copy is stale.
+    ///
+    /// [`Cache`]: crate::Cache
+    generation: AtomicUsize,
     node: AtomicPtr<Node<SharedInner<T>>>,
     phantom: PhantomData<Shared<T>>,
 }
@@ -19,6 +61,12 @@ pub struct SharedCell<T> {
 unsafe impl<T: Send + Sync> Send for SharedCell<T> {}
 unsafe impl<T: Send + Sync> Sync for SharedCell<T> {}
 
+// Every update to a `SharedCell` is a single atomic operation, so a panic can
+// never leave it observably half-modified.
+impl<T: RefUnwindSafe> UnwindSafe for SharedCell<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for SharedCell<T> {}
+impl<T> Unpin for SharedCell<T> {}
+
 impl<T: Send + 'static> SharedCell<T> {
     /// Constructs a new `SharedCell` containing `value`.
     ///
@@ -36,6 +84,7 @@ impl<T: Send + 'static> SharedCell<T> {
 
         SharedCell {
             readers: AtomicUsize::new(0),
+            generation: AtomicUsize::new(0),
             node: AtomicPtr::new(node),
             phantom: PhantomData,
         }
@@ -59,16 +108,26 @@ impl<T> SharedCell<T> {
     ///
     /// [`Shared<T>`]: crate::Shared
     pub fn get(&self) -> Shared<T> {
-        self.readers.fetch_add(1, Ordering::SeqCst);
+        // Readers and writers synchronize through the `readers` counter, which
+        // is only ever modified with read-modify-write operations, so all
+        // accesses to it are totally ordered:
+        //
+        // - If a writer's check of `readers` comes first, its release
+        //   synchronizes with our acquire below, so our load of `node` is
+        //   guaranteed to observe the new pointer.
+        // - Otherwise, the writer keeps checking until it reads our
+        //   decrement, whose release ensures that our reference count
+        //   increment happens before the writer drops the old pointer.
+        self.readers.fetch_add(1, Ordering::Acquire);
 
         let shared = Shared {
-            node: unsafe { NonNull::new_unchecked(self.node.load(Ordering::SeqCst)) },
+            node: unsafe { NonNull::new_unchecked(self.node.load(Ordering::Acquire)) },
             phantom: PhantomData,
         };
         let copy = shared.clone();
         core::mem::forget(shared);
 
-        self.readers.fetch_sub(1, Ordering::Relaxed);
+        self.readers.fetch_sub(1, Ordering::Release);
 
         copy
     }
@@ -114,8 +173,17 @@ impl<T> SharedCell<T> {
         core::mem::forget(value);
 
         let old = self.node.swap(node, Ordering::AcqRel);
-        while self.readers.load(Ordering::Relaxed) != 0 {}
-        fence(Ordering::Acquire);
+
+        // A read-modify-write rather than a plain load, so that it takes part
+        // in the total order of accesses to `readers` described in `get`.
+        let mut spins = 0;
+        while self.readers.fetch_add(0, Ordering::AcqRel) != 0 {
+            spins += 1;
+            unsafe { Node::report_stall(old, spins) };
+            core::hint::spin_loop();
+        }
+
+        self.generation.fetch_add(1, Ordering::Release);
 
         Shared {
             node: unsafe { NonNull::new_unchecked(old) },
@@ -123,6 +191,50 @@ impl<T> SharedCell<T> {
         }
     }
 
+    /// Creates a [`Cache`] of this `SharedCell`'s contents, for a thread which
+    /// reads them repeatedly.
+    ///
+    /// [`Cache`]: crate::Cache
+    pub fn cache(&self) -> Cache<'_, T> {
+        Cache::new(self)
+    }
+
+    /// Gets the number of times the contents of this `SharedCell` have been
+    /// replaced since it was constructed.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Shared, SharedCell};
+    ///
+    /// let collector = Collector::new();
+    /// let cell = SharedCell::new(Shared::new(&collector.handle(), 1));
+    ///
+    /// cell.set(Shared::new(&collector.handle(), 2));
+    /// assert_eq!(cell.generation(), 1);
+    /// ```
+    pub fn generation(&self) -> usize {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Returns a wrapper whose `Debug` output includes the current value as
+    /// well as the fields shown by `SharedCell`'s own `Debug` implementation.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Shared, SharedCell};
+    ///
+    /// let collector = Collector::new();
+    /// let cell = SharedCell::new(Shared::new(&collector.handle(), 1));
+    ///
+    /// println!("{:?}", cell.debug_value());
+    /// ```
+    pub fn debug_value(&self) -> DebugValue<'_, T>
+    where
+        T: fmt::Debug,
+    {
+        DebugValue { cell: self }
+    }
+
     /// Consumes the `SharedCell` and returns the contained [`Shared<T>`]. This
     /// is safe because we are guaranteed to be the only holder of the
     /// `SharedCell`.
@@ -149,6 +261,23 @@ impl<T> SharedCell<T> {
     }
 }
 
+/// Shows the address of the current allocation, the number of readers
+/// currently in the middle of [`get`], and the generation (see
+/// [`generation`]). To show the current value as well, use [`debug_value`].
+///
+/// [`get`]: crate::SharedCell::get
+/// [`generation`]: crate::SharedCell::generation
+/// [`debug_value`]: crate::SharedCell::debug_value
+impl<T> fmt::Debug for SharedCell<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SharedCell")
+            .field("ptr", &self.node.load(Ordering::Acquire))
+            .field("readers", &self.readers.load(Ordering::Relaxed))
+            .field("generation", &self.generation())
+            .finish()
+    }
+}
+
 impl<T> Drop for SharedCell<T> {
     fn drop(&mut self) {
         let _ = Shared {
@@ -158,6 +287,88 @@ impl<T> Drop for SharedCell<T> {
     }
 }
 
+/// Formats a [`SharedCell`] along with its current value. Returned by
+/// [`SharedCell::debug_value`].
+///
+/// [`SharedCell`]: crate::SharedCell
+/// [`SharedCell::debug_value`]: crate::SharedCell::debug_value
+pub struct DebugValue<'a, T> {
+    cell: &'a SharedCell<T>,
+}
+
+impl<T: fmt::Debug> fmt::Debug for DebugValue<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let readers = self.cell.readers.load(Ordering::Relaxed);
+        let generation = self.cell.generation();
+        let value = self.cell.get();
+
+        f.debug_struct("SharedCell")
+            .field("ptr", &value.node)
+            .field("readers", &readers)
+            .field("generation", &generation)
+            .field("value", &*value)
+            .finish()
+    }
+}
+
+/// A thread-local copy of a [`SharedCell`]'s contents.
+///
+/// [`Cache::load`] only touches the [`SharedCell`]'s reader count and the
+/// contained [`Shared<T>`]'s reference count when the cell has been updated
+/// since the last call; otherwise it costs a single atomic load. This makes
+/// repeated reads, e.g. once per audio callback, nearly free. Note that the
+/// `Cache` keeps its copy of the old value alive until the next call to
+/// [`Cache::load`] after an update.
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, Shared, SharedCell};
+///
+/// let collector = Collector::new();
+/// let cell = SharedCell::new(Shared::new(&collector.handle(), 1));
+///
+/// let mut cache = cell.cache();
+/// assert_eq!(**cache.load(), 1);
+///
+/// cell.set(Shared::new(&collector.handle(), 2));
+/// assert_eq!(**cache.load(), 2);
+/// ```
+///
+/// [`SharedCell`]: crate::SharedCell
+/// [`Shared<T>`]: crate::Shared
+/// [`Cache::load`]: crate::Cache::load
+pub struct Cache<'a, T> {
+    cell: &'a SharedCell<T>,
+    generation: usize,
+    value: Shared<T>,
+}
+
+impl<'a, T> Cache<'a, T> {
+    /// Constructs a new `Cache` of `cell`'s contents.
+    pub fn new(cell: &'a SharedCell<T>) -> Cache<'a, T> {
+        let generation = cell.generation.load(Ordering::Acquire);
+        let value = cell.get();
+        Cache { cell, generation, value }
+    }
+
+    /// Returns the [`SharedCell`]'s current contents, refreshing the cached
+    /// copy first if the cell has been updated.
+    ///
+    /// [`SharedCell`]: crate::SharedCell
+    pub fn load(&mut self) -> &Shared<T> {
+        // `replace` increments the generation after swapping in the new
+        // pointer, so once we observe a new generation, `get` is guaranteed
+        // to return a value at least as recent.
+        let generation = self.cell.generation.load(Ordering::Acquire);
+        if generation != self.generation {
+            self.value = self.cell.get();
+            self.generation = generation;
+        }
+
+        &self.value
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{Collector, Shared, SharedCell};
@@ -202,4 +413,81 @@ mod tests {
 
         assert_eq!(counter.load(Ordering::Relaxed), 1);
     }
+
+    #[test]
+    fn debug() {
+        extern crate alloc;
+        use alloc::format;
+
+        let collector = Collector::new();
+        let cell = SharedCell::new(Shared::new(&collector.handle(), 1));
+        cell.set(Shared::new(&collector.handle(), 2));
+
+        let debug = format!("{:?}", cell);
+        assert!(debug.starts_with("SharedCell { ptr: 0x"));
+        assert!(debug.ends_with("readers: 0, generation: 1 }"));
+
+        let debug = format!("{:?}", cell.debug_value());
+        assert!(debug.starts_with("SharedCell { ptr: 0x"));
+        assert!(debug.ends_with("readers: 0, generation: 1, value: 2 }"));
+
+        // Values which don't implement `Debug` aren't shown.
+        struct Opaque;
+        let cell = SharedCell::new(Shared::new(&collector.handle(), Opaque));
+        assert!(format!("{:?}", cell).ends_with("readers: 0, generation: 0 }"));
+    }
+
+    #[test]
+    fn cache() {
+        let mut collector = Collector::new();
+        let cell = SharedCell::new(Shared::new(&collector.handle(), 1));
+
+        let mut cache = cell.cache();
+        assert_eq!(**cache.load(), 1);
+
+        cell.set(Shared::new(&collector.handle(), 2));
+        collector.collect();
+        assert_eq!(collector.alloc_count(), 2);
+
+        assert_eq!(**cache.load(), 2);
+        collector.collect();
+        assert_eq!(collector.alloc_count(), 1);
+    }
+
+    #[test]
+    fn writer_stall() {
+        extern crate alloc;
+        extern crate std;
+        use alloc::sync::Arc;
+
+        let stalls = Arc::new(AtomicUsize::new(0));
+        let collector = Collector::builder()
+            .on_writer_stall(100, {
+                let stalls = stalls.clone();
+                move |info| {
+                    assert_eq!(info.spins, 100);
+                    stalls.fetch_add(1, Ordering::SeqCst);
+                }
+            })
+            .build();
+
+        let cell = Arc::new(SharedCell::new(Shared::new(&collector.handle(), 1)));
+
+        // Simulate a reader which has been preempted inside `get`.
+        cell.readers.fetch_add(1, Ordering::SeqCst);
+        let writer = std::thread::spawn({
+            let cell = cell.clone();
+            let value = Shared::new(&collector.handle(), 2);
+            move || cell.set(value)
+        });
+
+        while stalls.load(Ordering::SeqCst) == 0 {
+            core::hint::spin_loop();
+        }
+        cell.readers.fetch_sub(1, Ordering::SeqCst);
+        writer.join().unwrap();
+
+        assert_eq!(stalls.load(Ordering::SeqCst), 1);
+        assert_eq!(*cell.get(), 2);
+    }
 }
@@ -0,0 +1,684 @@
+use crate::collector::{alloc_raw, free_raw, NodeHeader};
+use crate::{Handle, Node};
+
+use core::alloc::Layout;
+use core::marker::PhantomData;
+use core::mem::{align_of, MaybeUninit};
+use core::ops::{Deref, DerefMut};
+use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::ptr::NonNull;
+use core::sync::atomic::{fence, AtomicUsize, Ordering};
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A uniquely owned slice with deferred collection, analogous to `Box<[T]>`.
+///
+/// When an `OwnedSlice<T>` is dropped, its contents are added to the drop
+/// queue of the [`Collector`] whose [`Handle`] it was allocated with, as with
+/// [`Owned`].
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, OwnedSlice};
+///
+/// let collector = Collector::new();
+/// let mut samples = OwnedSlice::from_vec(&collector.handle(), vec![0.0f32; 64]);
+/// samples[0] = 1.0;
+/// assert_eq!(samples.len(), 64);
+/// ```
+///
+/// [`Collector`]: crate::Collector
+/// [`Handle`]: crate::Handle
+/// [`Owned`]: crate::Owned
+pub struct OwnedSlice<T> {
+    // Never cloned, so that the elements can be accessed mutably. This lets
+    // them be stored inline and over-aligned in the same way.
+    inner: SharedSlice<T>,
+}
+
+unsafe impl<T: Send> Send for OwnedSlice<T> {}
+unsafe impl<T: Sync> Sync for OwnedSlice<T> {}
+
+impl<T: UnwindSafe> UnwindSafe for OwnedSlice<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for OwnedSlice<T> {}
+
+impl<T: Send + 'static> OwnedSlice<T> {
+    /// Constructs a new `OwnedSlice<T>` by moving the contents of a `Vec<T>`
+    /// into a new allocation.
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn from_vec(handle: &Handle, vec: Vec<T>) -> OwnedSlice<T> {
+        OwnedSlice {
+            inner: SharedSlice::from_vec(handle, vec),
+        }
+    }
+
+    /// Constructs a new `OwnedSlice` of `len` uninitialized elements,
+    /// analogous to `Box::new_uninit_slice`. The elements can be written
+    /// through the returned `OwnedSlice` and then marked as initialized with
+    /// [`assume_init`].
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, OwnedSlice};
+    /// use std::mem::MaybeUninit;
+    ///
+    /// let collector = Collector::new();
+    /// let mut samples = OwnedSlice::<f32>::new_uninit(&collector.handle(), 48000);
+    /// for (i, sample) in samples.iter_mut().enumerate() {
+    ///     *sample = MaybeUninit::new(i as f32);
+    /// }
+    ///
+    /// let samples = unsafe { OwnedSlice::assume_init(samples) };
+    /// assert_eq!(samples[47999], 47999.0);
+    /// ```
+    ///
+    /// [`assume_init`]: crate::OwnedSlice::assume_init
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn new_uninit(handle: &Handle, len: usize) -> OwnedSlice<MaybeUninit<T>> {
+        OwnedSlice::new_uninit_aligned(handle, len, align_of::<T>())
+    }
+
+    /// Like [`new_uninit`], but aligns the first element to `align` bytes,
+    /// e.g. for sample buffers processed with SIMD instructions which
+    /// require aligned loads and stores.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two. An `align` smaller than the
+    /// alignment of `T` has no effect.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, OwnedSlice};
+    /// use std::mem::MaybeUninit;
+    ///
+    /// let collector = Collector::new();
+    /// let mut buffer = OwnedSlice::<f32>::new_uninit_aligned(&collector.handle(), 256, 32);
+    /// buffer.fill(MaybeUninit::new(0.0));
+    ///
+    /// let mut buffer = unsafe { OwnedSlice::assume_init(buffer) };
+    /// assert_eq!(buffer.as_mut_ptr() as usize % 32, 0);
+    /// ```
+    ///
+    /// [`new_uninit`]: crate::OwnedSlice::new_uninit
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn new_uninit_aligned(
+        handle: &Handle,
+        len: usize,
+        align: usize,
+    ) -> OwnedSlice<MaybeUninit<T>> {
+        OwnedSlice {
+            inner: SharedSlice::new_uninit_aligned(handle, len, align),
+        }
+    }
+}
+
+impl<T: Send + 'static> OwnedSlice<MaybeUninit<T>> {
+    /// Converts an `OwnedSlice<MaybeUninit<T>>` into an `OwnedSlice<T>`,
+    /// analogous to `Box::assume_init`.
+    ///
+    /// # Safety
+    ///
+    /// All elements must have been initialized.
+    pub unsafe fn assume_init(this: Self) -> OwnedSlice<T> {
+        OwnedSlice {
+            inner: SharedSlice::assume_init(this.inner),
+        }
+    }
+}
+
+impl<T: Clone + Send + 'static> OwnedSlice<T> {
+    /// Constructs a new `OwnedSlice<T>` by cloning the contents of a slice.
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn from_slice(handle: &Handle, slice: &[T]) -> OwnedSlice<T> {
+        OwnedSlice::from_vec(handle, slice.to_vec())
+    }
+
+    /// Constructs a new `OwnedSlice<T>` by cloning the contents of a slice,
+    /// aligning the first element to `align` bytes as with
+    /// [`new_uninit_aligned`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two.
+    ///
+    /// [`new_uninit_aligned`]: crate::OwnedSlice::new_uninit_aligned
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn from_slice_aligned(handle: &Handle, slice: &[T], align: usize) -> OwnedSlice<T> {
+        OwnedSlice {
+            inner: SharedSlice::from_slice_aligned(handle, slice, align),
+        }
+    }
+}
+
+/// Clones the contents into a new allocation with the same alignment.
+impl<T: Clone + Send + 'static> Clone for OwnedSlice<T> {
+    fn clone(&self) -> Self {
+        let node = self.inner.node.as_ptr();
+        let handle = unsafe { Node::handle(node) };
+        let align = unsafe { (*node).data.align };
+        OwnedSlice::from_slice_aligned(&handle, self, align)
+    }
+}
+
+impl<T> Deref for OwnedSlice<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.inner
+    }
+}
+
+impl<T> DerefMut for OwnedSlice<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { core::slice::from_raw_parts_mut(self.inner.as_mut_ptr(), self.inner.len()) }
+    }
+}
+
+/// A uniquely owned string with deferred collection, analogous to
+/// `Box<str>`.
+///
+/// When an `OwnedStr` is dropped, its contents are added to the drop queue
+/// of the [`Collector`] whose [`Handle`] it was allocated with, as with
+/// [`Owned`].
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, OwnedStr};
+///
+/// let collector = Collector::new();
+/// let mut name = OwnedStr::from_string(&collector.handle(), "reverb".to_string());
+/// name.make_ascii_uppercase();
+/// assert_eq!(&*name, "REVERB");
+/// ```
+///
+/// [`Collector`]: crate::Collector
+/// [`Handle`]: crate::Handle
+/// [`Owned`]: crate::Owned
+#[derive(Clone)]
+pub struct OwnedStr {
+    bytes: OwnedSlice<u8>,
+}
+
+impl OwnedStr {
+    /// Constructs a new `OwnedStr` by moving the contents of a `String` into
+    /// a new allocation.
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn from_string(handle: &Handle, string: String) -> OwnedStr {
+        OwnedStr {
+            bytes: OwnedSlice::from_vec(handle, string.into_bytes()),
+        }
+    }
+
+    /// Constructs a new `OwnedStr` by copying a string slice.
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(handle: &Handle, string: &str) -> OwnedStr {
+        OwnedStr {
+            bytes: OwnedSlice::from_slice(handle, string.as_bytes()),
+        }
+    }
+}
+
+impl Deref for OwnedStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        // Only ever constructed from valid UTF-8, and only mutated as a str.
+        unsafe { core::str::from_utf8_unchecked(&self.bytes) }
+    }
+}
+
+impl DerefMut for OwnedStr {
+    fn deref_mut(&mut self) -> &mut str {
+        unsafe { core::str::from_utf8_unchecked_mut(&mut self.bytes) }
+    }
+}
+
+/// The fixed-size part of a [`SharedSlice`] or [`OwnedSlice`]'s node, which
+/// is followed by the elements.
+#[repr(C)]
+struct SliceHeader {
+    count: AtomicUsize,
+    len: usize,
+    /// The alignment of the first element, which may exceed that of `T`.
+    align: usize,
+}
+
+/// Computes the layout of a slice node holding `len` elements aligned to
+/// `align`, along with the offset of the first element.
+fn slice_layout<T>(len: usize, align: usize) -> (Layout, usize) {
+    let elements = Layout::array::<T>(len)
+        .expect("slice too large")
+        .align_to(align)
+        .expect("invalid alignment");
+    let (layout, offset) =
+        Layout::new::<Node<SliceHeader>>().extend(elements).expect("slice too large");
+    (layout.pad_to_align(), offset)
+}
+
+unsafe fn drop_slice<T>(node: *mut NodeHeader) -> bool {
+    let header = &(*(node as *mut Node<SliceHeader>)).data;
+    let len = header.len;
+    let (layout, offset) = slice_layout::<T>(len, header.align);
+
+    let elements = (node as *mut u8).add(offset) as *mut T;
+    core::ptr::drop_in_place(core::ptr::slice_from_raw_parts_mut(elements, len));
+
+    free_raw(node, layout, core::any::type_name::<[T]>())
+}
+
+/// A reference-counted slice with deferred collection, analogous to
+/// `Arc<[T]>`.
+///
+/// The elements are stored inline in the same allocation as the reference
+/// count and the length, so unlike a `Shared<Vec<T>>`, accessing them
+/// doesn't involve a second indirection. When the last `SharedSlice` pointer
+/// to the allocation is dropped, it is added to the drop queue of the
+/// [`Collector`] whose [`Handle`] it was allocated with, as with [`Shared`].
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, SharedSlice};
+///
+/// let mut collector = Collector::new();
+/// let handle = collector.handle();
+///
+/// let table = SharedSlice::from_iter(&handle, (0..2048).map(|i| i as f32 / 2048.0));
+/// let copy = table.clone();
+/// assert_eq!(copy.len(), 2048);
+///
+/// drop((table, copy));
+/// collector.collect();
+/// assert_eq!(collector.alloc_count(), 0);
+/// ```
+///
+/// [`Collector`]: crate::Collector
+/// [`Handle`]: crate::Handle
+/// [`Shared`]: crate::Shared
+pub struct SharedSlice<T> {
+    node: NonNull<Node<SliceHeader>>,
+    phantom: PhantomData<T>,
+}
+
+unsafe impl<T: Send + Sync> Send for SharedSlice<T> {}
+unsafe impl<T: Send + Sync> Sync for SharedSlice<T> {}
+
+impl<T: RefUnwindSafe> UnwindSafe for SharedSlice<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for SharedSlice<T> {}
+impl<T> Unpin for SharedSlice<T> {}
+
+impl<T: Send + 'static> SharedSlice<T> {
+    /// Constructs a new `SharedSlice<T>` by moving the contents of a
+    /// `Vec<T>` into a new allocation.
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn from_vec(handle: &Handle, mut vec: Vec<T>) -> SharedSlice<T> {
+        unsafe {
+            let slice = SharedSlice::alloc(handle, vec.len(), align_of::<T>(), drop_slice::<T>);
+            core::ptr::copy_nonoverlapping(vec.as_ptr(), slice.as_mut_ptr(), vec.len());
+            vec.set_len(0);
+            slice
+        }
+    }
+
+    /// Constructs a new `SharedSlice` of `len` uninitialized elements,
+    /// analogous to `Arc::new_uninit_slice`. The elements can be written
+    /// through [`get_mut`] and then marked as initialized with
+    /// [`assume_init`].
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, SharedSlice};
+    /// use std::mem::MaybeUninit;
+    ///
+    /// let collector = Collector::new();
+    /// let mut samples = SharedSlice::<f32>::new_uninit(&collector.handle(), 48000);
+    /// for (i, sample) in SharedSlice::get_mut(&mut samples).unwrap().iter_mut().enumerate() {
+    ///     *sample = MaybeUninit::new(i as f32);
+    /// }
+    ///
+    /// let samples = unsafe { SharedSlice::assume_init(samples) };
+    /// assert_eq!(samples[47999], 47999.0);
+    /// ```
+    ///
+    /// [`get_mut`]: crate::SharedSlice::get_mut
+    /// [`assume_init`]: crate::SharedSlice::assume_init
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn new_uninit(handle: &Handle, len: usize) -> SharedSlice<MaybeUninit<T>> {
+        SharedSlice::new_uninit_aligned(handle, len, align_of::<T>())
+    }
+
+    /// Like [`new_uninit`], but aligns the first element to `align` bytes,
+    /// e.g. for sample buffers processed with SIMD instructions which
+    /// require aligned loads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two. An `align` smaller than the
+    /// alignment of `T` has no effect.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, SharedSlice};
+    /// use std::mem::MaybeUninit;
+    ///
+    /// let collector = Collector::new();
+    /// let mut buffer = SharedSlice::<f32>::new_uninit_aligned(&collector.handle(), 256, 64);
+    /// for sample in SharedSlice::get_mut(&mut buffer).unwrap() {
+    ///     *sample = MaybeUninit::new(0.0);
+    /// }
+    ///
+    /// let buffer = unsafe { SharedSlice::assume_init(buffer) };
+    /// assert_eq!(buffer.as_ptr() as usize % 64, 0);
+    /// ```
+    ///
+    /// [`new_uninit`]: crate::SharedSlice::new_uninit
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn new_uninit_aligned(
+        handle: &Handle,
+        len: usize,
+        align: usize,
+    ) -> SharedSlice<MaybeUninit<T>> {
+        unsafe { SharedSlice::alloc(handle, len, align, drop_slice::<MaybeUninit<T>>) }
+    }
+
+    /// Constructs a new `SharedSlice<T>` from the items of an iterator.
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_iter<I: IntoIterator<Item = T>>(handle: &Handle, iter: I) -> SharedSlice<T> {
+        SharedSlice::from_vec(handle, iter.into_iter().collect())
+    }
+}
+
+impl<T: Send + 'static> SharedSlice<MaybeUninit<T>> {
+    /// Converts a `SharedSlice<MaybeUninit<T>>` into a `SharedSlice<T>`,
+    /// analogous to `Arc::assume_init`.
+    ///
+    /// # Safety
+    ///
+    /// All elements must have been initialized.
+    pub unsafe fn assume_init(this: Self) -> SharedSlice<T> {
+        let node = this.node;
+        core::mem::forget(this);
+
+        Node::set_drop(node.as_ptr(), drop_slice::<T>);
+        SharedSlice {
+            node,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Clone + Send + 'static> SharedSlice<T> {
+    /// Constructs a new `SharedSlice<T>` by cloning the contents of a slice.
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn from_slice(handle: &Handle, slice: &[T]) -> SharedSlice<T> {
+        SharedSlice::from_vec(handle, slice.to_vec())
+    }
+
+    /// Constructs a new `SharedSlice<T>` by cloning the contents of a slice,
+    /// aligning the first element to `align` bytes as with
+    /// [`new_uninit_aligned`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two.
+    ///
+    /// [`new_uninit_aligned`]: crate::SharedSlice::new_uninit_aligned
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn from_slice_aligned(handle: &Handle, slice: &[T], align: usize) -> SharedSlice<T> {
+        let mut uninit = SharedSlice::new_uninit_aligned(handle, slice.len(), align);
+        let elements = SharedSlice::get_mut(&mut uninit).unwrap();
+        for (element, value) in elements.iter_mut().zip(slice) {
+            *element = MaybeUninit::new(value.clone());
+        }
+
+        unsafe { SharedSlice::assume_init(uninit) }
+    }
+}
+
+impl<T> SharedSlice<T> {
+    /// Allocates a `SharedSlice` with room for `len` elements, which are left
+    /// uninitialized.
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    unsafe fn alloc(
+        handle: &Handle,
+        len: usize,
+        align: usize,
+        drop: unsafe fn(*mut NodeHeader) -> bool,
+    ) -> SharedSlice<T> {
+        let (layout, _) = slice_layout::<T>(len, align);
+        let node = alloc_raw(handle, layout, core::any::type_name::<[T]>(), drop)
+            as *mut Node<SliceHeader>;
+        core::ptr::write(&mut (*node).data, SliceHeader {
+            count: AtomicUsize::new(1),
+            len,
+            align,
+        });
+
+        SharedSlice {
+            node: NonNull::new_unchecked(node),
+            phantom: PhantomData,
+        }
+    }
+
+    fn as_mut_ptr(&self) -> *mut T {
+        unsafe {
+            let header = &self.node.as_ref().data;
+            let (_, offset) = slice_layout::<T>(header.len, header.align);
+            (self.node.as_ptr() as *mut u8).add(offset) as *mut T
+        }
+    }
+
+    /// Returns a mutable reference to the elements if there are no other
+    /// extant `SharedSlice` pointers to the same allocation; otherwise returns
+    /// `None`.
+    pub fn get_mut(this: &mut Self) -> Option<&mut [T]> {
+        unsafe {
+            let header = &this.node.as_ref().data;
+            if header.count.load(Ordering::Acquire) != 1 {
+                return None;
+            }
+
+            Some(core::slice::from_raw_parts_mut(this.as_mut_ptr(), header.len))
+        }
+    }
+}
+
+impl<T> Clone for SharedSlice<T> {
+    fn clone(&self) -> Self {
+        unsafe {
+            self.node.as_ref().data.count.fetch_add(1, Ordering::Relaxed);
+        }
+
+        SharedSlice { node: self.node, phantom: PhantomData }
+    }
+}
+
+impl<T> Deref for SharedSlice<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { core::slice::from_raw_parts(self.as_mut_ptr(), self.node.as_ref().data.len) }
+    }
+}
+
+impl<T> Drop for SharedSlice<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let count = self.node.as_ref().data.count.fetch_sub(1, Ordering::Release);
+
+            if count == 1 {
+                fence(Ordering::Acquire);
+                Node::queue_drop(self.node.as_ptr());
+            }
+        }
+    }
+}
+
+/// A reference-counted string with deferred collection, analogous to
+/// `Arc<str>`.
+///
+/// The bytes are stored inline in the same allocation as the reference
+/// count and the length, as with [`SharedSlice`].
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, SharedStr};
+///
+/// let collector = Collector::new();
+/// let name = SharedStr::from_str(&collector.handle(), "Init preset");
+///
+/// // On the audio thread:
+/// let current = name.clone();
+/// assert_eq!(&*current, "Init preset");
+/// ```
+///
+/// [`SharedSlice`]: crate::SharedSlice
+#[derive(Clone)]
+pub struct SharedStr {
+    bytes: SharedSlice<u8>,
+}
+
+impl SharedStr {
+    /// Constructs a new `SharedStr` by moving the contents of a `String`
+    /// into a new allocation.
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    pub fn from_string(handle: &Handle, string: String) -> SharedStr {
+        SharedStr {
+            bytes: SharedSlice::from_vec(handle, string.into_bytes()),
+        }
+    }
+
+    /// Constructs a new `SharedStr` by copying a string slice.
+    #[cfg_attr(feature = "track-allocations", track_caller)]
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(handle: &Handle, string: &str) -> SharedStr {
+        SharedStr {
+            bytes: SharedSlice::from_slice(handle, string.as_bytes()),
+        }
+    }
+}
+
+impl Deref for SharedStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        // Only ever constructed from valid UTF-8.
+        unsafe { core::str::from_utf8_unchecked(&self.bytes) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Collector, OwnedSlice, OwnedStr, SharedSlice, SharedStr};
+
+    #[test]
+    fn owned_slice() {
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        let mut slice = OwnedSlice::from_slice(&handle, &[1, 2, 3]);
+        slice.reverse();
+        let clone = slice.clone();
+        assert_eq!(&*clone, &[3, 2, 1]);
+
+        let mut string = OwnedStr::from_str(&handle, "abc");
+        string.make_ascii_uppercase();
+        let copy = string.clone();
+        assert_eq!(&*copy, "ABC");
+
+        drop((slice, clone, string, copy, handle));
+        collector.collect();
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[test]
+    fn shared_slice() {
+        extern crate alloc;
+        use alloc::string::ToString;
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        let strings = SharedSlice::from_iter(&handle, (0..10).map(|i| i.to_string()));
+        let clone = strings.clone();
+        assert_eq!(clone[3], "3");
+
+        let empty = SharedSlice::<u64>::from_slice(&handle, &[]);
+        assert!(empty.is_empty());
+
+        let units = SharedSlice::from_vec(&handle, alloc::vec![(); 5]);
+        assert_eq!(units.len(), 5);
+
+        let a = SharedStr::from_str(&handle, "abc");
+        let b = SharedStr::from_string(&handle, "ünïcödé".to_string());
+        assert_eq!(&*a.clone(), "abc");
+        assert_eq!(b.chars().count(), 7);
+
+        drop((strings, clone, empty, units, a, b, handle));
+        collector.collect();
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[test]
+    fn aligned() {
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+
+        for &align in &[1, 16, 64, 4096] {
+            let slice = SharedSlice::from_slice_aligned(&handle, &[1u8, 2, 3], align);
+            assert_eq!(slice.as_ptr() as usize % align, 0);
+            assert_eq!(&*slice, &[1, 2, 3]);
+
+            let mut owned = OwnedSlice::from_slice_aligned(&handle, &[1u8, 2, 3], align);
+            owned[0] = 4;
+            let clone = owned.clone();
+            assert_eq!(owned.as_ptr() as usize % align, 0);
+            assert_eq!(clone.as_ptr() as usize % align, 0);
+            assert_eq!(&*clone, &[4, 2, 3]);
+
+            let empty = SharedSlice::<u64>::from_slice_aligned(&handle, &[], align);
+            assert_eq!(empty.as_ptr() as usize % align.max(8), 0);
+        }
+
+        drop(handle);
+        collector.collect();
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[test]
+    fn uninit() {
+        extern crate alloc;
+        use alloc::sync::Arc;
+        use core::mem::MaybeUninit;
+
+        let mut collector = Collector::new();
+        let handle = collector.handle();
+        let arc = Arc::new(());
+
+        let mut owned = OwnedSlice::<Arc<()>>::new_uninit(&handle, 4);
+        for element in owned.iter_mut() {
+            *element = MaybeUninit::new(arc.clone());
+        }
+        let owned = unsafe { OwnedSlice::assume_init(owned) };
+
+        let mut shared = SharedSlice::<Arc<()>>::new_uninit(&handle, 4);
+        for element in SharedSlice::get_mut(&mut shared).unwrap() {
+            *element = MaybeUninit::new(arc.clone());
+        }
+        let shared = unsafe { SharedSlice::assume_init(shared) };
+        let mut clone = shared.clone();
+        assert!(SharedSlice::get_mut(&mut clone).is_none());
+
+        let uninit = SharedSlice::<Arc<()>>::new_uninit(&handle, 4);
+        assert_eq!(Arc::strong_count(&arc), 9);
+
+        drop((owned, shared, clone, uninit, handle));
+        collector.collect();
+        assert_eq!(Arc::strong_count(&arc), 1);
+        assert!(collector.try_cleanup().is_ok());
+    }
+}
@@ -0,0 +1,330 @@
+#[cfg(feature = "track-allocations")]
+use crate::LiveAllocation;
+
+#[cfg(feature = "diagnostics")]
+use core::panic::Location;
+
+extern crate alloc;
+#[cfg(feature = "std")]
+use alloc::string::String;
+#[cfg(any(feature = "diagnostics", feature = "thread-stats"))]
+use alloc::vec::Vec;
+#[cfg(feature = "thread-stats")]
+use crate::spin::SpinLock;
+#[cfg(feature = "thread-stats")]
+use alloc::sync::Arc;
+#[cfg(feature = "thread-stats")]
+use core::cell::RefCell;
+#[cfg(feature = "thread-stats")]
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "thread-stats")]
+use std::thread::ThreadId;
+
+/// A snapshot of a [`Collector`]'s state, returned by [`Collector::stats`].
+///
+/// Unlike the human-readable `Collector::report`, `CollectorStats` is meant
+/// to be consumed by programs, e.g. for shipping collector health
+/// data in crash reports. With the `std` feature, it can be exported as JSON
+/// with [`to_json`].
+///
+/// [`Collector`]: crate::Collector
+/// [`Collector::stats`]: crate::Collector::stats
+/// [`to_json`]: crate::CollectorStats::to_json
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct CollectorStats {
+    /// The number of live [`Handle`]s, or `None` if counters are disabled.
+    ///
+    /// [`Handle`]: crate::Handle
+    pub handles: Option<usize>,
+    /// The number of live allocations, or `None` if counters are disabled.
+    pub allocations: Option<usize>,
+    /// The collection epoch (see [`Collector::collection_epoch`]).
+    ///
+    /// [`Collector::collection_epoch`]: crate::Collector::collection_epoch
+    pub epoch: usize,
+    /// The creation site of each live [`Handle`], in order of creation. Only
+    /// available with the `diagnostics` feature.
+    ///
+    /// [`Handle`]: crate::Handle
+    #[cfg(feature = "diagnostics")]
+    pub live_handles: Vec<&'static Location<'static>>,
+    /// Every live allocation, in order of address. Only available with the
+    /// `track-allocations` feature.
+    #[cfg(feature = "track-allocations")]
+    pub live_allocations: Vec<LiveAllocation>,
+}
+
+#[cfg(feature = "std")]
+impl CollectorStats {
+    /// Serializes these statistics as a JSON object. Only available with the
+    /// `std` feature.
+    ///
+    /// Counts which aren't tracked are written as `null`.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::Collector;
+    ///
+    /// let collector = Collector::new();
+    /// let json = collector.stats().to_json();
+    /// assert!(json.starts_with(r#"{"handles":0,"allocations":0,"epoch":0"#));
+    /// ```
+    pub fn to_json(&self) -> String {
+        use core::fmt::Write;
+
+        let mut json = String::new();
+        json.push_str("{\"handles\":");
+        write_count(&mut json, self.handles);
+        json.push_str(",\"allocations\":");
+        write_count(&mut json, self.allocations);
+        let _ = write!(json, ",\"epoch\":{}", self.epoch);
+
+        #[cfg(feature = "diagnostics")]
+        {
+            json.push_str(",\"live_handles\":[");
+            for (i, location) in self.live_handles.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                json.push_str("{\"file\":");
+                write_string(&mut json, location.file());
+                let _ = write!(
+                    json,
+                    ",\"line\":{},\"column\":{}}}",
+                    location.line(),
+                    location.column()
+                );
+            }
+            json.push(']');
+        }
+
+        #[cfg(feature = "track-allocations")]
+        {
+            json.push_str(",\"live_allocations\":[");
+            for (i, allocation) in self.live_allocations.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                json.push_str("{\"type\":");
+                write_string(&mut json, allocation.type_name);
+                let _ = write!(json, ",\"size\":{},\"file\":", allocation.size);
+                write_string(&mut json, allocation.location.file());
+                let _ = write!(
+                    json,
+                    ",\"line\":{},\"column\":{},\"tag\":",
+                    allocation.location.line(),
+                    allocation.location.column()
+                );
+                match allocation.tag {
+                    Some(tag) => write_string(&mut json, tag),
+                    None => json.push_str("null"),
+                }
+                json.push('}');
+            }
+            json.push(']');
+        }
+
+        json.push('}');
+        json
+    }
+}
+
+#[cfg(feature = "std")]
+fn write_count(json: &mut String, count: Option<usize>) {
+    use core::fmt::Write;
+
+    match count {
+        Some(count) => {
+            let _ = write!(json, "{}", count);
+        }
+        None => json.push_str("null"),
+    }
+}
+
+/// Writes `string` as a quoted and escaped JSON string.
+#[cfg(feature = "std")]
+#[cfg_attr(not(feature = "diagnostics"), allow(dead_code))]
+fn write_string(json: &mut String, string: &str) {
+    use core::fmt::Write;
+
+    json.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+/// The live allocations of a single type, returned by
+/// [`Collector::stats_by_type`]. Only available with the `track-allocations`
+/// feature.
+///
+/// [`Collector::stats_by_type`]: crate::Collector::stats_by_type
+#[cfg(feature = "track-allocations")]
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct TypeStats {
+    /// The name of the type, as returned by [`core::any::type_name`].
+    pub type_name: &'static str,
+    /// The number of live allocations of this type.
+    pub count: usize,
+    /// The total size of those allocations in bytes, including node headers.
+    pub bytes: usize,
+}
+
+/// The number of allocations made and retired by a single thread, returned
+/// by [`Collector::thread_stats`]. Only available with the `thread-stats`
+/// feature.
+///
+/// [`Collector::thread_stats`]: crate::Collector::thread_stats
+#[cfg(feature = "thread-stats")]
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ThreadStats {
+    /// The thread's ID.
+    pub id: ThreadId,
+    /// The thread's name, if it has one.
+    pub name: Option<String>,
+    /// The number of allocations made on this thread.
+    pub allocations: usize,
+    /// The number of allocations queued for dropping on this thread.
+    pub retired: usize,
+}
+
+/// One thread's counts. Shared between the [`ThreadRegistry`] and the
+/// thread's own cache, so that recording a count takes neither a lock nor an
+/// allocation once the thread is registered.
+#[cfg(feature = "thread-stats")]
+struct ThreadSlot {
+    id: ThreadId,
+    name: Option<String>,
+    allocations: AtomicUsize,
+    retired: AtomicUsize,
+}
+
+/// Source of [`ThreadRegistry`] IDs, so that a registry allocated at the
+/// address of a dropped one is not mistaken for it in a thread's cache.
+#[cfg(feature = "thread-stats")]
+static NEXT_REGISTRY: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "thread-stats")]
+std::thread_local! {
+    // The slots this thread has registered, keyed by registry ID.
+    static SLOTS: RefCell<Vec<(usize, Arc<ThreadSlot>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Per-thread counts, in the order in which threads were first registered.
+#[cfg(feature = "thread-stats")]
+pub(crate) struct ThreadRegistry {
+    id: AtomicUsize,
+    // Only locked to register a thread or take a snapshot.
+    threads: SpinLock<Vec<Arc<ThreadSlot>>>,
+}
+
+#[cfg(feature = "thread-stats")]
+impl ThreadRegistry {
+    pub(crate) fn new() -> ThreadRegistry {
+        ThreadRegistry {
+            id: AtomicUsize::new(NEXT_REGISTRY.fetch_add(1, Ordering::Relaxed)),
+            threads: SpinLock::new(Vec::new()),
+        }
+    }
+
+    /// Calls `f` with the current thread's slot, registering the thread
+    /// first if necessary. Does nothing if the thread's cache has already
+    /// been destroyed, i.e. while the thread is exiting.
+    fn with_current(&self, f: impl FnOnce(&ThreadSlot)) {
+        let id = self.id.load(Ordering::Relaxed);
+        let _ = SLOTS.try_with(|slots| {
+            let mut slots = slots.borrow_mut();
+            if let Some((_, slot)) = slots.iter().find(|(slot_id, _)| *slot_id == id) {
+                f(slot);
+                return;
+            }
+
+            // Forget the slots of registries which have since been dropped.
+            slots.retain(|(_, slot)| Arc::strong_count(slot) > 1);
+
+            let thread = std::thread::current();
+            let slot = Arc::new(ThreadSlot {
+                id: thread.id(),
+                name: thread.name().map(String::from),
+                allocations: AtomicUsize::new(0),
+                retired: AtomicUsize::new(0),
+            });
+            self.threads.lock().push(slot.clone());
+            f(&slot);
+            slots.push((id, slot));
+        });
+    }
+
+    pub(crate) fn register(&self) {
+        self.with_current(|_| {});
+    }
+
+    pub(crate) fn record_alloc(&self) {
+        self.with_current(|slot| {
+            slot.allocations.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+
+    pub(crate) fn record_retire(&self, count: usize) {
+        self.with_current(|slot| {
+            slot.retired.fetch_add(count, Ordering::Relaxed);
+        });
+    }
+
+    /// Forgets all registered threads. Must not race with recording.
+    pub(crate) fn clear(&self) {
+        self.id.store(NEXT_REGISTRY.fetch_add(1, Ordering::Relaxed), Ordering::Relaxed);
+        self.threads.lock().clear();
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<ThreadStats> {
+        self.threads
+            .lock()
+            .iter()
+            .map(|slot| ThreadStats {
+                id: slot.id,
+                name: slot.name.clone(),
+                allocations: slot.allocations.load(Ordering::Relaxed),
+                retired: slot.retired.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json() {
+        let stats = CollectorStats {
+            handles: Some(2),
+            allocations: None,
+            epoch: 7,
+            #[cfg(feature = "diagnostics")]
+            live_handles: Vec::new(),
+            #[cfg(feature = "track-allocations")]
+            live_allocations: Vec::new(),
+        };
+
+        let json = stats.to_json();
+        assert!(json.starts_with(r#"{"handles":2,"allocations":null,"epoch":7"#));
+
+        let mut string = String::new();
+        write_string(&mut string, "a\"b\\c\n\u{1}");
+        assert_eq!(string, r#""a\"b\\c\n\u0001""#);
+    }
+}
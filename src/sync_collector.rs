@@ -0,0 +1,167 @@
+use crate::{Collector, Handle};
+
+use core::cell::UnsafeCell;
+use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// A [`Collector`] which can be shared between threads, any of which may
+/// trigger a collection through a shared reference.
+///
+/// Only one thread collects at a time. [`try_collect`] never waits: if
+/// another thread is already collecting, it returns false right away, so
+/// several non-real-time threads (e.g. the workers of a thread pool) can
+/// each call it periodically without holding up one another. Garbage queued
+/// while a pass is in progress may be left for the next one.
+///
+/// # Examples
+/// ```
+/// use basedrop::{Collector, Owned, SyncCollector};
+/// use std::sync::Arc;
+///
+/// let collector = Arc::new(SyncCollector::new(Collector::new()));
+///
+/// let workers: Vec<_> = (0..4)
+///     .map(|i| {
+///         let collector = collector.clone();
+///         std::thread::spawn(move || {
+///             drop(Owned::new(&collector.handle(), i));
+///             collector.try_collect();
+///         })
+///     })
+///     .collect();
+/// for worker in workers {
+///     worker.join().unwrap();
+/// }
+///
+/// let mut collector = Arc::try_unwrap(collector).ok().unwrap().into_inner();
+/// collector.collect();
+/// assert_eq!(collector.alloc_count(), 0);
+/// ```
+///
+/// [`Collector`]: crate::Collector
+/// [`try_collect`]: crate::SyncCollector::try_collect
+pub struct SyncCollector {
+    handle: Handle,
+    collecting: AtomicBool,
+    collector: UnsafeCell<Collector>,
+}
+
+// The `Collector` is only accessed by the thread which holds the
+// `collecting` flag.
+unsafe impl Sync for SyncCollector {}
+
+impl UnwindSafe for SyncCollector {}
+impl RefUnwindSafe for SyncCollector {}
+
+/// Clears the `collecting` flag, even if a destructor panics.
+struct Release<'a>(&'a AtomicBool);
+
+impl Drop for Release<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+impl SyncCollector {
+    /// Wraps `collector` so that it can be shared between threads.
+    pub fn new(collector: Collector) -> SyncCollector {
+        SyncCollector {
+            handle: collector.handle(),
+            collecting: AtomicBool::new(false),
+            collector: UnsafeCell::new(collector),
+        }
+    }
+
+    /// Gets a [`Handle`] to the [`Collector`].
+    ///
+    /// [`Handle`]: crate::Handle
+    /// [`Collector`]: crate::Collector
+    pub fn handle(&self) -> Handle {
+        self.handle.clone()
+    }
+
+    /// Drops all of the garbage in the queue, as with [`Collector::collect`],
+    /// unless another thread is already collecting. Returns whether a
+    /// collection took place.
+    ///
+    /// [`Collector::collect`]: crate::Collector::collect
+    pub fn try_collect(&self) -> bool {
+        self.try_with(Collector::collect).is_some()
+    }
+
+    /// Calls `f` with exclusive access to the [`Collector`], unless another
+    /// thread currently has it, in which case `None` is returned. This allows
+    /// any of the [`Collector`]'s methods to be used, e.g. [`collect_n`] to
+    /// bound the time spent collecting.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned, SyncCollector};
+    ///
+    /// let collector = SyncCollector::new(Collector::new());
+    /// drop(Owned::new(&collector.handle(), 1));
+    /// drop(Owned::new(&collector.handle(), 2));
+    ///
+    /// assert_eq!(collector.try_with(|collector| collector.collect_n(1)), Some(1));
+    /// ```
+    ///
+    /// [`Collector`]: crate::Collector
+    /// [`collect_n`]: crate::Collector::collect_n
+    pub fn try_with<R, F: FnOnce(&mut Collector) -> R>(&self, f: F) -> Option<R> {
+        if self
+            .collecting
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return None;
+        }
+
+        let _release = Release(&self.collecting);
+        Some(f(unsafe { &mut *self.collector.get() }))
+    }
+
+    /// Unwraps the [`Collector`], e.g. in order to clean it up.
+    ///
+    /// [`Collector`]: crate::Collector
+    pub fn into_inner(self) -> Collector {
+        self.collector.into_inner()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::{Collector, Owned, SyncCollector};
+
+    extern crate alloc;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn sync_collector() {
+        let collector = Arc::new(SyncCollector::new(Collector::new()));
+
+        let workers: Vec<_> = (0..8)
+            .map(|_| {
+                let collector = collector.clone();
+                std::thread::spawn(move || {
+                    let handle = collector.handle();
+                    for i in 0..1000 {
+                        drop(Owned::new(&handle, i));
+                        collector.try_collect();
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        // Exclusive access is refused while another caller holds it.
+        collector.try_with(|_| assert!(!collector.try_collect()));
+
+        assert!(collector.try_collect());
+        let collector = Arc::try_unwrap(collector).ok().unwrap().into_inner();
+        assert_eq!(collector.alloc_count(), 0);
+        assert!(collector.try_cleanup().is_ok());
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{Collector, Handle};
+
+use core::time::Duration;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+
+impl Collector {
+    /// Moves the `Collector` to a new background thread which calls
+    /// [`collect`] every `period`. Only available with the `std` feature.
+    ///
+    /// The thread runs until the returned [`CollectorThread`] is stopped or
+    /// dropped, at which point it collects one last time, so that everything
+    /// queued up to that point is dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// use basedrop::{Collector, Owned};
+    /// use std::time::Duration;
+    ///
+    /// let collector = Collector::new();
+    /// let thread = collector.spawn_thread(Duration::from_millis(10));
+    ///
+    /// let buffer = Owned::new(&thread.handle(), vec![0.0f32; 4096]);
+    /// drop(buffer);
+    ///
+    /// let collector = thread.stop();
+    /// assert_eq!(collector.alloc_count(), 0);
+    /// ```
+    ///
+    /// [`collect`]: crate::Collector::collect
+    /// [`CollectorThread`]: crate::CollectorThread
+    pub fn spawn_thread(self, period: Duration) -> CollectorThread {
+        let handle = self.handle();
+        let (sender, receiver) = mpsc::channel::<()>();
+
+        let mut collector = self;
+        let thread = std::thread::Builder::new()
+            .name("basedrop-collector".into())
+            .spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(period) {
+                    collector.collect();
+                }
+
+                collector.collect();
+                collector
+            })
+            .expect("failed to spawn collector thread");
+
+        CollectorThread {
+            handle: Some(handle),
+            stop: Some(sender),
+            thread: Some(thread),
+        }
+    }
+}
+
+/// A [`Collector`] running on a background thread, returned by
+/// [`Collector::spawn_thread`].
+///
+/// Dropping a `CollectorThread` stops the thread after a final collection
+/// and then drops the [`Collector`]. Use [`stop`] to get the [`Collector`]
+/// back instead.
+///
+/// [`Collector`]: crate::Collector
+/// [`Collector::spawn_thread`]: crate::Collector::spawn_thread
+/// [`stop`]: crate::CollectorThread::stop
+pub struct CollectorThread {
+    // Released before the thread is joined, so that it doesn't keep the
+    // `Collector` from being cleaned up.
+    handle: Option<Handle>,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<Collector>>,
+}
+
+impl CollectorThread {
+    /// Gets a [`Handle`] to the [`Collector`] running on this thread.
+    ///
+    /// [`Handle`]: crate::Handle
+    /// [`Collector`]: crate::Collector
+    pub fn handle(&self) -> Handle {
+        self.handle.clone().unwrap()
+    }
+
+    /// Stops the thread after a final collection and returns the
+    /// [`Collector`]. If a destructor panicked on the thread, the panic is
+    /// propagated.
+    ///
+    /// [`Collector`]: crate::Collector
+    pub fn stop(mut self) -> Collector {
+        match self.join() {
+            Ok(collector) => collector,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+
+    fn join(&mut self) -> std::thread::Result<Collector> {
+        self.handle.take();
+        // Dropping the sender wakes the thread up immediately.
+        self.stop.take();
+        self.thread.take().unwrap().join()
+    }
+}
+
+impl Drop for CollectorThread {
+    fn drop(&mut self) {
+        if self.thread.is_some() {
+            let _ = self.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Collector, DropPolicy, Owned};
+
+    use core::time::Duration;
+
+    #[test]
+    fn spawn_thread() {
+        let collector = Collector::new();
+        let handle = collector.handle();
+        let thread = collector.spawn_thread(Duration::from_millis(1));
+
+        drop(Owned::new(&handle, 1));
+        while thread.handle().collection_epoch() == 0 {
+            std::thread::yield_now();
+        }
+
+        // Anything queued before stopping is collected.
+        drop(Owned::new(&thread.handle(), 2));
+        let collector = thread.stop();
+        assert_eq!(collector.alloc_count(), 0);
+
+        drop(handle);
+        assert!(collector.try_cleanup().is_ok());
+    }
+
+    #[test]
+    fn drop_thread() {
+        // Dropping the thread must leave nothing that would keep the
+        // collector from being cleaned up.
+        let collector = Collector::builder().drop_policy(DropPolicy::Panic).build();
+        let thread = collector.spawn_thread(Duration::from_millis(1));
+        drop(Owned::new(&thread.handle(), 1));
+        drop(thread);
+    }
+}